@@ -1,14 +1,31 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Default, Debug, Parser)]
 #[command(name = crate::APP_NAME)]
 #[command(flatten_help = true)]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(after_help = "Exit codes:
+  0  Success
+  1  An error occurred
+  2  Invalid arguments
+  3  Not running in a terminal (see --print)
+  4  Not inside a git repository")]
 pub struct Args {
     #[command(subcommand)]
     pub command: Option<Commands>,
-    /// Print one frame and exit. Useful for debugging.
-    #[clap(long, action)]
+    /// Open at a commit, branch or tag, with the status underneath
+    #[clap(value_name = "REV")]
+    pub revision: Option<String>,
+    /// Run as if gitu was started in <PATH>, rather than the current directory
+    #[clap(short = 'C', long = "dir", value_name = "PATH")]
+    pub dir: Option<PathBuf>,
+    /// Print the view once, with colors, and exit. Useful in shell prompts and CI.
+    #[clap(long, global = true, action)]
     pub print: bool,
+    /// Start at the list of recently opened repositories, to switch to one of them
+    #[clap(long, action)]
+    pub recent: bool,
     /// Enable logging to 'gitu.log'
     #[clap(long, action)]
     pub log: bool,
@@ -20,5 +37,14 @@ pub struct Args {
 
 #[derive(Debug, Subcommand)]
 pub enum Commands {
+    /// Show the status, which is what's shown by default
+    Status,
+    /// Show a commit
     Show { reference: String },
+    /// Show the log of HEAD, or of another revision
+    Log { reference: Option<String> },
+    /// Blame a file
+    Blame { path: PathBuf },
+    /// Show the changes in a range like 'main..topic', or since a revision
+    Diff { range: String },
 }
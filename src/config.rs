@@ -1,16 +1,107 @@
-use crate::{Res, APP_NAME};
+use crate::{images::ImageProtocol, Res, APP_NAME};
 use figment::{
     providers::{Format, Toml},
     Figment,
 };
 use ratatui::style::{Color, Modifier, Style};
 use serde::Deserialize;
+use std::path::PathBuf;
 
 const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
 
 #[derive(Default, Debug, Deserialize)]
 pub struct Config {
+    pub general: GeneralConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub forge: ForgeConfig,
     pub style: StyleConfig,
+    /// Where recently opened repositories are remembered. Not kept when unset, like in tests.
+    #[serde(skip)]
+    pub recent_repos_file: Option<PathBuf>,
+    /// Where the screens left open in each repository are kept, like `recent_repos_file`.
+    #[serde(skip)]
+    pub sessions_dir: Option<PathBuf>,
+    /// How the terminal shows images, if it does and `inline_images` is on. Not in tests.
+    #[serde(skip)]
+    pub image_protocol: Option<ImageProtocol>,
+}
+
+#[derive(Default, Debug, Deserialize)]
+pub struct GeneralConfig {
+    pub background_jobs: bool,
+    pub refresh_on_file_change: bool,
+    /// Scans for changes where there are no file events, rather than not refreshing at all.
+    /// Does so when not set, if the OS has no file events gitu knows of.
+    #[serde(default)]
+    pub poll_for_file_changes: Option<bool>,
+    pub snapshots: bool,
+    #[serde(default)]
+    pub background_fetch: BackgroundFetchConfig,
+    #[serde(default)]
+    pub trash: TrashConfig,
+    /// Marks what state things are in with text and emphasis, not by color alone.
+    #[serde(default)]
+    pub accessible: bool,
+    #[serde(default)]
+    pub inline_images: bool,
+    #[serde(default)]
+    pub restore_session: bool,
+    /// Files in status sections are grouped in a section per directory.
+    #[serde(default)]
+    pub status_tree: bool,
+    /// `conventional`, or a command given the file with the message. Not linted when unset.
+    #[serde(default)]
+    pub commit_lint: Option<String>,
+    /// Falls back to git's `status.showUntrackedFiles` when not set.
+    #[serde(default)]
+    pub untracked_files: Option<UntrackedFiles>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UntrackedFiles {
+    /// Don't scan for untracked files until asked to.
+    No,
+    /// Show untracked directories, without looking inside them.
+    Normal,
+    /// Show every untracked file.
+    All,
+}
+
+#[derive(Default, Debug, Deserialize)]
+pub struct BackgroundFetchConfig {
+    #[serde(default)]
+    pub interval: u64,
+    #[serde(default)]
+    pub remotes: Vec<String>,
+}
+
+#[derive(Default, Debug, Deserialize)]
+pub struct TrashConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub retention_days: u64,
+}
+
+#[derive(Default, Debug, Deserialize)]
+pub struct HooksConfig {
+    #[serde(default)]
+    pub after_commit: Vec<String>,
+    #[serde(default)]
+    pub after_push: Vec<String>,
+    #[serde(default)]
+    pub on_refresh: Vec<String>,
+}
+
+#[derive(Default, Clone, Debug, Deserialize)]
+pub struct ForgeConfig {
+    #[serde(default)]
+    pub github_token: Option<String>,
+    #[serde(default)]
+    pub gitlab_token: Option<String>,
 }
 
 #[derive(Default, Debug, Deserialize)]
@@ -34,6 +125,68 @@ pub struct StyleConfig {
 
     pub command: StyleConfigEntry,
     pub hotkey: StyleConfigEntry,
+    pub error: StyleConfigEntry,
+
+    #[serde(default)]
+    pub blame: BlameHeatConfig,
+    #[serde(default)]
+    pub ci: CiStyleConfig,
+
+    /// Leave out all colors, also turned on by the `NO_COLOR` environment variable.
+    #[serde(default)]
+    pub monochrome: bool,
+}
+
+impl StyleConfig {
+    fn entries_mut(&mut self) -> [&mut StyleConfigEntry; 23] {
+        [
+            &mut self.section_header,
+            &mut self.file_header,
+            &mut self.hunk_header,
+            &mut self.line_added,
+            &mut self.line_removed,
+            &mut self.line_highlight.changed,
+            &mut self.line_highlight.unchanged,
+            &mut self.selection_line,
+            &mut self.selection_bar,
+            &mut self.selection_area,
+            &mut self.hash,
+            &mut self.branch,
+            &mut self.remote,
+            &mut self.tag,
+            &mut self.command,
+            &mut self.hotkey,
+            &mut self.error,
+            &mut self.blame.month,
+            &mut self.blame.year,
+            &mut self.blame.older,
+            &mut self.ci.success,
+            &mut self.ci.failure,
+            &mut self.ci.pending,
+        ]
+    }
+}
+
+/// Styles of blamed lines by how long ago they were changed
+#[derive(Default, Debug, Deserialize)]
+pub struct BlameHeatConfig {
+    #[serde(default)]
+    pub month: StyleConfigEntry,
+    #[serde(default)]
+    pub year: StyleConfigEntry,
+    #[serde(default)]
+    pub older: StyleConfigEntry,
+}
+
+/// Styles of the marks next to commits, by how their CI went
+#[derive(Default, Debug, Deserialize)]
+pub struct CiStyleConfig {
+    #[serde(default)]
+    pub success: StyleConfigEntry,
+    #[serde(default)]
+    pub failure: StyleConfigEntry,
+    #[serde(default)]
+    pub pending: StyleConfigEntry,
 }
 
 #[derive(Default, Debug, Deserialize)]
@@ -54,6 +207,16 @@ pub struct StyleConfigEntry {
     mods: Option<Modifier>,
 }
 
+impl StyleConfigEntry {
+    fn add_mods(&mut self, mods: Modifier) {
+        self.mods = Some(self.mods.unwrap_or(Modifier::empty()) | mods);
+    }
+
+    fn remove_mods(&mut self, mods: Modifier) {
+        self.mods = self.mods.map(|current| current - mods);
+    }
+}
+
 impl From<&StyleConfigEntry> for Style {
     fn from(val: &StyleConfigEntry) -> Self {
         Style {
@@ -66,16 +229,61 @@ impl From<&StyleConfigEntry> for Style {
     }
 }
 
+impl Config {
+    /// Makes up for colors that are left out, or that can't be told apart by everyone.
+    /// What's dimmed is easy to miss, so whatever matters is emphasized instead.
+    pub fn adapt_style(&mut self) {
+        let style = &mut self.style;
+
+        if style.monochrome {
+            for entry in style.entries_mut() {
+                entry.fg = None;
+                entry.bg = None;
+            }
+        }
+
+        if self.general.accessible || style.monochrome {
+            style.section_header.add_mods(Modifier::BOLD);
+            style
+                .selection_line
+                .add_mods(Modifier::BOLD | Modifier::UNDERLINED);
+            style.selection_bar.remove_mods(Modifier::DIM);
+            style
+                .line_highlight
+                .changed
+                .add_mods(Modifier::BOLD | Modifier::UNDERLINED);
+            style.line_highlight.unchanged.remove_mods(Modifier::DIM);
+            style.blame.month.add_mods(Modifier::BOLD);
+            style.blame.year.add_mods(Modifier::UNDERLINED);
+            style.ci.failure.add_mods(Modifier::BOLD);
+        }
+    }
+}
+
 pub(crate) fn init_config() -> Res<Config> {
-    let config = if let Some(app_dirs) = directories::ProjectDirs::from("", "", APP_NAME) {
-        Figment::new()
+    let mut config = if let Some(app_dirs) = directories::ProjectDirs::from("", "", APP_NAME) {
+        let mut config: Config = Figment::new()
             .merge(Toml::string(DEFAULT_CONFIG))
             .merge(Toml::file(app_dirs.config_dir().join("config.toml")))
-            .extract()?
+            .extract()?;
+
+        config.recent_repos_file = Some(app_dirs.data_dir().join("recent_repos"));
+        config.sessions_dir = Some(app_dirs.data_dir().join("sessions"));
+        config
     } else {
         Config::default()
     };
 
+    // https://no-color.org
+    if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        config.style.monochrome = true;
+    }
+    config.adapt_style();
+
+    if config.general.inline_images {
+        config.image_protocol = ImageProtocol::detect();
+    }
+
     Ok(config)
 }
 
@@ -91,7 +299,7 @@ mod tests {
         providers::{Format, Toml},
         Figment,
     };
-    use ratatui::style::Color;
+    use ratatui::style::{Color, Modifier};
 
     use super::{Config, DEFAULT_CONFIG};
 
@@ -111,4 +319,27 @@ mod tests {
         assert_eq!(config.style.line_added.bg, Some(Color::LightGreen));
         assert_eq!(config.style.line_added.fg, Some(Color::Green));
     }
+
+    #[test]
+    fn monochrome_leaves_out_colors() {
+        let mut config: Config = Figment::new()
+            .merge(Toml::string(DEFAULT_CONFIG))
+            .extract()
+            .unwrap();
+        config.style.monochrome = true;
+        config.adapt_style();
+
+        assert_eq!(config.style.line_added.fg, None);
+        assert_eq!(config.style.selection_area.bg, None);
+        assert_eq!(
+            config.style.line_highlight.unchanged.mods,
+            Some(Modifier::empty())
+        );
+        assert!(config
+            .style
+            .selection_line
+            .mods
+            .unwrap()
+            .contains(Modifier::UNDERLINED));
+    }
 }
@@ -0,0 +1,536 @@
+use crate::{config::ForgeConfig, Res};
+use git2::Repository;
+use serde_json::Value;
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ForgeKind {
+    GitHub,
+    GitLab,
+    Bitbucket,
+}
+
+/// Where a repository is hosted, as derived from the url of one of its remotes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Forge {
+    pub(crate) kind: ForgeKind,
+    pub(crate) host: String,
+    /// Such as `owner/repo`
+    pub(crate) path: String,
+}
+
+impl Forge {
+    /// The forge hosting the remote given by `remote_name`.
+    pub(crate) fn of_repo(repo: &Repository) -> Res<Self> {
+        let remote = repo.find_remote(&remote_name(repo)?)?;
+        let url = remote.url().ok_or("Remote url is not valid utf-8")?;
+        Self::from_url(url).ok_or_else(|| format!("Don't know how to browse {}", url).into())
+    }
+
+    pub(crate) fn from_url(url: &str) -> Option<Self> {
+        let (host, path) = if let Some((_scheme, rest)) = url.split_once("://") {
+            // https://host/owner/repo or ssh://git@host:22/owner/repo
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit('@').next()?;
+            (host.split(':').next()?, path)
+        } else {
+            // scp-like git@host:owner/repo
+            let (authority, path) = url.split_once(':')?;
+            (authority.rsplit('@').next()?, path)
+        };
+
+        let kind = if host.contains("github") {
+            ForgeKind::GitHub
+        } else if host.contains("gitlab") {
+            ForgeKind::GitLab
+        } else if host.contains("bitbucket") {
+            ForgeKind::Bitbucket
+        } else {
+            return None;
+        };
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+
+        Some(Self {
+            kind,
+            host: host.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    fn base(&self) -> String {
+        format!("https://{}/{}", self.host, self.path)
+    }
+
+    pub(crate) fn commit_url(&self, hash: &str) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("{}/commit/{}", self.base(), hash),
+            ForgeKind::GitLab => format!("{}/-/commit/{}", self.base(), hash),
+            ForgeKind::Bitbucket => format!("{}/commits/{}", self.base(), hash),
+        }
+    }
+
+    pub(crate) fn branch_url(&self, branch: &str) -> String {
+        match self.kind {
+            ForgeKind::GitHub => format!("{}/tree/{}", self.base(), branch),
+            ForgeKind::GitLab => format!("{}/-/tree/{}", self.base(), branch),
+            ForgeKind::Bitbucket => format!("{}/branch/{}", self.base(), branch),
+        }
+    }
+
+    pub(crate) fn file_url(&self, reference: &str, file: &Path, line: Option<u32>) -> String {
+        let file = file.to_string_lossy();
+        let url = match self.kind {
+            ForgeKind::GitHub => format!("{}/blob/{}/{}", self.base(), reference, file),
+            ForgeKind::GitLab => format!("{}/-/blob/{}/{}", self.base(), reference, file),
+            ForgeKind::Bitbucket => format!("{}/src/{}/{}", self.base(), reference, file),
+        };
+
+        match (line, self.kind) {
+            (None, _) => url,
+            (Some(line), ForgeKind::Bitbucket) => format!("{}#lines-{}", url, line),
+            (Some(line), _) => format!("{}#L{}", url, line),
+        }
+    }
+}
+
+/// `origin`, or the first remote if there's no `origin`.
+pub(crate) fn remote_name(repo: &Repository) -> Res<String> {
+    let remotes = repo.remotes()?;
+    let name = remotes
+        .iter()
+        .flatten()
+        .find(|name| *name == "origin")
+        .or_else(|| remotes.iter().flatten().next())
+        .ok_or("Repository has no remote")?;
+
+    Ok(name.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PullRequest {
+    pub(crate) number: u64,
+    pub(crate) title: String,
+    pub(crate) author: String,
+    pub(crate) branch: String,
+    pub(crate) head: String,
+    pub(crate) url: String,
+    pub(crate) description: String,
+    pub(crate) draft: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Check {
+    pub(crate) name: String,
+    /// Such as `success`, `failure` or `running`
+    pub(crate) status: String,
+    /// Where the run can be seen
+    pub(crate) url: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CiState {
+    Success,
+    Failure,
+    Pending,
+}
+
+/// How the checks of a commit went, all taken together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CiStatus {
+    pub(crate) state: CiState,
+    /// The first run that failed, or the first one if none did
+    pub(crate) url: String,
+}
+
+impl CiStatus {
+    /// Any check failing fails the commit, it passes once they all have. None if there are no checks.
+    pub(crate) fn of_checks(checks: &[Check]) -> Option<Self> {
+        let state_of = |check: &Check| match check.status.as_str() {
+            "success" | "neutral" | "skipped" | "manual" => CiState::Success,
+            "failure" | "failed" | "cancelled" | "canceled" | "timed_out" | "action_required"
+            | "startup_failure" => CiState::Failure,
+            _ => CiState::Pending,
+        };
+
+        let failed = checks
+            .iter()
+            .find(|check| state_of(check) == CiState::Failure);
+        let state = if failed.is_some() {
+            CiState::Failure
+        } else if checks
+            .iter()
+            .any(|check| state_of(check) == CiState::Pending)
+        {
+            CiState::Pending
+        } else {
+            CiState::Success
+        };
+
+        Some(Self {
+            state,
+            url: failed.or(checks.first())?.url.clone(),
+        })
+    }
+}
+
+impl Forge {
+    /// Open pull requests (merge requests on GitLab).
+    pub(crate) fn pull_requests(&self, config: &ForgeConfig, dir: &Path) -> Res<Vec<PullRequest>> {
+        let endpoint = match self.kind {
+            ForgeKind::GitHub => format!("repos/{}/pulls?state=open&per_page=100", self.path),
+            ForgeKind::GitLab => format!(
+                "projects/{}/merge_requests?state=opened&per_page=100",
+                self.path.replace('/', "%2F")
+            ),
+            ForgeKind::Bitbucket => {
+                return Err("Pull requests on Bitbucket aren't supported".into())
+            }
+        };
+
+        parse_pull_requests(self.kind, &self.api(config, dir, &endpoint)?)
+    }
+
+    /// Checks or pipelines that ran for a pull request.
+    pub(crate) fn checks(
+        &self,
+        config: &ForgeConfig,
+        dir: &Path,
+        pull_request: &PullRequest,
+    ) -> Res<Vec<Check>> {
+        let endpoint = match self.kind {
+            ForgeKind::GitHub => return self.commit_checks(config, dir, &pull_request.head),
+            ForgeKind::GitLab => format!(
+                "projects/{}/merge_requests/{}/pipelines",
+                self.path.replace('/', "%2F"),
+                pull_request.number
+            ),
+            ForgeKind::Bitbucket => return Ok(vec![]),
+        };
+
+        Ok(parse_checks(self.kind, &self.api(config, dir, &endpoint)?))
+    }
+
+    /// Checks or the latest pipeline that ran for a commit.
+    pub(crate) fn commit_checks(
+        &self,
+        config: &ForgeConfig,
+        dir: &Path,
+        hash: &str,
+    ) -> Res<Vec<Check>> {
+        let endpoint = match self.kind {
+            ForgeKind::GitHub => format!("repos/{}/commits/{}/check-runs", self.path, hash),
+            ForgeKind::GitLab => format!(
+                "projects/{}/pipelines?sha={}",
+                self.path.replace('/', "%2F"),
+                hash
+            ),
+            ForgeKind::Bitbucket => return Ok(vec![]),
+        };
+
+        Ok(parse_checks(self.kind, &self.api(config, dir, &endpoint)?))
+    }
+
+    /// The ref a pull request's head can be fetched from.
+    pub(crate) fn pull_request_ref(&self, number: u64) -> String {
+        match self.kind {
+            ForgeKind::GitHub | ForgeKind::Bitbucket => format!("pull/{}/head", number),
+            ForgeKind::GitLab => format!("refs/merge-requests/{}/head", number),
+        }
+    }
+
+    /// Calls the REST API with `curl` if there's a token configured, and otherwise through
+    /// the forge's command line tool, which handles authentication by itself.
+    /// The token is handed to `curl` through stdin, arguments are there for anyone to see.
+    fn api(&self, config: &ForgeConfig, dir: &Path, endpoint: &str) -> Res<Value> {
+        let (mut cmd, stdin) = match (self.kind, &config.github_token, &config.gitlab_token) {
+            (ForgeKind::GitHub, Some(token), _) => {
+                let api = if self.host == "github.com" {
+                    "https://api.github.com".to_string()
+                } else {
+                    format!("https://{}/api/v3", self.host)
+                };
+
+                (
+                    curl_cmd(&api, endpoint),
+                    Some(format!("Authorization: Bearer {}", token)),
+                )
+            }
+            (ForgeKind::GitLab, _, Some(token)) => (
+                curl_cmd(&format!("https://{}/api/v4", self.host), endpoint),
+                Some(format!("PRIVATE-TOKEN: {}", token)),
+            ),
+            (ForgeKind::GitHub, None, _) => {
+                let mut cmd = Command::new("gh");
+                cmd.args(["api", "--hostname", &self.host, endpoint]);
+                (cmd, None)
+            }
+            _ => {
+                let mut cmd = Command::new("glab");
+                cmd.args(["api", endpoint]);
+                (cmd, None)
+            }
+        };
+
+        cmd.current_dir(dir);
+        cmd.stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        });
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+
+        let program = cmd.get_program().to_string_lossy().to_string();
+        let mut child = cmd.spawn().map_err(|err| {
+            format!(
+                "Couldn't run {}: {} (install it, or configure a forge token)",
+                program, err
+            )
+        })?;
+
+        if let (Some(header), Some(mut child_stdin)) = (stdin, child.stdin.take()) {
+            // Dropped right after, for curl to see the end of it
+            child_stdin.write_all(format!("{}\n", header).as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "{} failed: {}",
+                program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+}
+
+/// Headers are read from stdin
+fn curl_cmd(api: &str, endpoint: &str) -> Command {
+    let mut cmd = Command::new("curl");
+    cmd.args(["--silent", "--show-error", "--fail", "--header", "@-"]);
+    cmd.arg(format!("{}/{}", api, endpoint));
+    cmd
+}
+
+fn parse_pull_requests(kind: ForgeKind, json: &Value) -> Res<Vec<PullRequest>> {
+    let str_at = |value: &Value, pointer: &str| {
+        value
+            .pointer(pointer)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    json.as_array()
+        .ok_or("Unexpected response from forge")?
+        .iter()
+        .map(|pr| {
+            let (number, author, branch, head, url, description) = match kind {
+                ForgeKind::GitLab => (
+                    "/iid",
+                    "/author/username",
+                    "/source_branch",
+                    "/sha",
+                    "/web_url",
+                    "/description",
+                ),
+                _ => (
+                    "/number",
+                    "/user/login",
+                    "/head/ref",
+                    "/head/sha",
+                    "/html_url",
+                    "/body",
+                ),
+            };
+
+            Ok(PullRequest {
+                number: pr
+                    .pointer(number)
+                    .and_then(Value::as_u64)
+                    .ok_or("Pull request without a number")?,
+                title: str_at(pr, "/title"),
+                author: str_at(pr, author),
+                branch: str_at(pr, branch),
+                head: str_at(pr, head),
+                url: str_at(pr, url),
+                description: str_at(pr, description),
+                draft: pr.get("draft").and_then(Value::as_bool).unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+fn parse_checks(kind: ForgeKind, json: &Value) -> Vec<Check> {
+    match kind {
+        ForgeKind::GitHub => json
+            .get("check_runs")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|run| Check {
+                name: run["name"].as_str().unwrap_or_default().to_string(),
+                status: run["conclusion"]
+                    .as_str()
+                    .or(run["status"].as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                url: run["html_url"].as_str().unwrap_or_default().to_string(),
+            })
+            .collect(),
+        // Only the latest pipeline is of interest
+        _ => json
+            .as_array()
+            .and_then(|pipelines| pipelines.first())
+            .map(|pipeline| Check {
+                name: format!("pipeline {}", pipeline["id"]),
+                status: pipeline["status"].as_str().unwrap_or_default().to_string(),
+                url: pipeline["web_url"].as_str().unwrap_or_default().to_string(),
+            })
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Opens `url` with whatever the desktop has set up for it.
+pub(crate) fn open_url_cmd(url: &str) -> Command {
+    if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("open");
+        cmd.arg(url);
+        cmd
+    } else if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", "", url]);
+        cmd
+    } else {
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(url);
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_checks, parse_pull_requests, CiState, CiStatus, Forge, ForgeKind};
+    use std::path::Path;
+
+    #[test]
+    fn remote_urls_are_recognized() {
+        let forge = |url| Forge::from_url(url).map(|forge| (forge.kind, forge.host, forge.path));
+
+        assert_eq!(
+            forge("git@github.com:altsem/gitu.git"),
+            Some((ForgeKind::GitHub, "github.com".into(), "altsem/gitu".into()))
+        );
+        assert_eq!(
+            forge("https://gitlab.com/group/sub/project"),
+            Some((
+                ForgeKind::GitLab,
+                "gitlab.com".into(),
+                "group/sub/project".into()
+            ))
+        );
+        assert_eq!(
+            forge("ssh://git@bitbucket.org:22/team/repo.git"),
+            Some((
+                ForgeKind::Bitbucket,
+                "bitbucket.org".into(),
+                "team/repo".into()
+            ))
+        );
+        assert_eq!(forge("/srv/git/repo.git"), None);
+    }
+
+    #[test]
+    fn urls_follow_the_forge() {
+        let github = Forge::from_url("git@github.com:altsem/gitu.git").unwrap();
+        assert_eq!(
+            github.file_url("main", Path::new("src/lib.rs"), Some(3)),
+            "https://github.com/altsem/gitu/blob/main/src/lib.rs#L3"
+        );
+
+        let gitlab = Forge::from_url("https://gitlab.com/group/project.git").unwrap();
+        assert_eq!(
+            gitlab.commit_url("abc123"),
+            "https://gitlab.com/group/project/-/commit/abc123"
+        );
+    }
+
+    #[test]
+    fn pull_requests_are_read_from_either_api() {
+        let github = serde_json::json!([{
+            "number": 7,
+            "title": "Add feature",
+            "user": { "login": "octocat" },
+            "head": { "ref": "feature", "sha": "abc" },
+            "html_url": "https://github.com/o/r/pull/7",
+            "body": null,
+            "draft": true
+        }]);
+        let gitlab = serde_json::json!([{
+            "iid": 7,
+            "title": "Add feature",
+            "author": { "username": "octocat" },
+            "source_branch": "feature",
+            "sha": "abc",
+            "web_url": "https://github.com/o/r/pull/7",
+            "description": "",
+            "draft": true
+        }]);
+
+        assert_eq!(
+            parse_pull_requests(ForgeKind::GitHub, &github).unwrap(),
+            parse_pull_requests(ForgeKind::GitLab, &gitlab).unwrap()
+        );
+    }
+
+    #[test]
+    fn check_runs_prefer_their_conclusion() {
+        let checks = parse_checks(
+            ForgeKind::GitHub,
+            &serde_json::json!({ "check_runs": [
+                { "name": "test", "status": "completed", "conclusion": "failure" },
+                { "name": "lint", "status": "in_progress", "conclusion": null }
+            ]}),
+        );
+
+        let statuses = checks
+            .iter()
+            .map(|check| (check.name.as_str(), check.status.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(statuses, [("test", "failure"), ("lint", "in_progress")]);
+    }
+
+    #[test]
+    fn ci_fails_with_any_check() {
+        let checks = parse_checks(
+            ForgeKind::GitHub,
+            &serde_json::json!({ "check_runs": [
+                { "name": "lint", "status": "in_progress", "html_url": "https://ci/1" },
+                { "name": "test", "conclusion": "failure", "html_url": "https://ci/2" }
+            ]}),
+        );
+
+        assert_eq!(
+            CiStatus::of_checks(&checks),
+            Some(CiStatus {
+                state: CiState::Failure,
+                url: "https://ci/2".into()
+            })
+        );
+        assert_eq!(
+            CiStatus::of_checks(&checks[..1]).map(|status| status.state),
+            Some(CiState::Pending)
+        );
+        assert_eq!(CiStatus::of_checks(&[]), None);
+    }
+}
@@ -0,0 +1,5 @@
+pub(crate) struct AmStatus {
+    /// 1-based number of the patch being applied
+    pub next: usize,
+    pub last: usize,
+}
@@ -0,0 +1,5 @@
+pub(crate) struct BisectStatus {
+    /// Commits that could still be the first bad one, apart from the known bad commit.
+    /// Unknown until both a good and a bad commit have been marked.
+    pub revisions_left: Option<usize>,
+}
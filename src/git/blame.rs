@@ -0,0 +1,108 @@
+use crate::Res;
+use git2::{BlameOptions, Oid, Repository};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug)]
+pub(crate) struct BlameLine {
+    pub(crate) commit: Oid,
+    /// Where the file was at `commit`
+    pub(crate) path: PathBuf,
+    /// 1-based, as in the file at `commit`
+    pub(crate) line_number: usize,
+    pub(crate) author: String,
+    /// Seconds since the epoch
+    pub(crate) time: i64,
+    pub(crate) content: String,
+    /// Consecutive lines from one commit are grouped, only the first carries its details
+    pub(crate) first_in_hunk: bool,
+}
+
+/// The contents of `path` as of `newest`, which blaming starts from.
+pub(crate) fn file_lines(repo: &Repository, newest: Oid, path: &Path) -> Res<Vec<String>> {
+    let blob = repo
+        .find_commit(newest)?
+        .tree()?
+        .get_path(path)?
+        .to_object(repo)?
+        .peel_to_blob()?;
+
+    Ok(String::from_utf8_lossy(blob.content())
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+/// Blames just the lines in `range` (0-based), which is a lot faster than the whole file.
+pub(crate) fn blame(
+    repo: &Repository,
+    newest: Oid,
+    path: &Path,
+    lines: &[String],
+    range: Range<usize>,
+) -> Res<Vec<BlameLine>> {
+    if range.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut opts = BlameOptions::new();
+    opts.newest_commit(newest);
+    opts.min_line(range.start + 1);
+    opts.max_line(range.end);
+
+    let blame = repo.blame_file(path, Some(&mut opts))?;
+    let mut blamed = vec![];
+
+    for hunk in blame.iter() {
+        let start = hunk.final_start_line().max(range.start + 1);
+        let end = (hunk.final_start_line() + hunk.lines_in_hunk()).min(range.end + 1);
+        let signature = hunk.final_signature();
+
+        for line_number in start..end {
+            blamed.push(BlameLine {
+                commit: hunk.final_commit_id(),
+                path: hunk.path().unwrap_or(path).to_path_buf(),
+                line_number,
+                author: String::from_utf8_lossy(signature.name_bytes()).to_string(),
+                time: signature.when().seconds(),
+                content: lines.get(line_number - 1).cloned().unwrap_or_default(),
+                first_in_hunk: line_number == start,
+            });
+        }
+    }
+
+    Ok(blamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blame, file_lines};
+    use crate::git::test_repo::TestRepo;
+    use std::path::PathBuf;
+
+    #[test]
+    fn lines_are_attributed_to_the_commit_that_changed_them() {
+        let test = TestRepo::new();
+        let path = PathBuf::from("file");
+
+        let mut head = None;
+        for (time, content) in [(1, "a\nb\n"), (2, "a\nB\nc\n")] {
+            test.stage("file", content);
+            head = Some(test.commit_as("CI", time, "m"));
+        }
+
+        let head = head.unwrap();
+        let lines = file_lines(&test.repo, head, &path).unwrap();
+        let blamed = blame(&test.repo, head, &path, &lines, 1..3).unwrap();
+
+        let summary = blamed
+            .iter()
+            .map(|line| (line.line_number, line.content.as_str(), line.time))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, [(2, "B", 2), (3, "c", 2)]);
+        assert!(blamed[0].first_in_hunk);
+        assert!(!blamed[1].first_in_hunk);
+    }
+}
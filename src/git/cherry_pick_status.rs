@@ -0,0 +1,3 @@
+pub(crate) struct CherryPickStatus {
+    pub head: String,
+}
@@ -0,0 +1,432 @@
+use super::git;
+use crate::Res;
+use git2::Repository;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+/// The built-in linter, any other is a command.
+pub(crate) const CONVENTIONAL: &str = "conventional";
+
+/// What the editor leaves behind, copied from git's COMMIT_EDITMSG.
+const EDITED_FILE: &str = "GITU_COMMIT_EDITMSG";
+
+/// The message to commit, or to edit again, passed to `git commit -F`.
+/// It's left behind after committing, to be overwritten the next time.
+const DRAFT_FILE: &str = "GITU_COMMIT_DRAFT";
+
+/// Types of `@commitlint/config-conventional`, which most teams go by.
+const TYPES: [&str; 11] = [
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+const MAX_SUBJECT_LENGTH: usize = 100;
+
+/// How far back types and scopes are looked for, to suggest.
+const RECENT_COMMITS: usize = 500;
+
+const SCISSORS: &str = "------------------------ >8 ------------------------";
+
+/// How an edited message is cleaned up, like git would with `commit.cleanup`.
+/// The message is committed as it comes out of this.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Cleanup {
+    mode: CleanupMode,
+    /// What comments start with, from `core.commentString` or `core.commentChar`
+    comment: String,
+    /// Git picks the comment character itself, it's told to use `comment` instead
+    auto_comment: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CleanupMode {
+    Strip,
+    Whitespace,
+    Verbatim,
+    Scissors,
+}
+
+/// Git has no way to check a message between it being edited and committed, other than the
+/// `commit-msg` hook which is the repository's own. So the editor is wrapped to keep what was
+/// written and then fail, which has git stop without committing. gitu commits it afterwards,
+/// once it's been linted. Hooks are run then, rather than twice.
+pub(crate) fn edit_cmd(
+    repo: &Repository,
+    mut cmd: Command,
+    cleanup: &Cleanup,
+    draft: Option<&str>,
+) -> Res<Command> {
+    let edited = repo.path().join(EDITED_FILE);
+    let _ = fs::remove_file(&edited);
+
+    if let Some(draft) = draft {
+        fs::write(repo.path().join(DRAFT_FILE), draft)?;
+        cmd.arg("--edit").arg("--file").arg(draft_arg(repo));
+    }
+
+    // The editor is a shell command, run the way git runs it
+    cmd.arg("--no-verify");
+    cmd.env(
+        "GIT_EDITOR",
+        format!(
+            "{} \"$1\" && cp \"$1\" \"$GITU_COMMIT_EDITMSG\"; false",
+            editor(repo)?
+        ),
+    );
+    cmd.env("GITU_COMMIT_EDITMSG", edited);
+
+    // The comments it writes have to be told apart from the message to clean it up
+    if cleanup.auto_comment {
+        cmd.env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", "core.commentChar")
+            .env("GIT_CONFIG_VALUE_0", &cleanup.comment);
+    }
+    Ok(cmd)
+}
+
+/// The editor git would use, from `GIT_EDITOR`, `core.editor`, `VISUAL` or `EDITOR`.
+fn editor(repo: &Repository) -> Res<String> {
+    let mut cmd = git(["var", "GIT_EDITOR"]);
+    super::in_repo(&mut cmd, repo);
+    let out = cmd.stderr(Stdio::null()).output()?;
+    if !out.status.success() {
+        return Err("No editor configured to write the commit message with".into());
+    }
+
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// What was written in the editor, if git got as far as opening it.
+pub(crate) fn take_edited(repo: &Repository) -> Option<String> {
+    let file = repo.path().join(EDITED_FILE);
+    let edited = fs::read_to_string(&file).ok();
+    let _ = fs::remove_file(file);
+    edited
+}
+
+/// Commit the message as it is, it's been cleaned up already.
+pub(crate) fn commit_cmd(repo: &Repository, mut cmd: Command, message: &str) -> Res<Command> {
+    fs::write(repo.path().join(DRAFT_FILE), message)?;
+    cmd.arg("--cleanup=verbatim")
+        .arg("--file")
+        .arg(draft_arg(repo));
+    Ok(cmd)
+}
+
+/// Relative to where git is run when it can be, as it's shown in the command.
+fn draft_arg(repo: &Repository) -> PathBuf {
+    let file = repo.path().join(DRAFT_FILE);
+    file.strip_prefix(super::command_dir(repo))
+        .map(Path::to_path_buf)
+        .unwrap_or(file)
+}
+
+impl Cleanup {
+    /// As configured for the repository. Editing, git's default is to strip comments.
+    /// When git is to pick the comment character, `#` is the one it picks for an empty message.
+    pub(crate) fn of(repo: &Repository) -> Res<Self> {
+        let config = repo.config()?;
+        let mode = match config.get_string("commit.cleanup").ok().as_deref() {
+            None | Some("default" | "strip") => CleanupMode::Strip,
+            Some("whitespace") => CleanupMode::Whitespace,
+            Some("verbatim") => CleanupMode::Verbatim,
+            Some("scissors") => CleanupMode::Scissors,
+            Some(other) => return Err(format!("Invalid commit.cleanup mode: {}", other).into()),
+        };
+
+        let comment = config
+            .get_string("core.commentString")
+            .or_else(|_| config.get_string("core.commentChar"))
+            .unwrap_or_else(|_| "#".to_string());
+        let auto_comment = comment == "auto";
+
+        Ok(Self {
+            mode,
+            comment: if auto_comment {
+                "#".to_string()
+            } else {
+                comment
+            },
+            auto_comment,
+        })
+    }
+
+    /// Like git does before committing what was edited: whatever is below the scissors of
+    /// `commit.verbose` is dropped, and comments too unless told otherwise. Unless verbatim,
+    /// trailing whitespace, runs of blank lines and blank lines around the message are dropped.
+    pub(crate) fn message(&self, edited: &str) -> String {
+        let scissors = format!("{} {}", self.comment, SCISSORS);
+        let lines = edited.lines().take_while(|line| *line != scissors);
+
+        if self.mode == CleanupMode::Verbatim {
+            return lines.map(|line| format!("{}\n", line)).collect();
+        }
+
+        let mut message = String::new();
+        let mut blank = false;
+        for line in lines {
+            if self.mode == CleanupMode::Strip && line.starts_with(&self.comment) {
+                continue;
+            }
+
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank = true;
+                continue;
+            }
+            if blank && !message.is_empty() {
+                message.push('\n');
+            }
+            blank = false;
+            message.push_str(line);
+            message.push('\n');
+        }
+
+        message.trim_end().to_string()
+    }
+
+    /// The message to edit again, with the problems pointed out underneath it.
+    /// They're comments only if comments are stripped, otherwise it's for the user to delete them.
+    pub(crate) fn draft(&self, message: &str, linter: &str, problems: &[String]) -> String {
+        let mut draft = format!("{}\n\n{} Found by {}:\n", message, self.comment, linter);
+        for problem in problems {
+            draft.push_str(&format!("{}   {}\n", self.comment, problem));
+        }
+        draft
+    }
+}
+
+/// What's wrong with a message, nothing if it's alright. A command is given the message in a
+/// file, the way commit-msg hooks are, it points out problems by failing and saying what they are.
+pub(crate) fn lint(repo: &Repository, linter: &str, message: &str) -> Res<Vec<String>> {
+    if linter == CONVENTIONAL {
+        return Ok(conventional(message));
+    }
+
+    let file = repo.path().join(DRAFT_FILE);
+    fs::write(&file, message)?;
+
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(format!("{} \"$1\"", linter))
+        .arg(linter)
+        .arg(&file)
+        .stdin(Stdio::null());
+    super::in_repo(&mut cmd, repo);
+    let out = cmd
+        .output()
+        .map_err(|err| format!("Couldn't run {}: {}", linter, err))?;
+
+    if out.status.success() {
+        return Ok(vec![]);
+    }
+
+    let output = [out.stdout, out.stderr].concat();
+    let problems = String::from_utf8_lossy(&output)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    if problems.is_empty() {
+        Ok(vec![format!("{} failed with {}", linter, out.status)])
+    } else {
+        Ok(problems)
+    }
+}
+
+/// Checks the message against https://www.conventionalcommits.org, like commitlint's
+/// conventional config does. Messages git writes itself, like for merges, are let through.
+fn conventional(message: &str) -> Vec<String> {
+    let mut lines = message.lines();
+    let subject = lines.next().unwrap_or_default();
+
+    if ["Merge ", "Revert ", "fixup! ", "squash! ", "amend! "]
+        .iter()
+        .any(|prefix| subject.starts_with(prefix))
+    {
+        return vec![];
+    }
+
+    let mut problems = vec![];
+
+    match subject.split_once(": ") {
+        Some((prefix, description)) => {
+            let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+            let (kind, scope) = match prefix.split_once('(') {
+                Some((kind, scope)) => (kind, scope.strip_suffix(')')),
+                None => (prefix, Some("")),
+            };
+
+            if !TYPES.contains(&kind) {
+                problems.push(format!(
+                    "The type should be one of {}, not '{}'",
+                    TYPES.join(", "),
+                    kind
+                ));
+            }
+            if scope.is_none() {
+                problems.push("The scope should be in parentheses, like 'fix(ui): '".to_string());
+            }
+            if description.trim().is_empty() {
+                problems.push("The subject has no description after the type".to_string());
+            }
+        }
+        None => problems.push("The subject isn't like 'type(scope): description'".to_string()),
+    }
+
+    if subject.chars().count() > MAX_SUBJECT_LENGTH {
+        problems.push(format!(
+            "The subject is longer than {} characters",
+            MAX_SUBJECT_LENGTH
+        ));
+    }
+
+    if lines.next().is_some_and(|line| !line.is_empty()) {
+        problems.push("The body should be apart from the subject by a blank line".to_string());
+    }
+
+    problems
+}
+
+/// The type and scope of a conventional subject, like `fix` and `ui` of `fix(ui): typo`.
+fn type_and_scope(subject: &str) -> Option<(&str, Option<&str>)> {
+    let (prefix, _) = subject.split_once(": ")?;
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let (kind, scope) = match prefix.split_once('(') {
+        Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+        None => (prefix, None),
+    };
+
+    let is_word = |word: &str| {
+        !word.is_empty() && !word.contains(|c: char| c.is_whitespace() || c == '(' || c == ')')
+    };
+    (is_word(kind) && scope.is_none_or(is_word)).then_some((kind, scope))
+}
+
+/// Types and scopes used in recent commits, the most used first. The usual types follow
+/// the ones used, so that there's something to pick from in a new repository.
+pub(crate) fn recent_types_and_scopes(repo: &Repository) -> Res<(Vec<String>, Vec<String>)> {
+    let mut revwalk = repo.revwalk()?;
+    if revwalk.push_head().is_err() {
+        return Ok((TYPES.map(String::from).to_vec(), vec![]));
+    }
+
+    let mut types = vec![];
+    let mut scopes = vec![];
+    for id in revwalk.take(RECENT_COMMITS) {
+        let commit = repo.find_commit(id?)?;
+        let Some((kind, scope)) = commit.summary().and_then(type_and_scope) else {
+            continue;
+        };
+
+        types.push(kind.to_string());
+        scopes.extend(scope.map(String::from));
+    }
+
+    let mut types = by_use(types);
+    for kind in TYPES {
+        if !types.iter().any(|used| used == kind) {
+            types.push(kind.to_string());
+        }
+    }
+
+    Ok((types, by_use(scopes)))
+}
+
+/// Each one once, the one there's the most of first. Ties go to the one seen first.
+fn by_use(all: Vec<String>) -> Vec<String> {
+    let mut counted: Vec<(String, usize)> = vec![];
+    for one in all {
+        match counted.iter_mut().find(|(seen, _)| *seen == one) {
+            Some((_, count)) => *count += 1,
+            None => counted.push((one, 1)),
+        }
+    }
+
+    counted.sort_by(|(_, a), (_, b)| b.cmp(a));
+    counted.into_iter().map(|(one, _)| one).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{by_use, conventional, type_and_scope, Cleanup, CleanupMode};
+
+    #[test]
+    fn conventional_messages_pass() {
+        assert!(conventional("feat(ui): add tabs\n\nThey have screens of their own.").is_empty());
+        assert!(conventional("fix!: drop support for git 1.x").is_empty());
+        assert!(conventional("Merge branch 'topic'").is_empty());
+
+        assert_eq!(
+            conventional("Add tabs\nwith screens"),
+            [
+                "The subject isn't like 'type(scope): description'",
+                "The body should be apart from the subject by a blank line"
+            ]
+        );
+        assert_eq!(
+            conventional("feature(ui: add tabs"),
+            [
+                "The type should be one of build, chore, ci, docs, feat, fix, perf, refactor, \
+                 revert, style, test, not 'feature'",
+                "The scope should be in parentheses, like 'fix(ui): '"
+            ]
+        );
+    }
+
+    #[test]
+    fn types_and_scopes_of_subjects() {
+        assert_eq!(
+            type_and_scope("feat(ui)!: tabs"),
+            Some(("feat", Some("ui")))
+        );
+        assert_eq!(type_and_scope("docs: readme"), Some(("docs", None)));
+        assert_eq!(type_and_scope("Fix this: and that"), None);
+
+        let used = ["fix", "feat", "fix", "chore", "feat", "fix"].map(String::from);
+        assert_eq!(by_use(used.to_vec()), ["fix", "feat", "chore"]);
+    }
+
+    #[test]
+    fn cleanup_drops_comments_and_diff() {
+        let edited = "\nfix: typo  \n\n# Please enter the commit message\n\
+                      # ------------------------ >8 ------------------------\n\
+                      diff --git a/file b/file\n";
+
+        let cleanup = Cleanup {
+            mode: CleanupMode::Strip,
+            comment: "#".to_string(),
+            auto_comment: false,
+        };
+        assert_eq!(cleanup.message(edited), "fix: typo");
+    }
+
+    #[test]
+    fn cleanup_goes_by_config() {
+        let edited = "fix: typo\n\n\n# Not a comment\n; A comment\n\
+                      ; ------------------------ >8 ------------------------\n\
+                      diff --git a/file b/file\n";
+        let cleanup = |mode| Cleanup {
+            mode,
+            comment: ";".to_string(),
+            auto_comment: false,
+        };
+
+        assert_eq!(
+            cleanup(CleanupMode::Strip).message(edited),
+            "fix: typo\n\n# Not a comment"
+        );
+        assert_eq!(
+            cleanup(CleanupMode::Whitespace).message(edited),
+            "fix: typo\n\n# Not a comment\n; A comment"
+        );
+        assert_eq!(
+            cleanup(CleanupMode::Verbatim).message(edited),
+            "fix: typo\n\n\n# Not a comment\n; A comment\n"
+        );
+    }
+}
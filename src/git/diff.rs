@@ -1,11 +1,92 @@
 use itertools::Itertools;
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub(crate) struct Diff {
     pub deltas: Vec<Delta>,
 }
 
+impl Diff {
+    /// Replace the deltas touching any of `paths` with those of `other`,
+    /// which is expected to be a diff limited to the same paths.
+    pub(crate) fn replace_paths(&mut self, paths: &[PathBuf], other: Diff) {
+        self.deltas.retain(|delta| !delta.touches_any(paths));
+        self.deltas.extend(other.deltas);
+        self.deltas.sort_by(|a, b| {
+            a.new_file
+                .cmp(&b.new_file)
+                .then(a.old_file.cmp(&b.old_file))
+        });
+    }
+
+    /// `paths` along with the other side of renames touching them. Either side changing may
+    /// undo the rename, yet it's only found as one when both sides are diffed.
+    pub(crate) fn with_renamed(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let mut widened = paths.to_vec();
+        for delta in &self.deltas {
+            if delta.status == git2::Delta::Renamed && delta.touches_any(paths) {
+                widened.extend([delta.old_file.clone(), delta.new_file.clone()]);
+            }
+        }
+
+        widened.sort();
+        widened.dedup();
+        widened
+    }
+}
+
+/// Deltas diffed before, so that a refresh only diffs files that changed since.
+/// Shared by the threads diffing in the background.
+#[derive(Clone, Default)]
+pub(crate) struct DeltaCache {
+    generations: Arc<Mutex<Generations>>,
+}
+
+/// Deltas used since the last full refresh, and those from before it
+#[derive(Default)]
+struct Generations {
+    current: HashMap<DeltaKey, Delta>,
+    previous: HashMap<DeltaKey, Delta>,
+}
+
+/// A delta is the same as long as both of its blobs are. Paths and modes are part of its header.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct DeltaKey {
+    pub(crate) old_file: PathBuf,
+    pub(crate) new_file: PathBuf,
+    pub(crate) old_id: git2::Oid,
+    pub(crate) new_id: git2::Oid,
+    pub(crate) modes: (u32, u32),
+}
+
+impl DeltaCache {
+    /// Starting a refresh of all files, deltas not used since the refresh before are dropped.
+    pub(crate) fn start_refresh(&self) {
+        let generations = &mut *self.generations.lock().unwrap();
+        generations.previous = std::mem::take(&mut generations.current);
+    }
+
+    pub(crate) fn get(&self, key: &DeltaKey) -> Option<Delta> {
+        let generations = &mut *self.generations.lock().unwrap();
+        if let Some(delta) = generations.current.get(key) {
+            return Some(delta.clone());
+        }
+
+        let delta = generations.previous.remove(key)?;
+        generations.current.insert(key.clone(), delta.clone());
+        Some(delta)
+    }
+
+    pub(crate) fn insert(&self, key: DeltaKey, delta: Delta) {
+        self.generations.lock().unwrap().current.insert(key, delta);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Delta {
     pub file_header: String,
@@ -13,6 +94,23 @@ pub(crate) struct Delta {
     pub new_file: PathBuf,
     pub hunks: Vec<Hunk>,
     pub status: git2::Delta,
+    /// Binary files have no hunks, their blobs are kept to show images from.
+    pub binary: bool,
+    pub old_id: git2::Oid,
+    pub new_id: git2::Oid,
+}
+
+impl Delta {
+    pub(crate) fn touches_any(&self, paths: &[PathBuf]) -> bool {
+        paths
+            .iter()
+            .any(|path| is_within(&self.old_file, path) || is_within(&self.new_file, path))
+    }
+}
+
+/// Whether `file` is `path`, or inside of it in case it's a directory.
+pub(crate) fn is_within(file: &Path, path: &Path) -> bool {
+    file.starts_with(path)
 }
 
 #[derive(Debug, Clone)]
@@ -21,7 +119,10 @@ pub(crate) struct Hunk {
     pub new_file: PathBuf,
     pub new_start: u32,
     pub header: String,
-    pub content: String,
+    /// Shared, as hunks are cloned into every item targeting them, and may be huge.
+    pub content: Arc<str>,
+    /// What `content` was before being made valid UTF-8, only if it wasn't already.
+    pub raw_content: Option<Arc<[u8]>>,
 }
 
 impl Hunk {
@@ -29,6 +130,20 @@ impl Hunk {
         format!("{}{}", &self.file_header, self)
     }
 
+    /// The patch to apply, which unlike `format_patch` keeps contents that aren't UTF-8.
+    pub(crate) fn patch_bytes(&self) -> Vec<u8> {
+        let Some(raw_content) = &self.raw_content else {
+            return self.format_patch().into_bytes();
+        };
+
+        [
+            self.file_header.as_bytes(),
+            self.header.as_bytes(),
+            raw_content,
+        ]
+        .concat()
+    }
+
     pub(crate) fn old_content(&self) -> String {
         self.content
             .lines()
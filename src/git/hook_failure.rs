@@ -0,0 +1,96 @@
+use git2::Repository;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Hooks that `--no-verify` skips, see githooks(5). Others can't be bypassed.
+const SKIPPABLE: [&str; 4] = ["pre-commit", "commit-msg", "pre-merge-commit", "pre-push"];
+
+static TRACES: AtomicUsize = AtomicUsize::new(0);
+
+/// Neither git's exit code nor its output tells a hook rejecting a commit or push apart
+/// from git failing otherwise. Its trace2 events do say which hooks it ran, from wherever
+/// `core.hooksPath` points, and how they exited. `cmd` writes them to the returned file.
+pub(crate) fn trace(cmd: &mut Command, repo: &Repository) -> PathBuf {
+    let file = repo.path().join(format!(
+        "gitu-trace2-{}-{}",
+        std::process::id(),
+        TRACES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = fs::remove_file(&file);
+    cmd.env("GIT_TRACE2_EVENT", &file);
+    file
+}
+
+/// The hook that failed, according to the trace of a command. The trace is removed.
+pub(crate) fn failed_hook(trace: &Path) -> Option<String> {
+    let events = fs::read_to_string(trace).ok();
+    let _ = fs::remove_file(trace);
+    parse(&events?)
+}
+
+pub(crate) fn is_skippable(hook: &str) -> bool {
+    SKIPPABLE.contains(&hook)
+}
+
+fn parse(events: &str) -> Option<String> {
+    let events = events
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .collect::<Vec<_>>();
+
+    // Git run from hooks writes here as well, with a sid nested in that of the command.
+    // A command retried after a locked index traced to the same file the first time.
+    let sid = events
+        .iter()
+        .rev()
+        .filter_map(|event| event["sid"].as_str())
+        .find(|sid| !sid.contains('/'))?;
+    let mut hooks = HashMap::new();
+
+    for event in events.iter().filter(|event| event["sid"] == sid) {
+        match event["event"].as_str() {
+            Some("child_start") if event["child_class"] == "hook" => {
+                hooks.insert(event["child_id"].as_u64()?, event["hook_name"].as_str()?);
+            }
+            Some("child_exit") if event["code"].as_i64() != Some(0) => {
+                if let Some(hook) = hooks.get(&event["child_id"].as_u64()?) {
+                    return Some(hook.to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn failed_hook_is_found() {
+        let events = [
+            r#"{"event":"version","sid":"top"}"#,
+            r#"{"event":"child_start","sid":"top","child_id":0,"child_class":"hook","hook_name":"pre-commit"}"#,
+            r#"{"event":"child_start","sid":"top/child","child_id":0,"child_class":"?"}"#,
+            r#"{"event":"child_exit","sid":"top/child","child_id":0,"code":1}"#,
+            r#"{"event":"child_exit","sid":"top","child_id":0,"code":0}"#,
+            r#"{"event":"child_start","sid":"top","child_id":1,"child_class":"hook","hook_name":"commit-msg"}"#,
+            r#"{"event":"child_exit","sid":"top","child_id":1,"code":1}"#,
+        ]
+        .join("\n");
+
+        assert_eq!(parse(&events).as_deref(), Some("commit-msg"));
+        assert_eq!(
+            parse(&events.lines().take(5).collect::<Vec<_>>().join("\n")),
+            None
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use super::git;
+use crate::Res;
+use git2::{IndexEntryExtendedFlag, IndexEntryFlag, Repository};
+use std::{
+    ffi::OsStr,
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Bits in the index that have git stop looking at a file in the worktree,
+/// so that changes to it never show up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum IndexFlag {
+    SkipWorktree,
+    AssumeUnchanged,
+}
+
+impl Display for IndexFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            IndexFlag::SkipWorktree => "skip-worktree",
+            IndexFlag::AssumeUnchanged => "assume-unchanged",
+        })
+    }
+}
+
+/// Files that have either bit set, skip-worktree being listed if both are.
+/// A sparse checkout sets skip-worktree on everything it leaves out, which isn't listed.
+pub(crate) fn flagged_files(repo: &Repository) -> Res<Vec<(PathBuf, IndexFlag)>> {
+    let sparse = repo
+        .config()?
+        .get_bool("core.sparseCheckout")
+        .unwrap_or(false);
+
+    let mut index = repo.index()?;
+    index.read(false)?;
+
+    Ok(index
+        .iter()
+        .filter_map(|entry| {
+            let flag = if !sparse
+                && IndexEntryExtendedFlag::from_bits_truncate(entry.flags_extended)
+                    .is_skip_worktree()
+            {
+                IndexFlag::SkipWorktree
+            } else if IndexEntryFlag::from_bits_truncate(entry.flags).is_valid() {
+                IndexFlag::AssumeUnchanged
+            } else {
+                return None;
+            };
+
+            Some((super::path_from_bytes(&entry.path), flag))
+        })
+        .collect())
+}
+
+pub(crate) fn is_set(repo: &Repository, file: &Path, flag: IndexFlag) -> Res<bool> {
+    let mut index = repo.index()?;
+    index.read(false)?;
+    let entry = index
+        .get_path(file, 0)
+        .ok_or_else(|| format!("{} isn't tracked", file.display()))?;
+
+    Ok(match flag {
+        IndexFlag::SkipWorktree => {
+            IndexEntryExtendedFlag::from_bits_truncate(entry.flags_extended).is_skip_worktree()
+        }
+        IndexFlag::AssumeUnchanged => IndexEntryFlag::from_bits_truncate(entry.flags).is_valid(),
+    })
+}
+
+/// `git update-index --[no-]<flag> -- <file>`
+pub(crate) fn set_cmd(flag: IndexFlag, set: bool, file: &OsStr) -> Command {
+    let arg = if set {
+        format!("--{}", flag)
+    } else {
+        format!("--no-{}", flag)
+    };
+
+    let mut cmd = git(["update-index", &arg, "--"]);
+    cmd.arg(file);
+    cmd
+}
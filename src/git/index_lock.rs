@@ -0,0 +1,64 @@
+use crate::Res;
+use git2::Repository;
+use std::{fs, path::PathBuf, time::Duration};
+
+/// Git holds this while writing the index, a git process that crashed or was killed
+/// leaves it behind. Each worktree has one of its own.
+fn path(repo: &Repository) -> PathBuf {
+    repo.path().join("index.lock")
+}
+
+/// Whether git or libgit2 failed because the index was locked.
+pub(crate) fn is_lock_error(message: &str) -> bool {
+    message.contains("index.lock")
+}
+
+/// How long ago the lock was taken, if the index is still locked.
+/// A lock that's only seconds old likely belongs to another git process that's still running.
+pub(crate) fn age(repo: &Repository) -> Option<Duration> {
+    let modified = fs::metadata(path(repo)).ok()?.modified().ok()?;
+    Some(modified.elapsed().unwrap_or_default())
+}
+
+pub(crate) fn remove(repo: &Repository) -> Res<()> {
+    fs::remove_file(path(repo)).map_err(|err| format!("Couldn't remove index.lock: {}", err))?;
+    Ok(())
+}
+
+/// In the largest unit that fits, like `5s` or `3h`.
+pub(crate) fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{age, format_age, is_lock_error};
+    use crate::git::test_repo::TestRepo;
+    use std::{fs, time::Duration};
+
+    #[test]
+    fn lock_is_found() {
+        let TestRepo { dir, repo } = TestRepo::new();
+        assert!(age(&repo).is_none());
+
+        fs::write(dir.child(".git/index.lock"), "").unwrap();
+        assert!(age(&repo).unwrap() < Duration::from_secs(60));
+        assert!(is_lock_error(
+            "fatal: Unable to create '/repo/.git/index.lock': File exists."
+        ));
+    }
+
+    #[test]
+    fn age_is_in_the_largest_unit() {
+        assert_eq!(format_age(Duration::from_secs(5)), "5s");
+        assert_eq!(format_age(Duration::from_secs(150)), "2m");
+        assert_eq!(format_age(Duration::from_secs(7200)), "2h");
+        assert_eq!(format_age(Duration::from_secs(3 * 86400)), "3d");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::Res;
+use git2::{Oid, Patch, Repository, Time};
+use std::{collections::HashMap, path::PathBuf};
+
+/// How far back activity and changed paths are counted.
+pub(crate) const WEEKS: usize = 12;
+/// This many of the largest changed paths are listed.
+const PATHS: usize = 10;
+pub(crate) const WEEK_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+pub(crate) struct Insights {
+    /// Commits per author in all of the history, the most first, like `git shortlog -sn`
+    pub(crate) authors: Vec<(String, usize)>,
+    /// When HEAD was committed, which weeks are counted back from
+    pub(crate) until: Time,
+    /// Commits per week, the one up to `until` first
+    pub(crate) weeks: Vec<usize>,
+    /// Lines added and removed per path in the last weeks, the most first
+    pub(crate) paths: Vec<(PathBuf, usize)>,
+}
+
+/// Walks the history of HEAD. Weeks are counted back from HEAD rather than from now,
+/// so that a repository that's been quiet for a while still shows what happened last.
+/// Only recent commits are diffed, it's what most of the time goes to.
+pub(crate) fn insights(repo: &Repository) -> Res<Insights> {
+    let mut authors = HashMap::<String, usize>::new();
+    let mut weeks = vec![0; WEEKS];
+    let mut paths = HashMap::<PathBuf, usize>::new();
+
+    let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) else {
+        return Ok(Insights {
+            authors: vec![],
+            until: Time::new(0, 0),
+            weeks,
+            paths: vec![],
+        });
+    };
+    let until = head.time();
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head.id())?;
+
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let author = String::from_utf8_lossy(commit.author().name_bytes()).into_owned();
+        *authors.entry(author).or_default() += 1;
+
+        let week = (until.seconds() - commit.time().seconds()).max(0) / WEEK_SECONDS;
+        let Some(count) = weeks.get_mut(week as usize) else {
+            continue;
+        };
+        *count += 1;
+
+        for (path, lines) in changed_lines(repo, commit.id())? {
+            *paths.entry(path).or_default() += lines;
+        }
+    }
+
+    Ok(Insights {
+        authors: most_first(authors),
+        until,
+        weeks,
+        paths: most_first(paths).into_iter().take(PATHS).collect(),
+    })
+}
+
+/// Lines added and removed per file compared to the first parent
+fn changed_lines(repo: &Repository, commit: Oid) -> Res<Vec<(PathBuf, usize)>> {
+    let commit = repo.find_commit(commit)?;
+    let parent_tree = commit
+        .parents()
+        .next()
+        .and_then(|parent| parent.tree().ok());
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+
+    let mut lines = vec![];
+    for i in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(&diff, i)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+            continue;
+        };
+
+        let (_, added, removed) = patch.line_stats()?;
+        lines.push((path.to_path_buf(), added + removed));
+    }
+
+    Ok(lines)
+}
+
+fn most_first<K: Ord>(counts: HashMap<K, usize>) -> Vec<(K, usize)> {
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by(|(a_key, a), (b_key, b)| b.cmp(a).then_with(|| a_key.cmp(b_key)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::insights;
+    use crate::git::test_repo::TestRepo;
+
+    #[test]
+    fn counts_commits_per_author_and_week() {
+        let test = TestRepo::new();
+        let week = 7 * 24 * 60 * 60;
+        let now = 100 * week;
+
+        for (author, file, content, age) in [
+            ("Ann", "old", "1\n", 20 * week),
+            ("Bob", "file", "1\n", 2 * week),
+            ("Ann", "file", "1\n2\n3\n", 0),
+        ] {
+            test.stage(file, content);
+            test.commit_as(author, now - age, file);
+        }
+
+        let insights = insights(&test.repo).unwrap();
+        assert_eq!(
+            insights.authors,
+            [("Ann".to_string(), 2), ("Bob".to_string(), 1)]
+        );
+        assert_eq!(insights.weeks[..3], [1, 0, 1]);
+        assert_eq!(insights.paths, [("file".into(), 3)]);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::Res;
+use git2::{Commit, Oid, Repository};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[derive(Debug)]
+pub(crate) struct LogEntry {
+    pub(crate) id: Oid,
+    pub(crate) short_id: String,
+    pub(crate) summary: String,
+    pub(crate) refs: Vec<LogRef>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LogRef {
+    pub(crate) name: String,
+    pub(crate) kind: RefKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RefKind {
+    Branch,
+    Remote,
+    Tag,
+}
+
+/// Commits to list, out of all in the history.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LogFilter {
+    Author(String),
+    /// Commits that changed the file, or anything in the directory, compared to their first parent
+    Path(PathBuf),
+}
+
+impl LogFilter {
+    fn matches(&self, commit: &Commit) -> bool {
+        match self {
+            LogFilter::Author(name) => commit.author().name_bytes() == name.as_bytes(),
+            LogFilter::Path(path) => {
+                let id_at = |commit: &Commit| Some(commit.tree().ok()?.get_path(path).ok()?.id());
+                id_at(commit) != commit.parents().next().and_then(|parent| id_at(&parent))
+            }
+        }
+    }
+}
+
+/// Where to continue listing history from.
+/// This is the state of the walk rather than a commit, as history may branch out,
+/// so that a page picks up where the last one stopped without walking up to there again.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct LogCursor {
+    /// Commits reached but not walked yet, with their commit times
+    frontier: Vec<(i64, Oid)>,
+    /// Commits that were in the frontier at some point, and aren't to be walked twice
+    seen: Arc<HashSet<Oid>>,
+    /// Counted in commits that pass the filter
+    offset: usize,
+    filter: Option<LogFilter>,
+}
+
+pub(crate) struct LogPage {
+    pub(crate) entries: Vec<LogEntry>,
+    /// Where the next page starts, if there are more commits.
+    pub(crate) next: Option<LogCursor>,
+}
+
+pub(crate) struct LogRange {
+    pub(crate) entries: Vec<LogEntry>,
+    /// How many commits there are in the range after those
+    pub(crate) more: usize,
+}
+
+impl LogCursor {
+    /// The start of the history of HEAD, if there's any yet.
+    pub(crate) fn head(repo: &Repository) -> Res<Option<Self>> {
+        let Ok(head) = repo.head() else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self::from(&head.peel_to_commit()?)))
+    }
+
+    pub(crate) fn at(repo: &Repository, reference: &str) -> Res<Self> {
+        Ok(Self::from(
+            &repo.revparse_single(reference)?.peel_to_commit()?,
+        ))
+    }
+
+    fn from(start: &Commit) -> Self {
+        Self {
+            frontier: vec![(start.time().seconds(), start.id())],
+            seen: Arc::new(HashSet::from([start.id()])),
+            offset: 0,
+            filter: None,
+        }
+    }
+
+    pub(crate) fn filtered(self, filter: Option<LogFilter>) -> Self {
+        Self { filter, ..self }
+    }
+
+    /// Number of commits listed before this position.
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Walks past the commits the filter leaves out, up to the next one to list if there's any.
+    fn skip_filtered<'repo>(
+        &mut self,
+        repo: &'repo Repository,
+    ) -> Res<Option<(usize, Commit<'repo>)>> {
+        while let Some(i) = self.newest() {
+            let commit = repo.find_commit(self.frontier[i].1)?;
+            if self
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(&commit))
+            {
+                return Ok(Some((i, commit)));
+            }
+            self.walk_past(i, &commit);
+        }
+
+        Ok(None)
+    }
+
+    /// The newest commit in the frontier is walked next, like `git log` does.
+    /// Of equally old ones, the one reached first.
+    fn newest(&self) -> Option<usize> {
+        let (i, _) = self
+            .frontier
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, (time, _))| *time)?;
+        Some(i)
+    }
+
+    fn walk_past(&mut self, i: usize, commit: &Commit) {
+        self.frontier.remove(i);
+        for parent in commit.parents() {
+            if Arc::make_mut(&mut self.seen).insert(parent.id()) {
+                self.frontier.push((parent.time().seconds(), parent.id()));
+            }
+        }
+    }
+}
+
+/// List up to `count` commits, starting at `cursor`.
+pub(crate) fn page(repo: &Repository, cursor: &LogCursor, count: usize) -> Res<LogPage> {
+    let mut walk = cursor.clone();
+    let mut refs = refs_by_target(repo)?;
+    let mut entries = vec![];
+
+    while entries.len() < count {
+        let Some((i, commit)) = walk.skip_filtered(repo)? else {
+            break;
+        };
+        entries.push(entry(repo, commit.id(), &mut refs)?);
+        walk.walk_past(i, &commit);
+    }
+
+    walk.offset += entries.len();
+    let next = walk.skip_filtered(repo)?.is_some().then_some(walk);
+    Ok(LogPage { entries, next })
+}
+
+/// Up to `count` of the commits reachable from `from` that aren't from `hide`,
+/// like `git log hide..from`.
+pub(crate) fn range(repo: &Repository, from: Oid, hide: Oid, count: usize) -> Res<LogRange> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(from)?;
+    revwalk.hide(hide)?;
+    let mut refs = refs_by_target(repo)?;
+
+    let entries = revwalk
+        .by_ref()
+        .take(count)
+        .map(|oid| entry(repo, oid?, &mut refs))
+        .collect::<Res<Vec<_>>>()?;
+    let more = revwalk.count();
+
+    Ok(LogRange { entries, more })
+}
+
+fn entry(repo: &Repository, oid: Oid, refs: &mut HashMap<Oid, Vec<LogRef>>) -> Res<LogEntry> {
+    let commit = repo.find_commit(oid)?;
+
+    Ok(LogEntry {
+        id: oid,
+        short_id: commit.as_object().short_id()?.as_str().unwrap().to_string(),
+        summary: commit
+            .summary_bytes()
+            .map(|summary| String::from_utf8_lossy(summary).into_owned())
+            .unwrap_or_default(),
+        refs: refs.remove(&oid).unwrap_or_default(),
+    })
+}
+
+fn refs_by_target(repo: &Repository) -> Res<HashMap<Oid, Vec<LogRef>>> {
+    let mut refs = HashMap::<Oid, Vec<LogRef>>::new();
+
+    for reference in repo.references()?.filter_map(Result::ok) {
+        let (Ok(target), Some(name)) = (reference.peel_to_commit(), reference.shorthand()) else {
+            continue;
+        };
+
+        // Bisect marks are shown in the status header instead, gitu's own refs aren't history
+        if name.ends_with("/HEAD")
+            || reference.name().is_some_and(|name| {
+                name.starts_with("refs/bisect/") || name.starts_with("refs/gitu/")
+            })
+        {
+            continue;
+        }
+
+        let kind = if reference.is_remote() {
+            RefKind::Remote
+        } else if reference.is_tag() {
+            RefKind::Tag
+        } else {
+            RefKind::Branch
+        };
+
+        refs.entry(target.id()).or_default().push(LogRef {
+            name: name.to_string(),
+            kind,
+        });
+    }
+
+    Ok(refs)
+}
+
+/// Commits that changed `path`, from `start` back to where it was added, the newest first.
+/// A merge only counts if it changed it compared to its first parent, like `git log` shows.
+pub(crate) fn file_history(repo: &Repository, start: Oid, path: &Path) -> Res<Vec<Oid>> {
+    let blob_at =
+        |commit: &Commit| -> Option<Oid> { Some(commit.tree().ok()?.get_path(path).ok()?.id()) };
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(start)?;
+
+    let mut history = vec![];
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let blob = blob_at(&commit);
+        let parent_blob = commit.parents().next().and_then(|parent| blob_at(&parent));
+
+        if blob.is_some() && blob != parent_blob {
+            history.push(commit.id());
+        }
+    }
+
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_history, page, range, LogCursor, LogFilter};
+    use crate::git::test_repo::TestRepo;
+
+    #[test]
+    fn pages_continue_where_the_last_one_ended() {
+        let test = TestRepo::new();
+        let repo = &test.repo;
+        for i in 0..5 {
+            test.commit(&format!("commit {}", i));
+        }
+
+        let cursor = LogCursor::head(repo).unwrap().unwrap();
+        let first = page(repo, &cursor, 3).unwrap();
+        let second = page(repo, first.next.as_ref().unwrap(), 3).unwrap();
+
+        let summaries = first
+            .entries
+            .iter()
+            .chain(&second.entries)
+            .map(|entry| entry.summary.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            summaries,
+            ["commit 4", "commit 3", "commit 2", "commit 1", "commit 0"]
+        );
+        assert!(second.next.is_none());
+    }
+
+    #[test]
+    fn filtered_pages_continue_where_the_last_one_ended() {
+        let test = TestRepo::new();
+        let repo = &test.repo;
+        for (i, author) in ["Ann", "Bob", "Bob", "Ann", "Bob", "Ann"]
+            .iter()
+            .enumerate()
+        {
+            test.commit_as(author, i as i64, &format!("commit {}", i));
+        }
+
+        let mut cursor = LogCursor::head(repo)
+            .unwrap()
+            .unwrap()
+            .filtered(Some(LogFilter::Author("Ann".into())));
+        let mut pages = vec![];
+        loop {
+            let page = page(repo, &cursor, 2).unwrap();
+            pages.push(
+                page.entries
+                    .iter()
+                    .map(|entry| entry.summary.clone())
+                    .collect::<Vec<_>>(),
+            );
+            match page.next {
+                Some(next) => cursor = next,
+                None => break,
+            }
+        }
+
+        assert_eq!(pages, [vec!["commit 5", "commit 3"], vec!["commit 0"]]);
+    }
+
+    #[test]
+    fn ranges_are_cut_short() {
+        let test = TestRepo::new();
+        let repo = &test.repo;
+        let ids = (0..5)
+            .map(|i| test.commit(&format!("commit {}", i)))
+            .collect::<Vec<_>>();
+
+        let range = range(repo, ids[4], ids[0], 2).unwrap();
+        let summaries = range
+            .entries
+            .iter()
+            .map(|entry| entry.summary.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(summaries, ["commit 4", "commit 3"]);
+        assert_eq!(range.more, 2);
+    }
+
+    #[test]
+    fn file_history_has_commits_changing_it() {
+        let test = TestRepo::new();
+        let repo = &test.repo;
+        let mut ids = vec![];
+        for (file, content) in [("file", "1"), ("other", "1"), ("file", "2")] {
+            test.stage(file, content);
+            ids.push(test.commit(file));
+        }
+
+        assert_eq!(
+            file_history(repo, ids[2], "file".as_ref()).unwrap(),
+            [ids[2], ids[0]]
+        );
+    }
+}
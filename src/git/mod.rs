@@ -1,104 +1,338 @@
-use git2::{DiffLineType::*, Repository};
+use git2::{DiffLineType::*, ErrorCode, Oid, Repository, RepositoryState};
 use itertools::Itertools;
 
 use self::{
+    am_status::AmStatus,
+    bisect_status::BisectStatus,
+    cherry_pick_status::CherryPickStatus,
     commit::Commit,
-    diff::{Delta, Diff, Hunk},
+    diff::{Delta, DeltaCache, DeltaKey, Diff, Hunk},
     merge_status::MergeStatus,
     rebase_status::RebaseStatus,
+    revert_status::RevertStatus,
 };
 use crate::{git2_opts, Res};
 use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    env,
     ffi::OsStr,
     fs,
+    io::Write,
+    mem,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Stdio},
     str::{self},
+    thread::{self, Scope, ScopedJoinHandle},
 };
 
+pub(crate) mod am_status;
+pub(crate) mod bisect_status;
+pub(crate) mod blame;
+pub(crate) mod cherry_pick_status;
 pub(crate) mod commit;
+pub(crate) mod commit_lint;
 pub(crate) mod diff;
+pub(crate) mod hook_failure;
+pub(crate) mod index_flags;
+pub(crate) mod index_lock;
+pub(crate) mod insights;
+pub(crate) mod log;
 pub(crate) mod merge_status;
 pub(crate) mod rebase_status;
-
-// TODO Use only plumbing commands
+pub(crate) mod revert_status;
+pub(crate) mod snapshot;
+pub(crate) mod tag;
+#[cfg(test)]
+pub(crate) mod test_repo;
+pub(crate) mod trash;
+pub(crate) mod undo;
+pub(crate) mod worktree;
 
 pub(crate) fn rebase_status(repo: &Repository) -> Res<Option<RebaseStatus>> {
-    let dir = repo.workdir().expect("No workdir");
-    let mut rebase_onto_file = dir.to_path_buf();
-    rebase_onto_file.push(".git/rebase-merge/onto");
-
-    let mut rebase_head_name_file = dir.to_path_buf();
-    rebase_head_name_file.push(".git/rebase-merge/head-name");
-
-    match fs::read_to_string(&rebase_onto_file) {
-        Ok(content) => {
-            let onto_hash = content.trim().to_string();
-            Ok(Some(RebaseStatus {
-                onto: branch_name(dir, &onto_hash)?.unwrap_or_else(|| onto_hash[..7].to_string()),
-                head_name: fs::read_to_string(rebase_head_name_file)?
-                    .trim()
-                    .strip_prefix("refs/heads/")
-                    .unwrap()
-                    .to_string(),
-                // TODO include log of 'done' items
-            }))
-        }
-        Err(err) => {
-            log::warn!(
-                "Couldn't read {}, due to {}",
-                rebase_onto_file.to_string_lossy(),
-                err
-            );
-            Ok(None)
-        }
-    }
+    // libgit2 can open a rebase, but doesn't expose what it is onto, so read the state files
+    let rebase_dir = repo.path().join(match repo.state() {
+        RepositoryState::RebaseMerge | RepositoryState::RebaseInteractive => "rebase-merge",
+        RepositoryState::Rebase | RepositoryState::ApplyMailboxOrRebase => "rebase-apply",
+        _ => return Ok(None),
+    });
+
+    let onto_hash = fs::read_to_string(rebase_dir.join("onto"))?
+        .trim()
+        .to_string();
+    let head_name = fs::read_to_string(rebase_dir.join("head-name"))?;
+    let head_name = head_name.trim();
+
+    Ok(Some(RebaseStatus {
+        onto: branch_name(repo, &onto_hash)?.unwrap_or_else(|| short_hash(&onto_hash)),
+        head_name: head_name
+            .strip_prefix("refs/heads/")
+            .unwrap_or(head_name)
+            .to_string(),
+        // TODO include log of 'done' items
+    }))
 }
 
 pub(crate) fn merge_status(repo: &Repository) -> Res<Option<MergeStatus>> {
-    let dir = repo.workdir().expect("No workdir");
-    let mut merge_head_file = dir.to_path_buf();
-    merge_head_file.push(".git/MERGE_HEAD");
-
-    match fs::read_to_string(&merge_head_file) {
-        Ok(content) => {
-            let head = content.trim().to_string();
-            Ok(Some(MergeStatus {
-                head: branch_name(dir, &head)?.unwrap_or(head[..7].to_string()),
-            }))
+    if repo.state() != RepositoryState::Merge {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(repo.path().join("MERGE_HEAD"))?;
+    let head = content.lines().next().unwrap_or("").trim().to_string();
+
+    Ok(Some(MergeStatus {
+        head: branch_name(repo, &head)?.unwrap_or_else(|| short_hash(&head)),
+    }))
+}
+
+pub(crate) fn am_status(repo: &Repository) -> Res<Option<AmStatus>> {
+    if repo.state() != RepositoryState::ApplyMailbox {
+        return Ok(None);
+    }
+
+    let read_number = |file: &str| -> Res<usize> {
+        let content = fs::read_to_string(repo.path().join("rebase-apply").join(file))?;
+        Ok(content.trim().parse()?)
+    };
+
+    Ok(Some(AmStatus {
+        next: read_number("next")?,
+        last: read_number("last")?,
+    }))
+}
+
+pub(crate) fn bisect_status(repo: &Repository) -> Res<Option<BisectStatus>> {
+    // Either is left in the git directory while bisecting
+    if !["BISECT_START", "BISECT_LOG"]
+        .iter()
+        .any(|file| repo.path().join(file).exists())
+    {
+        return Ok(None);
+    }
+
+    let mut good = vec![];
+    let mut skipped = HashSet::new();
+    for reference in repo
+        .references_glob("refs/bisect/*")?
+        .filter_map(Result::ok)
+    {
+        let (Some(name), Some(target)) = (reference.name(), reference.target()) else {
+            continue;
+        };
+
+        if name.starts_with("refs/bisect/good-") {
+            good.push(target);
+        } else if name.starts_with("refs/bisect/skip-") {
+            skipped.insert(target);
         }
-        Err(err) => {
-            log::warn!(
-                "Couldn't read {}, due to {}",
-                merge_head_file.to_string_lossy(),
-                err
-            );
-            Ok(None)
+    }
+
+    let bad = repo.refname_to_id("refs/bisect/bad").ok();
+    let revisions_left = match bad {
+        Some(bad) if !good.is_empty() => {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push(bad)?;
+            for id in good {
+                revwalk.hide(id)?;
+            }
+
+            let candidates = revwalk
+                .filter_map(Result::ok)
+                .filter(|id| *id != bad && !skipped.contains(id))
+                .count();
+
+            Some(candidates)
         }
+        _ => None,
+    };
+
+    Ok(Some(BisectStatus { revisions_left }))
+}
+
+pub(crate) fn cherry_pick_status(repo: &Repository) -> Res<Option<CherryPickStatus>> {
+    if !matches!(
+        repo.state(),
+        RepositoryState::CherryPick | RepositoryState::CherryPickSequence
+    ) {
+        return Ok(None);
+    }
+
+    // Between picks of a sequence, there's no CHERRY_PICK_HEAD
+    let content = fs::read_to_string(repo.path().join("CHERRY_PICK_HEAD")).unwrap_or_default();
+    let head = content.lines().next().unwrap_or("").trim().to_string();
+
+    Ok(Some(CherryPickStatus {
+        head: if head.is_empty() {
+            head
+        } else {
+            branch_name(repo, &head)?.unwrap_or_else(|| short_hash(&head))
+        },
+    }))
+}
+
+pub(crate) fn revert_status(repo: &Repository) -> Res<Option<RevertStatus>> {
+    if !matches!(
+        repo.state(),
+        RepositoryState::Revert | RepositoryState::RevertSequence
+    ) {
+        return Ok(None);
     }
+
+    // Between reverts of a sequence, there's no REVERT_HEAD
+    let content = fs::read_to_string(repo.path().join("REVERT_HEAD")).unwrap_or_default();
+    let head = content.lines().next().unwrap_or("").trim().to_string();
+
+    Ok(Some(RevertStatus {
+        head: if head.is_empty() {
+            head
+        } else {
+            branch_name(repo, &head)?.unwrap_or_else(|| short_hash(&head))
+        },
+    }))
 }
 
-// TODO replace with libgit2
-fn branch_name(dir: &Path, hash: &str) -> Res<Option<String>> {
-    let out = Command::new("git")
-        .args(["for-each-ref", "--format", "%(objectname) %(refname:short)"])
-        .current_dir(dir)
-        .output()?
-        .stdout;
+fn branch_name(repo: &Repository, hash: &str) -> Res<Option<String>> {
+    let oid = Oid::from_str(hash)?;
 
-    Ok(str::from_utf8(&out)?
-        .lines()
-        .find(|line| line.starts_with(hash))
-        .map(|line| line.split(' ').nth(1).unwrap().to_string()))
+    Ok(repo
+        .references()?
+        .filter_map(Result::ok)
+        .filter(|reference| reference.target() == Some(oid))
+        .filter_map(|reference| {
+            Some((
+                reference.name()?.to_string(),
+                reference.shorthand()?.to_string(),
+            ))
+        })
+        // Same order as `git for-each-ref`, so that local branches are preferred
+        .sorted()
+        .map(|(_name, shorthand)| shorthand)
+        .next())
+}
+
+/// Git paths are bytes, that need not be UTF-8 other than on Windows.
+pub(crate) fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    return PathBuf::from(<OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(bytes));
+    #[cfg(not(unix))]
+    return PathBuf::from(String::from_utf8_lossy(bytes).into_owned());
+}
+
+/// A path as git shows it with `core.quotePath`, bytes that aren't valid UTF-8 escaped in
+/// octal like `caf\351.txt`. Unlike a lossy conversion, different paths are told apart.
+pub(crate) fn display_path(path: &Path) -> Cow<'_, str> {
+    if let Some(path) = path.to_str() {
+        return path.into();
+    }
+
+    let mut shown = String::new();
+    for chunk in path.as_os_str().as_encoded_bytes().utf8_chunks() {
+        shown.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            shown.push_str(&format!("\\{:03o}", byte));
+        }
+    }
+    shown.into()
+}
+
+pub(crate) fn short_hash(hash: &str) -> String {
+    hash.chars().take(7).collect()
 }
 
 // TODO Move elsewhere
 pub(crate) fn convert_diff(diff: git2::Diff) -> Res<Diff> {
+    let mut reader = DiffReader::default();
+    diff.print(git2::DiffFormat::Patch, |delta, maybe_hunk, line| {
+        reader.line(&delta, maybe_hunk, &line);
+        true
+    })?;
+
+    Ok(Diff {
+        deltas: reader.finish(),
+    })
+}
+
+/// Like `convert_diff`, taking deltas from `cache` that were diffed before with the same blobs.
+/// Only the deltas that weren't are printed.
+fn convert_diff_cached(repo: &Repository, diff: git2::Diff, cache: &DeltaCache) -> Res<Diff> {
     let mut deltas = vec![];
-    let mut lines = String::new();
 
-    diff.print(git2::DiffFormat::Patch, |delta, maybe_hunk, line| {
-        let line_content = str::from_utf8(line.content()).unwrap();
+    for i in 0..diff.deltas().len() {
+        let Some(delta) = diff.get_delta(i) else {
+            continue;
+        };
+        let key = delta_key(repo, &delta);
+        if let Some(cached) = key.as_ref().and_then(|key| cache.get(key)) {
+            deltas.push(cached);
+            continue;
+        }
+
+        let Some(mut patch) = git2::Patch::from_diff(&diff, i)? else {
+            continue;
+        };
+        let mut reader = DiffReader::default();
+        patch.print(&mut |delta, maybe_hunk, line| {
+            reader.line(&delta, maybe_hunk, &line);
+            true
+        })?;
+
+        for delta in reader.finish() {
+            if let Some(key) = key.clone() {
+                cache.insert(key, delta.clone());
+            }
+            deltas.push(delta);
+        }
+    }
+
+    Ok(Diff { deltas })
+}
+
+/// Files in the worktree only have an id if libgit2 had to hash them, it's done here otherwise.
+/// Symlinks and submodules aren't cached, hashing what's there wouldn't tell them apart.
+fn delta_key(repo: &Repository, delta: &git2::DiffDelta) -> Option<DeltaKey> {
+    let (old, new) = (delta.old_file(), delta.new_file());
+    if [old.mode(), new.mode()]
+        .iter()
+        .any(|mode| matches!(mode, git2::FileMode::Link | git2::FileMode::Commit))
+    {
+        return None;
+    }
+
+    let new_id = if new.id().is_zero() && new.exists() {
+        let file = repo.workdir()?.join(new.path()?);
+        Oid::hash_file(git2::ObjectType::Blob, file).ok()?
+    } else {
+        new.id()
+    };
+
+    Some(DeltaKey {
+        old_file: path(&old),
+        new_file: path(&new),
+        old_id: old.id(),
+        new_id,
+        modes: (old.mode().into(), new.mode().into()),
+    })
+}
+
+/// Reads deltas from the printed lines of a diff.
+#[derive(Default)]
+struct DiffReader {
+    deltas: Vec<Delta>,
+    // Content of the hunk being read, it's kept shared once complete
+    content: HunkContent,
+}
+
+impl DiffReader {
+    fn line(
+        &mut self,
+        delta: &git2::DiffDelta,
+        maybe_hunk: Option<git2::DiffHunk>,
+        line: &git2::DiffLine,
+    ) {
+        // Paths in headers are quoted by libgit2, only file contents may not be UTF-8
+        let line_content = &String::from_utf8_lossy(line.content());
         let is_new_header = line_content.starts_with("diff")
             && line.origin_value() == git2::DiffLineType::FileHeader;
         let is_new_hunk =
@@ -107,38 +341,39 @@ pub(crate) fn convert_diff(diff: git2::Diff) -> Res<Diff> {
         match maybe_hunk {
             None => {
                 if is_new_header {
-                    deltas.push(Delta {
+                    finish_hunk(&mut self.deltas, &mut self.content);
+                    self.deltas.push(Delta {
                         file_header: line_content.to_string(),
                         old_file: path(&delta.old_file()),
                         new_file: path(&delta.new_file()),
                         hunks: vec![],
                         status: delta.status(),
+                        binary: delta.flags().is_binary(),
+                        old_id: delta.old_file().id(),
+                        new_id: delta.new_file().id(),
                     });
                 } else {
-                    let delta = deltas.last_mut().unwrap();
+                    let delta = self.deltas.last_mut().unwrap();
                     delta.file_header.push_str(line_content);
                 }
             }
             Some(hunk) => {
                 if is_new_hunk {
-                    let delta = deltas.last_mut().unwrap();
+                    finish_hunk(&mut self.deltas, &mut self.content);
+                    let delta = self.deltas.last_mut().unwrap();
 
                     delta.hunks.push(Hunk {
                         file_header: delta.file_header.clone(),
                         new_file: delta.new_file.clone(),
                         new_start: hunk.new_start(),
                         header: line_content.to_string(),
-                        content: String::new(),
+                        content: "".into(),
+                        raw_content: None,
                     });
                 } else {
-                    lines.push_str(line_content);
-                    let last_hunk = deltas.last_mut().unwrap().hunks.last_mut().unwrap();
-
                     match line.origin_value() {
                         Context | Addition | Deletion => {
-                            last_hunk
-                                .content
-                                .push_str(&(format!("{}{}", line.origin(), line_content)));
+                            self.content.push(line.origin(), line.content());
                         }
                         ContextEOFNL => {
                             // TODO Handle '\ No newline at the end of file'
@@ -148,31 +383,237 @@ pub(crate) fn convert_diff(diff: git2::Diff) -> Res<Diff> {
                 }
             }
         }
+    }
 
-        true
-    })?;
+    fn finish(mut self) -> Vec<Delta> {
+        finish_hunk(&mut self.deltas, &mut self.content);
+        self.deltas
+    }
+}
 
-    Ok(Diff { deltas })
+/// Lines of a hunk, shown lossily. The original bytes are only kept if that lost anything,
+/// so that the hunk still applies as it was.
+#[derive(Default)]
+struct HunkContent {
+    text: String,
+    raw: Vec<u8>,
+    lossy: bool,
+}
+
+impl HunkContent {
+    fn push(&mut self, origin: char, line: &[u8]) {
+        let text = String::from_utf8_lossy(line);
+        self.lossy |= matches!(text, Cow::Owned(_));
+        self.text.push(origin);
+        self.text.push_str(&text);
+        self.raw.push(origin as u8);
+        self.raw.extend_from_slice(line);
+    }
+}
+
+fn finish_hunk(deltas: &mut [Delta], content: &mut HunkContent) {
+    let content = mem::take(content);
+    if let Some(hunk) = deltas.last_mut().and_then(|delta| delta.hunks.last_mut()) {
+        if !content.text.is_empty() {
+            hunk.content = content.text.into();
+            hunk.raw_content = content.lossy.then(|| content.raw.into());
+        }
+    }
 }
 
 fn path(file: &git2::DiffFile) -> PathBuf {
     file.path().unwrap().to_path_buf()
 }
 
-pub(crate) fn diff_unstaged(repo: &Repository) -> Res<Diff> {
-    let diff = repo.diff_index_to_workdir(None, Some(&mut git2_opts::diff(repo)?))?;
-    convert_diff(diff)
+/// Diff the worktree against the index, limited to `paths` unless empty.
+pub(crate) fn diff_unstaged(repo: &Repository, paths: &[PathBuf], cache: &DeltaCache) -> Res<Diff> {
+    let opts = &mut git2_opts::diff(repo)?;
+    paths.iter().for_each(|path| {
+        opts.pathspec(path).disable_pathspec_match(true);
+    });
+
+    let diff = repo.diff_index_to_workdir(None, Some(opts))?;
+    convert_diff_cached(repo, diff, cache)
 }
 
-pub(crate) fn diff_staged(repo: &Repository) -> Res<Diff> {
+/// Diff the index against HEAD, limited to `paths` unless empty.
+pub(crate) fn diff_staged(repo: &Repository, paths: &[PathBuf], cache: &DeltaCache) -> Res<Diff> {
     let opts = &mut git2_opts::diff(repo)?;
+    paths.iter().for_each(|path| {
+        opts.pathspec(path).disable_pathspec_match(true);
+    });
 
-    let diff = match repo.head() {
+    let mut diff = match repo.head() {
         Ok(head) => repo.diff_tree_to_index(Some(&head.peel_to_tree()?), None, Some(opts))?,
         Err(_) => repo.diff_tree_to_index(None, None, Some(opts))?,
     };
 
-    convert_diff(diff)
+    // A file moved with `git mv` is a deletion and an addition in the index, shown as one
+    diff.find_similar(Some(git2::DiffFindOptions::new().renames(true)))?;
+    convert_diff_cached(repo, diff, cache)
+}
+
+/// Open the repository like git would, from `GIT_DIR` or by searching parent directories.
+/// libgit2 leaves out `GIT_WORK_TREE`, as used with e.g. a bare repository of dotfiles.
+pub(crate) fn open_from_env() -> Result<Repository, git2::Error> {
+    let repo = Repository::open_from_env()?;
+
+    if let Some(work_tree) = env::var_os("GIT_WORK_TREE").filter(|dir| !dir.is_empty()) {
+        let work_tree = env::current_dir()
+            .map_err(|err| git2::Error::from_str(&err.to_string()))?
+            .join(work_tree);
+        repo.set_workdir(&work_tree, false)?;
+    }
+
+    Ok(repo)
+}
+
+/// Where to open a repository again from, as git2 repositories can't be shared between threads.
+/// The worktree isn't necessarily where the git directory would be found from.
+#[derive(Clone, Debug)]
+pub(crate) struct RepoDirs {
+    pub(crate) git_dir: PathBuf,
+    pub(crate) workdir: Option<PathBuf>,
+}
+
+impl RepoDirs {
+    pub(crate) fn of(repo: &Repository) -> Self {
+        Self {
+            git_dir: repo.path().to_path_buf(),
+            workdir: repo.workdir().map(Path::to_path_buf),
+        }
+    }
+
+    pub(crate) fn open(&self) -> Result<Repository, git2::Error> {
+        let repo = Repository::open(&self.git_dir)?;
+
+        if let Some(workdir) = &self.workdir {
+            if repo.workdir() != Some(workdir.as_path()) {
+                repo.set_workdir(workdir, false)?;
+            }
+        }
+
+        Ok(repo)
+    }
+}
+
+/// The directory to run commands concerning `repo` in.
+pub(crate) fn command_dir(repo: &Repository) -> &Path {
+    repo.workdir().unwrap_or(repo.path())
+}
+
+/// Run `cmd` in `repo`. Git finds the repository by itself from the worktree, unless it's
+/// somewhere else like with `GIT_WORK_TREE`, then it's told where both are.
+/// Otherwise these are cleared, as they'd still point at where gitu was started in.
+pub(crate) fn in_repo<'a>(cmd: &'a mut Command, repo: &Repository) -> &'a mut Command {
+    cmd.current_dir(command_dir(repo));
+
+    let discoverable = match repo.workdir() {
+        Some(workdir) => {
+            Repository::discover(workdir).is_ok_and(|found| same_dir(found.path(), repo.path()))
+        }
+        None => true,
+    };
+
+    if discoverable {
+        cmd.env_remove("GIT_DIR").env_remove("GIT_WORK_TREE");
+    } else {
+        cmd.env("GIT_DIR", repo.path());
+        cmd.env("GIT_WORK_TREE", command_dir(repo));
+    }
+
+    cmd
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Above this many changed files, diffing is split across threads.
+const FILES_PER_DIFF_THREAD: usize = 64;
+
+/// Both the unstaged and staged diff, `changed` being every file that may differ.
+/// Lots of changed files are split into chunks that are diffed in parallel, each thread
+/// opening a repository of its own. Renames are found among all that's staged, so the
+/// staged diff isn't split up when there may be any.
+pub(crate) fn diff_unstaged_and_staged(
+    repo: &Repository,
+    changed: &[PathBuf],
+    cache: &DeltaCache,
+) -> Res<(Diff, Diff)> {
+    if changed.len() <= FILES_PER_DIFF_THREAD {
+        return Ok((
+            diff_unstaged(repo, &[], cache)?,
+            diff_staged(repo, &[], cache)?,
+        ));
+    }
+
+    let threads = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .min(changed.len().div_ceil(FILES_PER_DIFF_THREAD));
+    let chunk_size = changed.len().div_ceil(threads);
+    let dirs = RepoDirs::of(repo);
+
+    thread::scope(|scope| {
+        let unstaged = changed
+            .chunks(chunk_size)
+            .map(|chunk| spawn_diff(scope, &dirs, cache, diff_unstaged, chunk))
+            .collect::<Vec<_>>();
+
+        let staged = if may_have_staged_renames(repo)? {
+            diff_staged(repo, &[], cache)?
+        } else {
+            let staged = changed
+                .chunks(chunk_size)
+                .map(|chunk| spawn_diff(scope, &dirs, cache, diff_staged, chunk))
+                .collect::<Vec<_>>();
+            join_diffs(staged)?
+        };
+
+        Ok((join_diffs(unstaged)?, staged))
+    })
+}
+
+/// Whether anything staged is both added and deleted, which may be a rename. Only compares
+/// ids, the contents aren't looked at.
+fn may_have_staged_renames(repo: &Repository) -> Res<bool> {
+    let tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_index(tree.as_ref(), None, None)?;
+
+    let has = |status| diff.deltas().any(|delta| delta.status() == status);
+    Ok(has(git2::Delta::Added) && has(git2::Delta::Deleted))
+}
+
+type DiffFn = fn(&Repository, &[PathBuf], &DeltaCache) -> Res<Diff>;
+
+fn spawn_diff<'scope, 'env>(
+    scope: &'scope Scope<'scope, 'env>,
+    dirs: &'env RepoDirs,
+    cache: &'env DeltaCache,
+    diff: DiffFn,
+    chunk: &'env [PathBuf],
+) -> ScopedJoinHandle<'scope, Result<Diff, String>> {
+    scope.spawn(move || {
+        let repo = dirs.open().map_err(|err| err.to_string())?;
+        diff(&repo, chunk, cache).map_err(|err| err.to_string())
+    })
+}
+
+/// Chunks are in the order of `changed`, which is git's, so the deltas can simply be appended.
+fn join_diffs(handles: Vec<ScopedJoinHandle<'_, Result<Diff, String>>>) -> Res<Diff> {
+    let mut deltas = vec![];
+    for handle in handles {
+        let diff = handle.join().map_err(|_| "Diff thread panicked")??;
+        deltas.extend(diff.deltas);
+    }
+
+    Ok(Diff { deltas })
 }
 
 pub(crate) fn show(repo: &Repository, reference: &str) -> Res<Diff> {
@@ -193,53 +634,116 @@ pub(crate) fn show(repo: &Repository, reference: &str) -> Res<Diff> {
     convert_diff(diff)
 }
 
+/// What a commit changed in one file.
+pub(crate) fn show_file(repo: &Repository, commit: Oid, path: &Path) -> Res<Diff> {
+    let commit = repo.find_commit(commit)?;
+    let tree = commit.tree()?;
+    let parent_tree = commit
+        .parents()
+        .next()
+        .and_then(|parent| parent.tree().ok());
+
+    let mut opts = git2_opts::diff(repo)?;
+    opts.pathspec(path).disable_pathspec_match(true);
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))?;
+    convert_diff(diff)
+}
+
+/// Diff between the ends of `range`, like `git diff <range>`.
+/// A single revision is compared against the worktree instead.
+pub(crate) fn diff_range(repo: &Repository, range: &str) -> Res<Diff> {
+    let opts = &mut git2_opts::diff(repo)?;
+    let spec = repo.revparse(range)?;
+
+    let diff = match (spec.from(), spec.to()) {
+        (Some(from), None) => {
+            repo.diff_tree_to_workdir_with_index(Some(&from.peel_to_tree()?), Some(opts))?
+        }
+        (Some(from), Some(to)) => {
+            let from = if spec.mode().contains(git2::RevparseMode::MERGE_BASE) {
+                let base =
+                    repo.merge_base(from.peel_to_commit()?.id(), to.peel_to_commit()?.id())?;
+                repo.find_commit(base)?.tree()?
+            } else {
+                from.peel_to_tree()?
+            };
+
+            repo.diff_tree_to_tree(Some(&from), Some(&to.peel_to_tree()?), Some(opts))?
+        }
+        _ => return Err(format!("Invalid range: {}", range).into()),
+    };
+
+    convert_diff(diff)
+}
+
 pub(crate) fn show_summary(repo: &Repository, reference: &str) -> Res<Commit> {
     let object = &repo.revparse_single(reference)?;
     let commit = object.peel_to_commit()?;
 
     let author = commit.author();
-    let name = author.name().unwrap_or("");
+    let name = String::from_utf8_lossy(author.name_bytes());
     let email = commit
         .author()
         .email()
         .map(|email| format!("<{}>", email))
         .unwrap_or("".to_string());
 
-    let message = commit
-        .message()
-        .unwrap_or("")
-        .to_string()
+    let message = String::from_utf8_lossy(commit.message_bytes())
         .lines()
         .map(|line| format!("    {}", line))
         .join("\n");
 
-    let offset = chrono::FixedOffset::east_opt(author.when().offset_minutes() * 60).unwrap();
-    let time = chrono::DateTime::with_timezone(
-        &chrono::DateTime::from_timestamp(author.when().seconds(), 0).unwrap(),
-        &offset,
-    );
-
-    let details = format!(
+    let mut details = format!(
         "Author: {}\nDate:   {}\n\n{}",
-        [name, &email].join(" "),
-        time.to_rfc2822(),
+        [&name, email.as_str()].join(" "),
+        format_time(author.when()),
         message
     );
 
+    // Like `git log`, show notes from the default `refs/notes/commits`
+    if let Ok(note) = repo.find_note(None, commit.id()) {
+        let note = String::from_utf8_lossy(note.message_bytes())
+            .lines()
+            .map(|line| format!("    {}", line))
+            .join("\n");
+
+        details.push_str(&format!("\n\nNotes:\n{}", note));
+    }
+
     Ok(Commit {
         hash: commit.id().to_string(),
         details,
     })
 }
 
+/// Formats a time in the timezone it was recorded in, like `git show` does.
+pub(crate) fn format_time(when: git2::Time) -> String {
+    let offset = chrono::FixedOffset::east_opt(when.offset_minutes() * 60).unwrap();
+    chrono::DateTime::with_timezone(
+        &chrono::DateTime::from_timestamp(when.seconds(), 0).unwrap(),
+        &offset,
+    )
+    .to_rfc2822()
+}
+
 pub(crate) fn stage_file_cmd(file: &OsStr) -> Command {
-    git([OsStr::new("add"), file])
+    git_on_paths([OsStr::new("add"), file])
 }
 pub(crate) fn stage_patch_cmd() -> Command {
     git(["apply", "--cached"])
 }
 pub(crate) fn unstage_file_cmd(file: &OsStr) -> Command {
-    git([OsStr::new("restore"), OsStr::new("--staged"), file])
+    git_on_paths([OsStr::new("restore"), OsStr::new("--staged"), file])
+}
+/// There's no HEAD to restore from before the first commit, the file is only taken out of the index.
+pub(crate) fn unstage_new_file_cmd(file: &OsStr) -> Command {
+    git_on_paths([
+        OsStr::new("rm"),
+        OsStr::new("--cached"),
+        OsStr::new("--quiet"),
+        OsStr::new("--"),
+        file,
+    ])
 }
 pub(crate) fn unstage_patch_cmd() -> Command {
     git(["apply", "--cached", "--reverse"])
@@ -262,8 +766,186 @@ pub(crate) fn reset_mixed_cmd(reference: &OsStr) -> Command {
 pub(crate) fn reset_hard_cmd(reference: &OsStr) -> Command {
     git([OsStr::new("reset"), OsStr::new("--hard"), reference])
 }
+pub(crate) fn reset_keep_cmd(reference: &OsStr) -> Command {
+    git([OsStr::new("reset"), OsStr::new("--keep"), reference])
+}
 pub(crate) fn checkout_file_cmd(file: &OsStr) -> Command {
-    git([OsStr::new("checkout"), OsStr::new("--"), file])
+    git_on_paths([OsStr::new("checkout"), OsStr::new("--"), file])
+}
+
+/// Move a tracked file, the rename is staged.
+pub(crate) fn mv_cmd(from: &Path, to: &Path) -> Command {
+    let mut cmd = git_on_paths(["mv", "--"]);
+    cmd.arg(from).arg(to);
+    cmd
+}
+
+/// Force `file` into the index even though it's ignored.
+pub(crate) fn force_add_file_cmd(file: &OsStr) -> Command {
+    git_on_paths([
+        OsStr::new("add"),
+        OsStr::new("--force"),
+        OsStr::new("--"),
+        file,
+    ])
+}
+
+/// What ignores each of `paths`, like `.gitignore:3:*.log`, as told by `git check-ignore`.
+pub(crate) fn ignore_rules(repo: &Repository, paths: &[PathBuf]) -> Res<HashMap<PathBuf, String>> {
+    if paths.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut cmd = git(["check-ignore", "--verbose", "-z", "--stdin"]);
+    in_repo(&mut cmd, repo)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    let mut child = cmd.spawn()?;
+
+    let mut input = vec![];
+    for path in paths {
+        input.extend_from_slice(path.as_os_str().as_encoded_bytes());
+        input.push(0);
+    }
+    child.stdin.take().ok_or("No stdin")?.write_all(&input)?;
+    let out = child.wait_with_output()?;
+
+    // Source, line number, pattern, path, each ended by NUL
+    let fields = out.stdout.split(|&byte| byte == 0).collect::<Vec<_>>();
+    Ok(fields
+        .chunks_exact(4)
+        .map(|fields| {
+            let [source, line, pattern, path] = fields else {
+                unreachable!()
+            };
+            let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+            (
+                path_from_bytes(path),
+                format!("{}:{}:{}", text(source), text(line), text(pattern)),
+            )
+        })
+        .collect())
+}
+
+/// Remove untracked `paths`, directories included. Ignored ones are only removed with `ignored`.
+pub(crate) fn clean_cmd(paths: &[PathBuf], ignored: bool) -> Command {
+    let mut cmd = git_on_paths(["clean", "--force", "-d"]);
+    if ignored {
+        cmd.arg("-x");
+    }
+    cmd.arg("--").args(paths);
+    cmd
+}
+
+pub(crate) fn am_continue_cmd() -> Command {
+    git(["am", "--continue"])
+}
+pub(crate) fn am_skip_cmd() -> Command {
+    git(["am", "--skip"])
+}
+pub(crate) fn am_abort_cmd() -> Command {
+    git(["am", "--abort"])
+}
+
+pub(crate) fn cherry_pick_continue_cmd() -> Command {
+    git(["cherry-pick", "--continue"])
+}
+pub(crate) fn cherry_pick_skip_cmd() -> Command {
+    git(["cherry-pick", "--skip"])
+}
+pub(crate) fn cherry_pick_abort_cmd() -> Command {
+    git(["cherry-pick", "--abort"])
+}
+
+pub(crate) fn revert_continue_cmd() -> Command {
+    git(["revert", "--continue"])
+}
+pub(crate) fn revert_abort_cmd() -> Command {
+    git(["revert", "--abort"])
+}
+
+pub(crate) fn bisect_good_cmd() -> Command {
+    git(["bisect", "good"])
+}
+pub(crate) fn bisect_bad_cmd() -> Command {
+    git(["bisect", "bad"])
+}
+pub(crate) fn bisect_skip_cmd() -> Command {
+    git(["bisect", "skip"])
+}
+pub(crate) fn bisect_reset_cmd() -> Command {
+    git(["bisect", "reset"])
+}
+
+/// Fetch `remotes` without ever stopping to ask for credentials, as nobody may be watching.
+pub(crate) fn background_fetch_cmd(repo: &Repository, remotes: &[String]) -> Command {
+    let mut cmd = if remotes.is_empty() {
+        git(["fetch", "--all"])
+    } else {
+        let mut cmd = git(["fetch", "--multiple"]);
+        cmd.args(remotes);
+        cmd
+    };
+
+    never_prompt(&mut cmd, repo.config());
+    cmd
+}
+
+/// Has `cmd` fail rather than ask for credentials, for when there's no terminal to ask on.
+/// Ssh asks on the terminal itself, it's put in batch mode on top of however it's configured
+/// in `config`. A `GIT_SSH` program is left as it is, it may not be ssh.
+pub(crate) fn never_prompt(cmd: &mut Command, config: Result<git2::Config, git2::Error>) {
+    cmd.env("GIT_TERMINAL_PROMPT", "0");
+
+    let ssh = env::var("GIT_SSH_COMMAND").ok().or_else(|| {
+        config
+            .and_then(|config| config.get_string("core.sshCommand"))
+            .ok()
+    });
+    if let Some(ssh) = ssh {
+        cmd.env("GIT_SSH_COMMAND", format!("{} -o BatchMode=yes", ssh));
+    } else if env::var_os("GIT_SSH").is_none() {
+        cmd.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+}
+
+/// The branch HEAD is on when there are no commits on it yet, as in a fresh repository.
+pub(crate) fn unborn_branch(repo: &Repository) -> Option<String> {
+    match repo.head() {
+        Err(err) if err.code() == ErrorCode::UnbornBranch => {
+            let head = repo.find_reference("HEAD").ok()?;
+            let target = head.symbolic_target()?;
+            Some(
+                target
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(target)
+                    .to_string(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Where the upstream of the current branch points, if it has one.
+pub(crate) fn upstream_id(repo: &Repository) -> Option<Oid> {
+    let head = repo.head().ok()?;
+    let upstream = repo.branch_upstream_name(head.name()?).ok()?;
+    repo.refname_to_id(upstream.as_str()?).ok()
+}
+
+/// The short name of the upstream of the current branch, like `origin/main`.
+pub(crate) fn upstream_name(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    let upstream = repo.branch_upstream_name(head.name()?).ok()?;
+    let upstream = upstream.as_str()?;
+
+    Some(
+        upstream
+            .strip_prefix("refs/remotes/")
+            .unwrap_or(upstream)
+            .to_string(),
+    )
 }
 
 pub(crate) fn git<I, S>(args: I) -> Command
@@ -275,3 +957,160 @@ where
     cmd.args(args);
     cmd
 }
+
+/// Paths are taken as they are rather than as globs, like the refresh after looks at them.
+fn git_on_paths<I, S>(args: I) -> Command
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut cmd = git(args);
+    cmd.env("GIT_LITERAL_PATHSPECS", "1");
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        diff::{DeltaCache, Diff},
+        diff_staged, diff_unstaged, diff_unstaged_and_staged, display_path, git, in_repo,
+        path_from_bytes, RepoDirs,
+    };
+    use crate::git::test_repo::TestRepo;
+    use git2::Repository;
+    use std::{ffi::OsStr, fs, path::PathBuf};
+    use temp_dir::TempDir;
+
+    fn files(diff: &Diff) -> Vec<PathBuf> {
+        diff.deltas
+            .iter()
+            .map(|delta| delta.new_file.clone())
+            .collect()
+    }
+
+    #[test]
+    fn parallel_diff_matches_sequential_one() {
+        let test = TestRepo::new();
+        let names = (0..200)
+            .map(|i| format!("file-{:03}", i))
+            .collect::<Vec<_>>();
+
+        for name in &names {
+            test.stage(name, "original\n");
+        }
+        test.commit("init");
+
+        let (dir, repo) = (&test.dir, &test.repo);
+        let mut index = repo.index().unwrap();
+        for (i, name) in names.iter().enumerate() {
+            fs::write(dir.child(name), "changed\n").unwrap();
+            if i % 2 == 0 {
+                index.add_path(name.as_ref()).unwrap();
+            }
+        }
+        index.write().unwrap();
+
+        let changed = names.iter().map(PathBuf::from).collect::<Vec<_>>();
+        let cache = DeltaCache::default();
+        let (unstaged, staged) = diff_unstaged_and_staged(repo, &changed, &cache).unwrap();
+
+        let cache = DeltaCache::default();
+        assert_eq!(
+            files(&unstaged),
+            files(&diff_unstaged(repo, &[], &cache).unwrap())
+        );
+        assert_eq!(
+            files(&staged),
+            files(&diff_staged(repo, &[], &cache).unwrap())
+        );
+        assert_eq!(unstaged.deltas.len(), 100);
+    }
+
+    #[test]
+    fn parallel_diff_finds_renames_across_chunks() {
+        let test = TestRepo::new();
+        let names = (0..200)
+            .map(|i| format!("file-{:03}", i))
+            .collect::<Vec<_>>();
+
+        for name in &names {
+            test.stage(name, &format!("original {}\n", name));
+        }
+        test.commit("init");
+
+        let (dir, repo) = (&test.dir, &test.repo);
+        let mut index = repo.index().unwrap();
+        for name in &names[1..] {
+            fs::write(dir.child(name), "changed\n").unwrap();
+        }
+        fs::rename(dir.child("file-000"), dir.child("renamed")).unwrap();
+        index.remove_path("file-000".as_ref()).unwrap();
+        index.add_path("renamed".as_ref()).unwrap();
+        index.write().unwrap();
+
+        let mut changed = names.iter().map(PathBuf::from).collect::<Vec<_>>();
+        changed.push(PathBuf::from("renamed"));
+        let (_, staged) = diff_unstaged_and_staged(repo, &changed, &DeltaCache::default()).unwrap();
+
+        assert_eq!(staged.deltas.len(), 1);
+        assert_eq!(staged.deltas[0].status, git2::Delta::Renamed);
+        assert_eq!(staged.deltas[0].old_file, PathBuf::from("file-000"));
+    }
+
+    #[test]
+    fn cached_deltas_are_rediffed_once_changed() {
+        let test = TestRepo::new();
+        for name in ["kept", "changed"] {
+            test.stage(name, "original\n");
+        }
+        let (dir, repo) = (&test.dir, &test.repo);
+        fs::write(dir.child("kept"), "kept\n").unwrap();
+        fs::write(dir.child("changed"), "once\n").unwrap();
+
+        let cache = DeltaCache::default();
+        let first = diff_unstaged(repo, &[], &cache).unwrap();
+        fs::write(dir.child("changed"), "twice\n").unwrap();
+        let second = diff_unstaged(repo, &[], &cache).unwrap();
+        let uncached = diff_unstaged(repo, &[], &DeltaCache::default()).unwrap();
+
+        let contents = |diff: &Diff| {
+            diff.deltas
+                .iter()
+                .map(|delta| {
+                    let hunks = delta.hunks.iter().map(|hunk| hunk.content.to_string());
+                    (delta.file_header.clone(), hunks.collect::<Vec<_>>())
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(contents(&second), contents(&uncached));
+        assert_ne!(contents(&first), contents(&second));
+        assert!(contents(&second)[0].1[0].contains("+twice"));
+    }
+
+    #[test]
+    fn worktree_apart_from_git_dir_is_kept() {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init_bare(dir.child("dotfiles.git")).unwrap();
+        fs::create_dir(dir.child("home")).unwrap();
+        repo.set_workdir(&dir.child("home"), false).unwrap();
+
+        let reopened = RepoDirs::of(&repo).open().unwrap();
+        assert!(!reopened.is_bare());
+        assert_eq!(reopened.workdir(), repo.workdir());
+
+        let mut cmd = git(["status"]);
+        let envs = in_repo(&mut cmd, &repo)
+            .get_envs()
+            .map(|(key, value)| (key.to_os_string(), value.map(OsStr::to_os_string)))
+            .collect::<Vec<_>>();
+        assert!(envs.contains(&("GIT_DIR".into(), Some(repo.path().into()))));
+    }
+
+    #[test]
+    fn invalid_utf8_in_paths_is_escaped() {
+        assert_eq!(display_path("café".as_ref()), "café");
+        if cfg!(unix) {
+            assert_eq!(display_path(&path_from_bytes(b"caf\xe9")), "caf\\351");
+        }
+    }
+}
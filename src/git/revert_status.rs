@@ -0,0 +1,3 @@
+pub(crate) struct RevertStatus {
+    pub head: String,
+}
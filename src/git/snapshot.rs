@@ -0,0 +1,168 @@
+use super::{git, in_repo};
+use crate::Res;
+use git2::{Oid, Repository, Signature};
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Snapshots of the worktree are commits on top of what HEAD was at the time.
+/// Only the latest is what the ref points to, its reflog keeps the others around.
+pub(crate) const SNAPSHOTS_REF: &str = "refs/gitu/snapshots";
+
+/// Where the worktree is staged into, so that the actual index is left as it is.
+const SNAPSHOT_INDEX: &str = "gitu-snapshot-index";
+
+pub(crate) struct Snapshot {
+    pub(crate) id: Oid,
+    pub(crate) message: String,
+    pub(crate) time: git2::Time,
+}
+
+/// Save the worktree, untracked files included, before something may throw changes away.
+/// Ignored files are left out, but for those in `ignored`.
+/// Nothing is saved if the worktree doesn't differ from HEAD.
+pub(crate) fn create(repo: &Repository, message: &str, ignored: &[PathBuf]) -> Res<Option<Oid>> {
+    if repo.is_bare() {
+        return Ok(None);
+    }
+
+    let tree = write_worktree_tree(repo, ignored)?;
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    if head.as_ref().is_some_and(|head| head.tree_id() == tree) {
+        return Ok(None);
+    }
+
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("gitu", "gitu@localhost"))?;
+    let id = repo.commit(
+        None,
+        &signature,
+        &signature,
+        message,
+        &repo.find_tree(tree)?,
+        &head.iter().collect::<Vec<_>>(),
+    )?;
+
+    // Refs outside of refs/heads don't get a reflog unless asked for
+    let mut cmd = git([
+        "update-ref",
+        "--create-reflog",
+        "-m",
+        message,
+        SNAPSHOTS_REF,
+    ]);
+    cmd.arg(id.to_string());
+    run(in_repo(&mut cmd, repo))?;
+
+    Ok(Some(id))
+}
+
+fn write_worktree_tree(repo: &Repository, ignored: &[PathBuf]) -> Res<Oid> {
+    let index = repo.path().join(SNAPSHOT_INDEX);
+    // Starting out from the real index saves git from hashing files that haven't changed
+    if let Err(err) = fs::copy(repo.path().join("index"), &index) {
+        log::debug!("Snapshot starts from an empty index: {}", err);
+    }
+
+    let result = (|| -> Res<Oid> {
+        run(in_repo(&mut git(["add", "--all"]), repo).env("GIT_INDEX_FILE", &index))?;
+        if !ignored.is_empty() {
+            let mut cmd = git(["add", "--force", "--"]);
+            cmd.args(ignored.iter().map(PathBuf::as_path).map(Path::as_os_str));
+            run(in_repo(&mut cmd, repo).env("GIT_INDEX_FILE", &index))?;
+        }
+        let output = run(in_repo(&mut git(["write-tree"]), repo).env("GIT_INDEX_FILE", &index))?;
+        Ok(Oid::from_str(output.trim())?)
+    })();
+
+    let _ = fs::remove_file(&index);
+    result
+}
+
+fn run(cmd: &mut Command) -> Res<String> {
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr)
+            .trim()
+            .to_string()
+            .into());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Every snapshot still kept, the latest first.
+/// They expire along with other unreachable reflog entries, see `gc.reflogExpireUnreachable`.
+pub(crate) fn list(repo: &Repository) -> Res<Vec<Snapshot>> {
+    Ok(repo
+        .reflog(SNAPSHOTS_REF)?
+        .iter()
+        .map(|entry| Snapshot {
+            id: entry.id_new(),
+            message: entry.message().unwrap_or("").to_string(),
+            time: entry.committer().when(),
+        })
+        .collect())
+}
+
+/// Bring back the files of a snapshot, the index isn't touched.
+pub(crate) fn restore_cmd(id: &OsStr) -> Command {
+    let mut cmd = git(["restore", "--worktree", "--source"]);
+    cmd.arg(id);
+    cmd.args(["--", "."]);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create, list};
+    use crate::git::test_repo::TestRepo;
+    use std::fs;
+
+    #[test]
+    fn snapshot_has_untracked_files_but_leaves_index_alone() {
+        let test = TestRepo::new();
+        let repo = &test.repo;
+        test.commit("init");
+
+        assert!(create(repo, "clean", &[]).unwrap().is_none());
+
+        fs::write(test.dir.child("untracked"), "precious\n").unwrap();
+        let id = create(repo, "before discarding", &[]).unwrap().unwrap();
+
+        let snapshot = repo.find_commit(id).unwrap();
+        assert!(snapshot
+            .tree()
+            .unwrap()
+            .get_path("untracked".as_ref())
+            .is_ok());
+        assert!(repo.index().unwrap().is_empty());
+
+        let snapshots = list(repo).unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].message, "before discarding");
+    }
+    #[test]
+    fn ignored_files_are_only_snapshotted_when_asked_for() {
+        let test = TestRepo::new();
+        let repo = &test.repo;
+        test.stage(".gitignore", "build\n");
+        test.commit("init");
+
+        fs::create_dir(test.dir.child("build")).unwrap();
+        fs::write(test.dir.child("build/out"), "built\n").unwrap();
+        assert!(create(repo, "without", &[]).unwrap().is_none());
+
+        let id = create(repo, "with", &["build".into()]).unwrap().unwrap();
+        let snapshot = repo.find_commit(id).unwrap();
+        assert!(snapshot
+            .tree()
+            .unwrap()
+            .get_path("build/out".as_ref())
+            .is_ok());
+    }
+}
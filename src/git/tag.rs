@@ -0,0 +1,195 @@
+use super::git;
+use crate::Res;
+use git2::Repository;
+
+const SIGNATURE_HEADERS: [&str; 3] = [
+    "-----BEGIN PGP SIGNATURE-----",
+    "-----BEGIN SSH SIGNATURE-----",
+    "-----BEGIN SIGNED MESSAGE-----",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Signature {
+    /// Verified, by whom if known
+    Good(Option<String>),
+    /// Why it couldn't be verified
+    Bad(String),
+}
+
+pub(crate) struct TagSummary {
+    pub name: String,
+    pub details: String,
+    pub signature: Option<Signature>,
+}
+
+/// Details of `reference` if it's an annotated tag, rather than a commit or lightweight tag.
+pub(crate) fn show_tag_summary(repo: &Repository, reference: &str) -> Res<Option<TagSummary>> {
+    let object = repo.revparse_single(reference)?;
+    let Some(tag) = object.as_tag() else {
+        return Ok(None);
+    };
+
+    let tagger = tag
+        .tagger()
+        .map(|tagger| {
+            format!(
+                "Tagger: {} <{}>\nDate:   {}\n\n",
+                tagger.name().unwrap_or(""),
+                tagger.email().unwrap_or(""),
+                super::format_time(tagger.when())
+            )
+        })
+        .unwrap_or_default();
+
+    let message = message_without_signature(tag.message().unwrap_or(""))
+        .lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let name = tag.name().unwrap_or("").to_string();
+    Ok(Some(TagSummary {
+        signature: verify(repo, &name)?,
+        details: format!("{}{}", tagger, message),
+        name,
+    }))
+}
+
+/// Checks the signature of tag `name`, if it has any.
+pub(crate) fn verify(repo: &Repository, name: &str) -> Res<Option<Signature>> {
+    let object = repo.revparse_single(&format!("refs/tags/{}", name))?;
+    let Some(message) = object.as_tag().and_then(|tag| tag.message()) else {
+        return Ok(None);
+    };
+
+    if !is_signed(message) {
+        return Ok(None);
+    }
+
+    let mut cmd = git(["verify-tag", "--raw", name]);
+    super::in_repo(&mut cmd, repo);
+    let output = cmd.output()?;
+
+    Ok(Some(parse_verification(
+        output.status.success(),
+        &String::from_utf8_lossy(&output.stderr),
+    )))
+}
+
+fn is_signed(message: &str) -> bool {
+    message
+        .lines()
+        .any(|line| SIGNATURE_HEADERS.contains(&line.trim_end()))
+}
+
+fn message_without_signature(message: &str) -> &str {
+    let end = SIGNATURE_HEADERS
+        .iter()
+        .filter_map(|header| message.find(header))
+        .min()
+        .unwrap_or(message.len());
+
+    message[..end].trim_end()
+}
+
+/// Makes sense of what `git verify-tag --raw` prints, for both GnuPG and ssh signatures.
+fn parse_verification(success: bool, output: &str) -> Signature {
+    let status = |keyword: &str| {
+        output.lines().find_map(|line| {
+            line.strip_prefix("[GNUPG:] ")?
+                .strip_prefix(keyword)
+                .map(str::trim)
+        })
+    };
+
+    if success {
+        let gpg_signer = status("GOODSIG ")
+            .and_then(|rest| rest.split_once(' '))
+            .map(|(_key_id, user_id)| user_id.to_string());
+
+        let ssh_signer = output.lines().find_map(|line| {
+            let rest = line.split_once(" signature for ")?.1;
+            Some(rest.split(" with ").next().unwrap_or(rest).to_string())
+        });
+
+        return Signature::Good(gpg_signer.or(ssh_signer));
+    }
+
+    let reason = [
+        ("BADSIG ", "bad signature"),
+        ("NO_PUBKEY ", "no public key"),
+        ("EXPKEYSIG ", "expired key"),
+        ("REVKEYSIG ", "revoked key"),
+        ("ERRSIG ", "couldn't verify"),
+    ]
+    .iter()
+    .find(|(keyword, _)| status(keyword).is_some())
+    .map(|(_, reason)| reason.to_string())
+    // ssh verifies the signature itself before looking up who made it
+    .or_else(|| {
+        output
+            .contains("No principal matched")
+            .then(|| "unknown signer".to_string())
+    })
+    .or_else(|| {
+        output
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty() && !line.starts_with("[GNUPG:]"))
+            .map(|line| line.trim().trim_end_matches('.').to_string())
+    })
+    .unwrap_or_else(|| "couldn't verify".to_string());
+
+    Signature::Bad(reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{message_without_signature, parse_verification, Signature};
+
+    #[test]
+    fn parse_gpg_and_ssh_verifications() {
+        assert_eq!(
+            parse_verification(
+                true,
+                "[GNUPG:] NEWSIG\n[GNUPG:] GOODSIG 0123456789ABCDEF CI <ci@example.com>\n"
+            ),
+            Signature::Good(Some("CI <ci@example.com>".into()))
+        );
+        assert_eq!(
+            parse_verification(
+                true,
+                "Good \"git\" signature for ci@example.com with ED25519 key SHA256:abc\n"
+            ),
+            Signature::Good(Some("ci@example.com".into()))
+        );
+        assert_eq!(
+            parse_verification(
+                false,
+                "[GNUPG:] ERRSIG 0123 1 10 00 1 9\n[GNUPG:] NO_PUBKEY 0123\n"
+            ),
+            Signature::Bad("no public key".into())
+        );
+        assert_eq!(
+            parse_verification(
+                false,
+                "Good \"git\" signature with ED25519 key SHA256:abc\nNo principal matched.\n"
+            ),
+            Signature::Bad("unknown signer".into())
+        );
+        assert_eq!(
+            parse_verification(false, "Couldn't load public key key.pub.\n"),
+            Signature::Bad("Couldn't load public key key.pub".into())
+        );
+    }
+
+    #[test]
+    fn strip_signature_from_message() {
+        assert_eq!(
+            message_without_signature(
+                "Release\n\nNotes\n-----BEGIN SSH SIGNATURE-----\nabc\n-----END SSH SIGNATURE-----\n"
+            ),
+            "Release\n\nNotes"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use git2::{Oid, Repository, Signature, Time};
+use std::{fs, path::Path};
+use temp_dir::TempDir;
+
+/// A repository in a temporary directory, to test against. Commits are by `CI` and are
+/// made on top of HEAD.
+pub(crate) struct TestRepo {
+    pub(crate) dir: TempDir,
+    pub(crate) repo: Repository,
+}
+
+impl TestRepo {
+    pub(crate) fn new() -> Self {
+        Self::at("")
+    }
+
+    /// With the repository at `path` in the temporary directory, which may hold others too.
+    pub(crate) fn at(path: &str) -> Self {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path().join(path)).unwrap();
+        Self { dir, repo }
+    }
+
+    /// Another repository in the same temporary directory.
+    pub(crate) fn init_other(&self, path: &str) -> Repository {
+        Repository::init(self.dir.path().join(path)).unwrap()
+    }
+
+    /// Writes `file` in the worktree and stages it.
+    pub(crate) fn stage(&self, file: &str, content: &str) {
+        fs::write(self.repo.workdir().unwrap().join(file), content).unwrap();
+        let mut index = self.repo.index().unwrap();
+        index.add_path(Path::new(file)).unwrap();
+        index.write().unwrap();
+    }
+
+    /// Commits what's staged.
+    pub(crate) fn commit(&self, message: &str) -> Oid {
+        self.commit_with(&Signature::now("CI", "ci@example.com").unwrap(), message)
+    }
+
+    /// Commits what's staged, by `author` at `seconds` since the epoch.
+    pub(crate) fn commit_as(&self, author: &str, seconds: i64, message: &str) -> Oid {
+        let signature = Signature::new(author, "ci@example.com", &Time::new(seconds, 0)).unwrap();
+        self.commit_with(&signature, message)
+    }
+
+    fn commit_with(&self, signature: &Signature, message: &str) -> Oid {
+        let repo = &self.repo;
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents = parent.iter().collect::<Vec<_>>();
+
+        repo.commit(Some("HEAD"), signature, signature, message, &tree, &parents)
+            .unwrap()
+    }
+}
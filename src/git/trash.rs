@@ -0,0 +1,262 @@
+use crate::Res;
+use chrono::{Local, NaiveDateTime};
+use git2::Repository;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Discarded files are kept in here, by when they were discarded and then their path in
+/// the worktree, like `.git/gitu-trash/2024-03-01_12-00-00.000/src/main.rs`.
+const TRASH_DIR: &str = "gitu-trash";
+
+/// Sorts in the order things were discarded, and is a valid file name everywhere.
+const STAMP_FORMAT: &str = "%Y-%m-%d_%H-%M-%S%.3f";
+
+#[derive(Clone, Debug)]
+pub(crate) struct Trashed {
+    /// Where it is in the trash
+    pub(crate) path: PathBuf,
+    /// Where it was in the worktree, relative to it
+    pub(crate) original: PathBuf,
+    pub(crate) discarded_at: String,
+}
+
+fn trash_dir(repo: &Repository) -> PathBuf {
+    repo.path().join(TRASH_DIR)
+}
+
+fn workdir(repo: &Repository) -> Res<&Path> {
+    Ok(repo
+        .workdir()
+        .ok_or("There's no worktree to trash files from")?)
+}
+
+/// Where a file of the worktree goes in the trash if discarded now.
+fn destination(repo: &Repository, file: &Path, retention_days: u64) -> Res<PathBuf> {
+    purge(repo, retention_days)?;
+
+    let stamp = Local::now().format(STAMP_FORMAT).to_string();
+    let path = trash_dir(repo).join(stamp).join(file);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    Ok(path)
+}
+
+/// Move an untracked file or directory into the trash, rather than deleting it.
+pub(crate) fn move_to_trash(repo: &Repository, file: &Path, retention_days: u64) -> Res<()> {
+    let source = workdir(repo)?.join(file);
+    let path = destination(repo, file, retention_days)?;
+    move_path(&source, &path)?;
+    Ok(())
+}
+
+/// Keep a copy of what's in the worktree before changes to it are thrown away.
+/// Does nothing if the file isn't there, as for a deleted file.
+pub(crate) fn copy_to_trash(repo: &Repository, file: &Path, retention_days: u64) -> Res<()> {
+    let source = workdir(repo)?.join(file);
+    if !source.is_file() {
+        return Ok(());
+    }
+
+    fs::copy(&source, destination(repo, file, retention_days)?)?;
+    Ok(())
+}
+
+/// The trash may be on another file system if the git dir is kept apart from the worktree.
+fn move_path(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    copy_and_remove(from, to)
+}
+
+fn copy_and_remove(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        copy_dir(from, to)?;
+        fs::remove_dir_all(from)
+    } else {
+        fs::copy(from, to)?;
+        fs::remove_file(from)
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let to = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to)?;
+        } else {
+            fs::copy(entry.path(), to)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Everything in the trash, the latest discarded first.
+pub(crate) fn list(repo: &Repository) -> Res<Vec<Trashed>> {
+    let mut stamps = match fs::read_dir(trash_dir(repo)) {
+        Ok(entries) => entries
+            .map(|entry| Ok(entry?.path()))
+            .collect::<io::Result<Vec<_>>>()?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => vec![],
+        Err(err) => return Err(err.into()),
+    };
+    stamps.sort();
+    stamps.reverse();
+
+    let mut trashed = vec![];
+    for stamp_dir in stamps {
+        let discarded_at = stamp_dir
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        let mut files = vec![];
+        collect_files(&stamp_dir, &mut files)?;
+        files.sort();
+
+        trashed.extend(files.into_iter().map(|path| Trashed {
+            original: path.strip_prefix(&stamp_dir).unwrap().to_path_buf(),
+            path,
+            discarded_at: discarded_at.clone(),
+        }));
+    }
+
+    Ok(trashed)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Put a file back where it was. Whatever is there now goes into the trash in its place.
+pub(crate) fn restore(repo: &Repository, trashed: &Trashed, retention_days: u64) -> Res<()> {
+    let original = workdir(repo)?.join(&trashed.original);
+    if original.exists() {
+        move_to_trash(repo, &trashed.original, retention_days)?;
+    } else if let Some(parent) = original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    move_path(&trashed.path, &original)?;
+    remove_empty_dirs(&trash_dir(repo).join(&trashed.discarded_at))?;
+    Ok(())
+}
+
+fn remove_empty_dirs(dir: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path)?;
+        }
+    }
+
+    if fs::read_dir(dir)?.next().is_none() {
+        fs::remove_dir(dir)?;
+    }
+
+    Ok(())
+}
+
+/// Delete what was discarded longer than `retention_days` ago, 0 keeps everything.
+fn purge(repo: &Repository, retention_days: u64) -> Res<()> {
+    if retention_days == 0 {
+        return Ok(());
+    }
+
+    let Ok(entries) = fs::read_dir(trash_dir(repo)) else {
+        return Ok(());
+    };
+
+    let cutoff = Local::now().naive_local()
+        - chrono::TimeDelta::try_days(retention_days as i64).ok_or("Retention is too long")?;
+    for entry in entries {
+        let path = entry?.path();
+        let discarded_at = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| NaiveDateTime::parse_from_str(name, STAMP_FORMAT).ok());
+
+        // Anything that wasn't put there by gitu is left alone
+        if discarded_at.is_some_and(|discarded_at| discarded_at < cutoff) {
+            fs::remove_dir_all(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{copy_and_remove, copy_to_trash, list, move_to_trash, restore, trash_dir};
+    use crate::git::test_repo::TestRepo;
+    use std::{fs, path::Path};
+
+    #[test]
+    fn trashed_file_can_be_restored() {
+        let TestRepo { dir, repo } = TestRepo::new();
+        fs::create_dir(dir.child("src")).unwrap();
+        fs::write(dir.child("src/untracked"), "precious\n").unwrap();
+
+        move_to_trash(&repo, "src/untracked".as_ref(), 0).unwrap();
+        assert!(!dir.child("src/untracked").exists());
+
+        let trashed = list(&repo).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].original, Path::new("src/untracked"));
+
+        restore(&repo, &trashed[0], 0).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.child("src/untracked")).unwrap(),
+            "precious\n"
+        );
+        assert!(list(&repo).unwrap().is_empty());
+    }
+
+    #[test]
+    fn directories_are_moved_by_copying_too() {
+        let TestRepo { dir, .. } = TestRepo::new();
+        fs::create_dir_all(dir.child("build/nested")).unwrap();
+        fs::write(dir.child("build/nested/output"), "built\n").unwrap();
+
+        copy_and_remove(&dir.child("build"), &dir.child("moved")).unwrap();
+        assert!(!dir.child("build").exists());
+        assert_eq!(
+            fs::read_to_string(dir.child("moved/nested/output")).unwrap(),
+            "built\n"
+        );
+    }
+
+    #[test]
+    fn old_trash_is_purged() {
+        let TestRepo { dir, repo } = TestRepo::new();
+        let old = trash_dir(&repo).join("2001-01-01_00-00-00.000");
+        fs::create_dir_all(&old).unwrap();
+        fs::write(old.join("file"), "old\n").unwrap();
+        fs::write(dir.child("file"), "new\n").unwrap();
+
+        copy_to_trash(&repo, "file".as_ref(), 30).unwrap();
+
+        assert!(!old.exists());
+        let trashed = list(&repo).unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(fs::read_to_string(&trashed[0].path).unwrap(), "new\n");
+    }
+}
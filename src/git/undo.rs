@@ -0,0 +1,131 @@
+use super::git;
+use crate::Res;
+use git2::{Oid, Repository, RepositoryState};
+use std::{ffi::OsStr, process::Command};
+
+/// Undoing is a reset like any other, what sets it apart is this in its reflog message.
+/// This is how later undos know to skip over what was undone already.
+const UNDO_REFLOG_ACTION: &str = "gitu undo";
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Undo {
+    /// The branch that moved, or HEAD if detached
+    pub(crate) reference: String,
+    /// What the reflog says happened
+    pub(crate) message: String,
+    pub(crate) from: Oid,
+    pub(crate) to: Oid,
+}
+
+/// The last move of the current branch that wasn't undone already.
+/// The branch's own reflog is used rather than HEAD's, as that has every step of a rebase,
+/// and checkouts which aren't worth undoing.
+pub(crate) fn last_operation(repo: &Repository) -> Res<Option<Undo>> {
+    if repo.state() != RepositoryState::Clean {
+        return Err("Finish or abort what's in progress before undoing".into());
+    }
+
+    if super::unborn_branch(repo).is_some() {
+        return Err("There are no commits to undo yet".into());
+    }
+
+    let head = repo.head()?;
+    let (name, reference) = if head.is_branch() {
+        (
+            head.name()
+                .ok_or("Branch name isn't valid utf-8")?
+                .to_string(),
+            head.shorthand().unwrap_or("HEAD").to_string(),
+        )
+    } else {
+        ("HEAD".to_string(), "HEAD".to_string())
+    };
+
+    let reflog = repo.reflog(&name)?;
+    let entries = reflog
+        .iter()
+        .map(|entry| {
+            (
+                entry.message().unwrap_or("").to_string(),
+                entry.id_old(),
+                entry.id_new(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(undoable(&entries).map(|(message, from, to)| Undo {
+        reference,
+        message: message.to_string(),
+        from: *from,
+        to: *to,
+    }))
+}
+
+/// The entry to undo out of reflog entries (latest first), as (message, old, new).
+/// Every undo skips one more entry, so that undoing repeatedly goes further back.
+fn undoable(entries: &[(String, Oid, Oid)]) -> Option<(&str, &Oid, &Oid)> {
+    let mut undone = 0;
+
+    for (message, old, new) in entries {
+        if message.starts_with(UNDO_REFLOG_ACTION) {
+            undone += 1;
+        } else if undone > 0 {
+            undone -= 1;
+        } else if old.is_zero() {
+            // The branch was created here, there's nothing before it
+            return None;
+        } else {
+            return Some((message, new, old));
+        }
+    }
+
+    None
+}
+
+/// Move back, keeping uncommitted changes. Git refuses if they'd conflict.
+pub(crate) fn undo_cmd(to: &OsStr) -> Command {
+    let mut cmd = git(["reset", "--keep"]);
+    cmd.arg(to);
+    cmd.env("GIT_REFLOG_ACTION", UNDO_REFLOG_ACTION);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::undoable;
+    use git2::Oid;
+
+    fn oid(n: u8) -> Oid {
+        Oid::from_bytes(&[n; 20]).unwrap()
+    }
+
+    fn entry(message: &str, old: u8, new: u8) -> (String, Oid, Oid) {
+        (message.to_string(), oid(old), oid(new))
+    }
+
+    #[test]
+    fn undoing_again_goes_further_back() {
+        let entries = [entry("commit: second", 1, 2), entry("commit: first", 0, 1)];
+        assert_eq!(
+            undoable(&entries),
+            Some(("commit: second", &oid(2), &oid(1)))
+        );
+
+        let undone = [
+            entry("gitu undo: updating HEAD", 3, 2),
+            entry("commit (amend): third", 2, 3),
+            entry("commit: second", 1, 2),
+            entry("branch: Created from HEAD", 0, 1),
+        ];
+        assert_eq!(
+            undoable(&undone),
+            Some(("commit: second", &oid(2), &oid(1)))
+        );
+    }
+
+    #[test]
+    fn creating_the_branch_isnt_undone() {
+        let entries = [entry("branch: Created from HEAD", 0, 1)];
+        assert_eq!(undoable(&entries), None);
+    }
+}
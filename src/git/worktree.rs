@@ -0,0 +1,94 @@
+use crate::Res;
+use git2::Repository;
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Worktree {
+    pub(crate) path: PathBuf,
+    /// Short name of the checked out branch, `None` if HEAD is detached
+    pub(crate) branch: Option<String>,
+    pub(crate) head: Option<String>,
+    pub(crate) bare: bool,
+}
+
+/// Every worktree of the repository, the main one first.
+/// libgit2 only knows about linked worktrees, so this asks git itself.
+pub(crate) fn list(repo: &Repository) -> Res<Vec<Worktree>> {
+    let output =
+        super::in_repo(&mut super::git(["worktree", "list", "--porcelain"]), repo).output()?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr)
+            .trim()
+            .to_string()
+            .into());
+    }
+
+    Ok(parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse(porcelain: &str) -> Vec<Worktree> {
+    porcelain
+        .split("\n\n")
+        .filter_map(|record| {
+            let mut lines = record.lines();
+            let path = PathBuf::from(lines.next()?.strip_prefix("worktree ")?);
+            let mut worktree = Worktree {
+                path,
+                branch: None,
+                head: None,
+                bare: false,
+            };
+
+            for line in lines {
+                if let Some(head) = line.strip_prefix("HEAD ") {
+                    worktree.head = Some(head.to_string());
+                } else if let Some(branch) = line.strip_prefix("branch ") {
+                    let branch = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+                    worktree.branch = Some(branch.to_string());
+                } else if line == "bare" {
+                    worktree.bare = true;
+                }
+            }
+
+            Some(worktree)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Worktree};
+    use std::path::PathBuf;
+
+    #[test]
+    fn parse_porcelain() {
+        let porcelain = "worktree /src/repo\n\
+            HEAD 1234\n\
+            branch refs/heads/main\n\
+            \n\
+            worktree /src/repo-fix\n\
+            HEAD 5678\n\
+            detached\n\
+            locked\n\
+            \n";
+
+        assert_eq!(
+            parse(porcelain),
+            [
+                Worktree {
+                    path: PathBuf::from("/src/repo"),
+                    branch: Some("main".to_string()),
+                    head: Some("1234".to_string()),
+                    bare: false,
+                },
+                Worktree {
+                    path: PathBuf::from("/src/repo-fix"),
+                    branch: None,
+                    head: Some("5678".to_string()),
+                    bare: false,
+                },
+            ]
+        );
+    }
+}
@@ -1,17 +1,38 @@
-use crate::Res;
+use crate::{
+    config::{Config, UntrackedFiles},
+    Res,
+};
 use git2::{DiffOptions, Repository, StatusOptions};
 
-pub(crate) fn status(repo: &Repository) -> Res<StatusOptions> {
+pub(crate) fn status(untracked: UntrackedFiles) -> StatusOptions {
     let mut opts = StatusOptions::new();
 
-    opts.include_untracked(
-        repo.config()?
-            .get_bool("status.showUntrackedFiles")
-            .ok()
-            .unwrap_or(true),
-    );
+    opts.include_untracked(untracked != UntrackedFiles::No);
+    opts.recurse_untracked_dirs(untracked == UntrackedFiles::All);
+    // Ignored trees are never shown, so there's no need to walk them
+    opts.include_ignored(false);
+    opts.recurse_ignored_dirs(false);
 
-    Ok(opts)
+    opts
+}
+
+/// The configured untracked files mode, defaulting to that of git.
+pub(crate) fn untracked_files(config: &Config, repo: &Repository) -> Res<UntrackedFiles> {
+    if let Some(untracked) = config.general.untracked_files {
+        return Ok(untracked);
+    }
+
+    let git_config = repo.config()?;
+    Ok(
+        match git_config.get_string("status.showUntrackedFiles").ok() {
+            Some(value) if value == "all" => UntrackedFiles::All,
+            Some(value) if value == "normal" => UntrackedFiles::Normal,
+            _ => match git_config.get_bool("status.showUntrackedFiles") {
+                Ok(false) => UntrackedFiles::No,
+                _ => UntrackedFiles::Normal,
+            },
+        },
+    )
 }
 
 pub(crate) fn diff(_repo: &Repository) -> Res<DiffOptions> {
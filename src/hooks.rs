@@ -0,0 +1,60 @@
+use crate::config::HooksConfig;
+use std::{path::Path, process::Command};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Hook {
+    AfterCommit,
+    AfterPush,
+    OnRefresh,
+}
+
+impl Hook {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Hook::AfterCommit => "after_commit",
+            Hook::AfterPush => "after_push",
+            Hook::OnRefresh => "on_refresh",
+        }
+    }
+
+    pub(crate) fn commands<'a>(&self, config: &'a HooksConfig) -> &'a [String] {
+        match self {
+            Hook::AfterCommit => &config.after_commit,
+            Hook::AfterPush => &config.after_push,
+            Hook::OnRefresh => &config.on_refresh,
+        }
+    }
+}
+
+pub(crate) fn hook_cmd(hook: Hook, command: &str, dir: &Path) -> Command {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+
+    cmd.arg(command);
+    cmd.current_dir(dir);
+    cmd.env("GITU_HOOK", hook.name());
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hook_cmd, Hook};
+    use std::env;
+
+    #[cfg(unix)]
+    #[test]
+    fn hook_name_is_passed_to_command() {
+        let output = hook_cmd(Hook::AfterPush, "echo \"$GITU_HOOK\"", &env::temp_dir())
+            .output()
+            .unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "after_push");
+    }
+}
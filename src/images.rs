@@ -0,0 +1,304 @@
+use crate::{
+    git::diff::Delta,
+    term::{self, Term, TermBackend},
+    Res,
+};
+use crossterm::{cursor::MoveTo, QueueableCommand};
+use git2::{Oid, Repository};
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+};
+
+/// How many lines of a diff the thumbnails of an image take up.
+pub(crate) const IMAGE_ROWS: u16 = 8;
+
+/// Base64 is sent in chunks of at most this many bytes, as kitty asks for.
+const KITTY_CHUNK: usize = 4096;
+
+/// Kitty's graphics protocol takes PNG as it is, iTerm2's inline images take most formats.
+/// Neither needs images decoded to pixels, which sixel does, so sixel terminals and any
+/// others are shown the plain line for binary files instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageProtocol {
+    Kitty,
+    Iterm2,
+}
+
+impl ImageProtocol {
+    /// Querying the terminal would mean waiting on its answer, or its silence, at startup.
+    /// What terminals set in the environment says just as much.
+    pub(crate) fn detect() -> Option<Self> {
+        let var = |name| env::var(name).unwrap_or_default();
+
+        // Multiplexers don't pass graphics through unless asked to, keeping them in place
+        // isn't feasible either
+        if env::var_os("TMUX").is_some() || var("TERM").starts_with("screen") {
+            return None;
+        }
+
+        if var("TERM") == "xterm-kitty"
+            || env::var_os("KITTY_WINDOW_ID").is_some()
+            || var("TERM_PROGRAM") == "ghostty"
+        {
+            Some(ImageProtocol::Kitty)
+        } else if matches!(var("TERM_PROGRAM").as_str(), "iTerm.app" | "WezTerm")
+            || env::var_os("ITERM_SESSION_ID").is_some()
+        {
+            Some(ImageProtocol::Iterm2)
+        } else {
+            None
+        }
+    }
+
+    fn shows(self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match self {
+            ImageProtocol::Kitty => extension == "png",
+            ImageProtocol::Iterm2 => matches!(
+                extension.as_str(),
+                "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tif" | "tiff" | "webp"
+            ),
+        }
+    }
+}
+
+/// One side of a changed image. Unstaged changes aren't in the object database,
+/// they're read from the worktree instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ImageFile {
+    id: Oid,
+    path: PathBuf,
+}
+
+impl ImageFile {
+    fn load(&self, repo: &Repository) -> Option<Vec<u8>> {
+        if let Ok(blob) = repo.find_blob(self.id) {
+            return Some(blob.content().to_vec());
+        }
+
+        fs::read(repo.workdir()?.join(&self.path)).ok()
+    }
+}
+
+/// Thumbnails of an image before and after it changed, side by side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct InlineImage {
+    before: Option<ImageFile>,
+    after: Option<ImageFile>,
+}
+
+impl InlineImage {
+    pub(crate) fn of_delta(protocol: ImageProtocol, delta: &Delta) -> Option<Self> {
+        if !delta.binary || !protocol.shows(&delta.new_file) {
+            return None;
+        }
+
+        let is_new = matches!(
+            delta.status,
+            git2::Delta::Added | git2::Delta::Untracked | git2::Delta::Copied
+        );
+        let is_deleted = delta.status == git2::Delta::Deleted;
+
+        Some(Self {
+            before: (!is_new).then(|| ImageFile {
+                id: delta.old_id,
+                path: delta.old_file.clone(),
+            }),
+            after: (!is_deleted).then(|| ImageFile {
+                id: delta.new_id,
+                path: delta.new_file.clone(),
+            }),
+        })
+    }
+
+    /// Where to draw the thumbnails, with their top left corner at `x`, `y`.
+    pub(crate) fn placements(&self, x: u16, y: u16, width: u16) -> Vec<Placement> {
+        let cols = (width / 2).saturating_sub(1);
+
+        [(&self.before, x), (&self.after, x + width / 2)]
+            .into_iter()
+            .filter_map(|(image, x)| {
+                Some(Placement {
+                    x,
+                    y,
+                    cols,
+                    image: image.clone()?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Placement {
+    x: u16,
+    y: u16,
+    /// How wide the thumbnail may be, it's `IMAGE_ROWS` high at most
+    cols: u16,
+    image: ImageFile,
+}
+
+/// Draw thumbnails over the blank lines kept for them, once the rest of the frame is drawn.
+/// Kitty keeps images apart from text, so the previous ones are deleted first.
+pub(crate) fn draw(
+    term: &mut Term,
+    protocol: ImageProtocol,
+    repo: &Repository,
+    placements: &[Placement],
+) -> Res<()> {
+    if !matches!(term.backend(), TermBackend::Crossterm(_)) {
+        return Ok(());
+    }
+
+    let mut stderr = io::stderr();
+    if protocol == ImageProtocol::Kitty {
+        write!(stderr, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+    }
+
+    let cell = cell_size();
+    for placement in placements {
+        let Some(bytes) = placement.image.load(repo) else {
+            continue;
+        };
+
+        let (cols, rows) = fit(image_size(&bytes), cell, placement.cols, IMAGE_ROWS);
+        stderr.queue(MoveTo(placement.x, placement.y))?;
+        stderr.write_all(escape(protocol, &bytes, cols, rows).as_bytes())?;
+    }
+
+    stderr.flush()?;
+    Ok(())
+}
+
+fn escape(protocol: ImageProtocol, bytes: &[u8], cols: u16, rows: u16) -> String {
+    let data = term::base64(bytes);
+
+    match protocol {
+        ImageProtocol::Kitty => {
+            let chunks = data.as_bytes().chunks(KITTY_CHUNK).collect::<Vec<_>>();
+            let mut escape = String::new();
+
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(i + 1 < chunks.len());
+                let chunk = std::str::from_utf8(chunk).unwrap();
+
+                // Not moving the cursor (C=1) keeps what ratatui knows about it true
+                let _ = if i == 0 {
+                    write!(
+                        escape,
+                        "\x1b_Ga=T,f=100,t=d,q=2,C=1,c={},r={},m={};{}\x1b\\",
+                        cols, rows, more, chunk
+                    )
+                } else {
+                    write!(escape, "\x1b_Gm={};{}\x1b\\", more, chunk)
+                };
+            }
+
+            escape
+        }
+        ImageProtocol::Iterm2 => format!(
+            "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+            bytes.len(),
+            cols,
+            rows,
+            data
+        ),
+    }
+}
+
+/// The size of a cell in pixels, if the terminal tells.
+fn cell_size() -> Option<(u16, u16)> {
+    let size = crossterm::terminal::window_size().ok()?;
+    if size.columns == 0 || size.rows == 0 || size.width == 0 || size.height == 0 {
+        return None;
+    }
+
+    Some((size.width / size.columns, size.height / size.rows))
+}
+
+/// Width and height in pixels, read from the header of a PNG.
+fn image_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    if bytes.len() < 24 || !bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return None;
+    }
+
+    let width = u32::from_be_bytes(bytes[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(bytes[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+/// The cells an image takes up when scaled down to fit `max_cols` and `max_rows`, keeping
+/// its aspect ratio. Small images aren't scaled up. Without sizes to go by, it fills them.
+fn fit(
+    image: Option<(u32, u32)>,
+    cell: Option<(u16, u16)>,
+    max_cols: u16,
+    max_rows: u16,
+) -> (u16, u16) {
+    let (Some((width, height)), Some((cell_width, cell_height))) = (image, cell) else {
+        return (max_cols, max_rows);
+    };
+    if width == 0 || height == 0 || cell_width == 0 || cell_height == 0 {
+        return (max_cols, max_rows);
+    }
+
+    let scale = 1f64
+        .min(f64::from(max_cols) * f64::from(cell_width) / f64::from(width))
+        .min(f64::from(max_rows) * f64::from(cell_height) / f64::from(height));
+    let cells = |pixels: u32, cell: u16, max: u16| {
+        ((f64::from(pixels) * scale / f64::from(cell)).ceil() as u16).clamp(1, max.max(1))
+    };
+
+    (
+        cells(width, cell_width, max_cols),
+        cells(height, cell_height, max_rows),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, fit, image_size, ImageProtocol, KITTY_CHUNK};
+
+    #[test]
+    fn thumbnails_keep_aspect_ratio() {
+        // 10x20 pixel cells, 40x8 cells to fit in
+        assert_eq!(
+            fit(Some((800, 800)), Some((10, 20)), 40, 8),
+            (16, 8),
+            "scaled down to the height"
+        );
+        assert_eq!(fit(Some((20, 20)), Some((10, 20)), 40, 8), (2, 1));
+        assert_eq!(fit(None, Some((10, 20)), 40, 8), (40, 8));
+    }
+
+    #[test]
+    fn png_size_is_read_from_its_header() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+
+        assert_eq!(image_size(&png), Some((640, 480)));
+        assert_eq!(image_size(b"GIF89a"), None);
+    }
+
+    #[test]
+    fn kitty_images_are_sent_in_chunks() {
+        let escape = escape(ImageProtocol::Kitty, &[0; KITTY_CHUNK], 4, 2);
+        let chunks = escape.split("\x1b\\").filter(|chunk| !chunk.is_empty());
+
+        assert_eq!(
+            chunks
+                .map(|chunk| chunk.split(';').next().unwrap())
+                .collect::<Vec<_>>(),
+            ["\x1b_Ga=T,f=100,t=d,q=2,C=1,c=4,r=2,m=1", "\x1b_Gm=0"]
+        );
+    }
+}
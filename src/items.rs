@@ -1,10 +1,17 @@
 use crate::config::Config;
+use crate::config::StyleConfig;
+use crate::forge::PullRequest;
+use crate::git;
+use crate::git::blame::BlameLine;
 use crate::git::diff::Delta;
 use crate::git::diff::Diff;
 use crate::git::diff::Hunk;
-use crate::Res;
-use git2::Commit;
-use git2::Repository;
+use crate::git::log::LogEntry;
+use crate::git::log::LogFilter;
+use crate::git::log::RefKind;
+use crate::git::trash::Trashed;
+use crate::images::InlineImage;
+use crate::images::IMAGE_ROWS;
 use ratatui::style::Style;
 use ratatui::text::Line;
 use ratatui::text::Span;
@@ -12,9 +19,17 @@ use similar::Algorithm;
 use similar::ChangeTag;
 use similar::TextDiff;
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
 use std::iter;
+use std::ops::Range;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Default, Clone, Debug)]
 pub(crate) struct Item {
@@ -25,64 +40,277 @@ pub(crate) struct Item {
     pub(crate) depth: usize,
     pub(crate) unselectable: bool,
     pub(crate) target_data: Option<TargetData>,
+    pub(crate) lazy_children: Option<LazyItems>,
+    /// Drawn instead of `display` when set.
+    pub(crate) raw_line: Option<RawDiffLine>,
+    /// Drawn over this line and the blank ones after it, by terminals that can.
+    pub(crate) image: Option<InlineImage>,
+}
+
+impl Item {
+    /// Moved `by` levels deeper, along with its children once they're created.
+    pub(crate) fn deepen(mut self, by: usize) -> Self {
+        if by == 0 {
+            return self;
+        }
+
+        self.depth += by;
+        self.lazy_children = self.lazy_children.map(|children| {
+            LazyItems(Rc::new(move || {
+                (children.0)()
+                    .into_iter()
+                    .map(|child| child.deepen(by))
+                    .collect()
+            }))
+        });
+        self
+    }
+
+    /// The line to draw, formatting raw diff lines only now.
+    pub(crate) fn line(&self, style: &StyleConfig) -> Cow<'_, Line<'static>> {
+        match &self.raw_line {
+            Some(raw_line) => Cow::Owned(raw_line.format(style)),
+            None => Cow::Borrowed(&self.display),
+        }
+    }
+}
+
+/// A line of a huge hunk. It's kept as a range of the hunk's content rather than as
+/// formatted text, which would take many times the memory of the diff itself.
+#[derive(Clone, Debug)]
+pub(crate) struct RawDiffLine {
+    content: Arc<str>,
+    range: Range<usize>,
+}
+
+impl RawDiffLine {
+    fn format(&self, style: &StyleConfig) -> Line<'static> {
+        let line = &self.content[self.range.clone()];
+        let line_style: Style = match line.chars().next() {
+            Some('+') => (&style.line_added).into(),
+            Some('-') => (&style.line_removed).into(),
+            _ => Style::new(),
+        };
+
+        Line::styled(line.to_string(), line_style)
+    }
+}
+
+/// Child items of a section that are only created once the section is expanded, and scrolled to.
+/// A status screen with thousands of files then won't diff and format each of them.
+#[derive(Clone)]
+pub(crate) struct LazyItems(pub(crate) Rc<dyn Fn() -> Vec<Item>>);
+
+impl fmt::Debug for LazyItems {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LazyItems")
+    }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) enum TargetData {
+    BlameLine(BlameLine),
     Branch(String),
     Commit(String),
     Delta(Delta),
     File(PathBuf),
     Hunk(Hunk),
+    LogFilter(LogFilter),
+    PullRequest(PullRequest),
+    Repository(PathBuf),
+    Snapshot(String),
+    Submodule(PathBuf),
+    Tag(String),
+    Trashed(Trashed),
+    Worktree(PathBuf),
+}
+
+/// Keeps the formatted lines of hunks between refreshes, so that only hunks that actually
+/// changed get diffed and highlighted again.
+/// Hunks are keyed by their patch, which includes the file header with paths and blob ids.
+#[derive(Default)]
+pub(crate) struct DiffCache {
+    current: RefCell<HunkLines>,
+    previous: RefCell<HunkLines>,
+}
+
+/// Formatted lines by (depth, patch)
+type HunkLines = HashMap<(usize, String), Rc<[Item]>>;
+
+impl DiffCache {
+    /// Holds on to the entries used since the last call, dropping any others.
+    pub(crate) fn start_refresh(&self) {
+        self.previous.replace(self.current.take());
+    }
+
+    fn get_or_insert_with<F: FnOnce() -> Vec<Item>>(
+        &self,
+        key: (usize, String),
+        render: F,
+    ) -> Rc<[Item]> {
+        if let Some(items) = self.current.borrow().get(&key) {
+            return Rc::clone(items);
+        }
+
+        let items = self
+            .previous
+            .borrow_mut()
+            .remove(&key)
+            .unwrap_or_else(|| render().into());
+
+        self.current.borrow_mut().insert(key, Rc::clone(&items));
+        items
+    }
 }
 
 pub(crate) fn create_diff_items<'a>(
     config: Rc<Config>,
+    cache: &'a Rc<DiffCache>,
     diff: &'a Diff,
     depth: &'a usize,
     default_collapsed: bool,
 ) -> impl Iterator<Item = Item> + 'a {
-    diff.deltas.iter().flat_map(move |delta| {
+    diff.deltas.iter().map(move |delta| {
         let target_data = TargetData::Delta(delta.clone());
-        let config = Rc::clone(&config);
+        let hunk_items = {
+            let config = Rc::clone(&config);
+            let cache = Rc::clone(cache);
+            let delta = delta.clone();
+            let depth = *depth;
+
+            LazyItems(Rc::new(move || {
+                if delta.binary {
+                    return create_binary_items(&config, &delta, depth + 1);
+                }
 
-        iter::once(Item {
+                delta
+                    .hunks
+                    .iter()
+                    .flat_map(|hunk| create_hunk_items(&config, &cache, hunk, depth + 1))
+                    .collect()
+            }))
+        };
+
+        Item {
             id: delta.file_header.to_string().into(),
             display: Line::styled(
-                format!(
-                    "{}   {}",
-                    format!("{:?}", delta.status).to_lowercase(),
-                    delta.new_file.to_string_lossy()
-                ),
+                if delta.old_file == delta.new_file {
+                    format!(
+                        "{}   {}",
+                        format!("{:?}", delta.status).to_lowercase(),
+                        git::display_path(&delta.new_file)
+                    )
+                } else {
+                    format!(
+                        "{}   {} -> {}",
+                        format!("{:?}", delta.status).to_lowercase(),
+                        git::display_path(&delta.old_file),
+                        git::display_path(&delta.new_file)
+                    )
+                },
                 &config.style.file_header,
             ),
             section: true,
             default_collapsed,
             depth: *depth,
             target_data: Some(target_data),
+            lazy_children: Some(hunk_items),
             ..Default::default()
-        })
-        .chain(
-            delta
-                .hunks
-                .iter()
-                .flat_map(move |hunk| create_hunk_items(Rc::clone(&config), hunk, *depth + 1)),
-        )
+        }
     })
 }
 
-fn create_hunk_items(config: Rc<Config>, hunk: &Hunk, depth: usize) -> impl Iterator<Item = Item> {
+/// Binary files have no hunks to show. Changed images are shown as thumbnails where the
+/// terminal can, on blank lines that are kept for them.
+fn create_binary_items(config: &Config, delta: &Delta, depth: usize) -> Vec<Item> {
+    let image = config
+        .image_protocol
+        .and_then(|protocol| InlineImage::of_delta(protocol, delta));
+    let rows = if image.is_some() { IMAGE_ROWS } else { 0 };
+
+    iter::once(Item {
+        id: format!("{}binary", delta.file_header).into(),
+        display: Line::raw("Binary file changed"),
+        depth,
+        unselectable: true,
+        ..Default::default()
+    })
+    .chain((0..rows).map(|row| Item {
+        id: format!("{}image_{}", delta.file_header, row).into(),
+        display: Line::raw(""),
+        depth,
+        unselectable: true,
+        image: if row == 0 { image.clone() } else { None },
+        ..Default::default()
+    }))
+    .collect()
+}
+
+/// Hunks longer than this are collapsed, and their lines drawn without highlighting.
+const LARGE_HUNK_LINES: usize = 2000;
+
+fn create_hunk_items(config: &Config, cache: &DiffCache, hunk: &Hunk, depth: usize) -> Vec<Item> {
     let target_data = TargetData::Hunk(hunk.clone());
 
+    if hunk.content.lines().count() > LARGE_HUNK_LINES {
+        return vec![create_large_hunk_item(config, hunk, depth)];
+    }
+
+    let patch = hunk.format_patch();
+    let lines = cache.get_or_insert_with((depth, patch.clone()), || {
+        format_diff_hunk_items(config, depth + 1, hunk).collect()
+    });
+
     iter::once(Item {
-        id: hunk.format_patch().into(),
+        id: patch.into(),
         display: Line::styled(hunk.header.clone(), &config.style.hunk_header),
         section: true,
         depth,
         target_data: Some(target_data),
         ..Default::default()
     })
-    .chain(format_diff_hunk_items(&config, depth + 1, hunk))
+    .chain(lines.iter().cloned())
+    .collect()
+}
+
+/// Lines of a large hunk are only created when it's expanded, and are not diffed word by word.
+fn create_large_hunk_item(config: &Config, hunk: &Hunk, depth: usize) -> Item {
+    // The patch could be megabytes, a hash of it does just as well for telling hunks apart
+    let mut hasher = DefaultHasher::new();
+    hunk.format_patch().hash(&mut hasher);
+
+    let content = Arc::clone(&hunk.content);
+    let lines = LazyItems(Rc::new(move || {
+        let mut start = 0;
+        content
+            .split_inclusive('\n')
+            .map(|line| {
+                let range = start..start + line.trim_end_matches('\n').len();
+                start += line.len();
+
+                Item {
+                    unselectable: true,
+                    depth: depth + 1,
+                    raw_line: Some(RawDiffLine {
+                        content: Arc::clone(&content),
+                        range,
+                    }),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }));
+
+    Item {
+        id: format!("{}{}{:x}", hunk.file_header, hunk.header, hasher.finish()).into(),
+        display: Line::styled(hunk.header.clone(), &config.style.hunk_header),
+        section: true,
+        default_collapsed: true,
+        depth,
+        target_data: Some(TargetData::Hunk(hunk.clone())),
+        lazy_children: Some(lines),
+        ..Default::default()
+    }
 }
 
 fn format_diff_hunk_items(
@@ -160,84 +388,33 @@ fn format_changes(
     lines
 }
 
-pub(crate) fn log(
-    config: &Config,
-    repo: &Repository,
-    limit: usize,
-    reference: Option<String>,
-) -> Res<Vec<Item>> {
+pub(crate) fn log_entry(config: &Config, entry: &LogEntry) -> Item {
     let style = &config.style;
-    let mut revwalk = repo.revwalk()?;
-    if let Some(r) = reference {
-        let oid = repo.revparse_single(&r)?.id();
-        revwalk.push(oid)?;
-    } else if revwalk.push_head().is_err() {
-        return Ok(vec![]);
-    }
 
-    let references = repo
-        .references()?
-        .filter_map(Result::ok)
-        .filter_map(
-            |reference| match (reference.peel_to_commit(), reference.shorthand()) {
-                (Ok(target), Some(name)) => {
-                    if name.ends_with("/HEAD") {
-                        return None;
-                    }
-
-                    let style: Style = if reference.is_remote() {
-                        &style.remote
-                    } else if reference.is_tag() {
-                        &style.tag
-                    } else {
-                        &style.branch
-                    }
-                    .into();
-
-                    Some((target, Span::styled(name.to_string(), style)))
+    let spans = itertools::intersperse(
+        iter::once(Span::styled(entry.short_id.clone(), &style.hash))
+            .chain(entry.refs.iter().map(|reference| {
+                let style: Style = match reference.kind {
+                    RefKind::Remote => &style.remote,
+                    RefKind::Tag => &style.tag,
+                    RefKind::Branch => &style.branch,
                 }
-                _ => None,
-            },
-        )
-        .collect::<Vec<(Commit, Span)>>();
-
-    Ok(revwalk
-        .map(|oid_result| -> Res<Item> {
-            let oid = oid_result?;
-            let commit = repo.find_commit(oid)?;
-            let short_id = commit.as_object().short_id()?.as_str().unwrap().to_string();
-
-            let spans = itertools::intersperse(
-                iter::once(Span::styled(short_id, &style.hash))
-                    .chain(
-                        references
-                            .iter()
-                            .filter(|(commit, _)| commit.id() == oid)
-                            .map(|(_, name)| name.clone()),
-                    )
-                    .chain([commit.summary().unwrap_or("").to_string().into()]),
-                Span::raw(" "),
-            )
-            .collect::<Vec<_>>();
-
-            Ok(Item {
-                id: oid.to_string().into(),
-                display: Line::from(spans),
-                depth: 1,
-                target_data: Some(TargetData::Commit(oid.to_string())),
-                ..Default::default()
-            })
-        })
-        .map(|result| match result {
-            Ok(item) => item,
-            Err(err) => Item {
-                id: err.to_string().into(),
-                display: err.to_string().into(),
-                ..Default::default()
-            },
-        })
-        .take(limit)
-        .collect())
+                .into();
+
+                Span::styled(reference.name.clone(), style)
+            }))
+            .chain([entry.summary.clone().into()]),
+        Span::raw(" "),
+    )
+    .collect::<Vec<_>>();
+
+    Item {
+        id: entry.id.to_string().into(),
+        display: Line::from(spans),
+        depth: 1,
+        target_data: Some(TargetData::Commit(entry.id.to_string())),
+        ..Default::default()
+    }
 }
 
 pub(crate) fn blank_line() -> Item {
@@ -248,3 +425,32 @@ pub(crate) fn blank_line() -> Item {
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{blank_line, DiffCache};
+    use std::cell::Cell;
+
+    #[test]
+    fn diff_cache_keeps_only_entries_in_use() {
+        let cache = DiffCache::default();
+        let renders = Cell::new(0);
+        let render = || {
+            renders.set(renders.get() + 1);
+            vec![blank_line()]
+        };
+
+        cache.start_refresh();
+        cache.get_or_insert_with((1, "a".into()), render);
+        cache.get_or_insert_with((1, "b".into()), render);
+
+        cache.start_refresh();
+        cache.get_or_insert_with((1, "a".into()), render);
+        assert_eq!(renders.get(), 2);
+
+        cache.start_refresh();
+        cache.get_or_insert_with((1, "a".into()), render);
+        cache.get_or_insert_with((1, "b".into()), render);
+        assert_eq!(renders.get(), 3);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{state::State, term::Term, Res};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+pub(crate) type JobId = usize;
+
+/// The part of a job that runs off the main thread. It may not touch `State`,
+/// so anything it needs has to be moved into it up front.
+pub(crate) type Work = Box<dyn FnOnce() -> Result<JobOutput, String> + Send>;
+
+/// Called back on the main thread with the result once the `Work` is done.
+pub(crate) type OnDone = Box<dyn FnOnce(&mut State, &mut Term, &JobOutput) -> Res<()>>;
+
+pub(crate) struct JobOutput {
+    pub(crate) out: String,
+    pub(crate) success: bool,
+}
+
+pub(crate) struct Job {
+    pub(crate) display: Cow<'static, str>,
+    /// Don't show the output once done, for work the user didn't ask for.
+    pub(crate) quiet: bool,
+    pub(crate) on_done: OnDone,
+}
+
+type JobResult = (JobId, Result<JobOutput, String>);
+
+/// Keeps track of work running in the background. Git commands are queued onto a single
+/// worker thread (running them concurrently would only make them fight over `index.lock`),
+/// while detached jobs get a thread of their own.
+/// When not running in the background, all work is done immediately, which is what tests rely on.
+pub(crate) struct Jobs {
+    background: bool,
+    queue: Option<Sender<(JobId, Work)>>,
+    results_sender: Sender<JobResult>,
+    results: Receiver<JobResult>,
+    pending: HashMap<JobId, Job>,
+    /// Those of the pending jobs that are queued rather than detached
+    queued: HashSet<JobId>,
+    /// Queued jobs that were dropped before the worker got to them
+    cancelled: Arc<Mutex<HashSet<JobId>>>,
+    next_id: JobId,
+}
+
+impl Jobs {
+    pub(crate) fn new(background: bool) -> Self {
+        let (results_sender, results) = mpsc::channel();
+
+        Self {
+            background,
+            queue: None,
+            results_sender,
+            results,
+            pending: HashMap::new(),
+            queued: HashSet::new(),
+            cancelled: Arc::default(),
+            next_id: 0,
+        }
+    }
+
+    /// Queue work to be run after any previously queued work has finished.
+    pub(crate) fn spawn(&mut self, job: Job, work: Work) {
+        let id = self.register(job);
+
+        if !self.background {
+            self.run_inline(id, work);
+            return;
+        }
+
+        self.queued.insert(id);
+        let queue = self.queue.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel::<(JobId, Work)>();
+            let results_sender = self.results_sender.clone();
+            let cancelled = Arc::clone(&self.cancelled);
+
+            thread::spawn(move || {
+                for (id, work) in receiver {
+                    if cancelled.lock().unwrap().remove(&id) {
+                        continue;
+                    }
+                    if results_sender.send((id, work())).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            sender
+        });
+
+        queue
+            .send((id, work))
+            .expect("Job worker thread has stopped");
+    }
+
+    /// Run work on a thread of its own, not waiting for (or holding up) any queued work.
+    pub(crate) fn spawn_detached(&mut self, job: Job, work: Work) {
+        let id = self.register(job);
+
+        if !self.background {
+            self.run_inline(id, work);
+            return;
+        }
+
+        let results_sender = self.results_sender.clone();
+        thread::spawn(move || {
+            // The receiving end is gone if gitu has quit, nothing to report to.
+            let _ = results_sender.send((id, work()));
+        });
+    }
+
+    fn register(&mut self, job: Job) -> JobId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.insert(id, job);
+        id
+    }
+
+    fn run_inline(&self, id: JobId, work: Work) {
+        self.results_sender
+            .send((id, work()))
+            .expect("Job results channel closed");
+    }
+
+    pub(crate) fn try_recv(&mut self) -> Option<(Job, Result<JobOutput, String>)> {
+        loop {
+            let (id, result) = self.results.try_recv().ok()?;
+            if let Some(job) = self.finished(id) {
+                return Some((job, result));
+            }
+        }
+    }
+
+    /// Wait for the next job to finish, as long as any queued job is still pending.
+    /// Detached jobs are only waited on if they happen to finish first.
+    pub(crate) fn recv_queued(&mut self) -> Option<(Job, Result<JobOutput, String>)> {
+        while !self.queued.is_empty() {
+            let (id, result) = self.results.recv().ok()?;
+            if let Some(job) = self.finished(id) {
+                return Some((job, result));
+            }
+        }
+
+        None
+    }
+
+    /// Forget about quiet jobs, the queued ones won't be run if they haven't started yet.
+    /// Their results are of no use when quitting.
+    pub(crate) fn drop_quiet(&mut self) {
+        let quiet = self
+            .pending
+            .iter()
+            .filter(|(_, job)| job.quiet)
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+
+        let mut cancelled = self.cancelled.lock().unwrap();
+        for id in quiet {
+            self.pending.remove(&id);
+            if self.queued.remove(&id) {
+                cancelled.insert(id);
+            }
+        }
+    }
+
+    /// Results of dropped jobs may still come in, these aren't pending anymore.
+    fn finished(&mut self, id: JobId) -> Option<Job> {
+        self.queued.remove(&id);
+        self.pending.remove(&id)
+    }
+
+    /// Whether any work the user asked for is still running.
+    pub(crate) fn is_running(&self) -> bool {
+        self.pending.values().any(|job| !job.quiet)
+    }
+
+    pub(crate) fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Job, JobOutput, Jobs};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    };
+
+    fn job(display: &'static str) -> Job {
+        Job {
+            display: display.into(),
+            quiet: false,
+            on_done: Box::new(|_state, _term, _output| Ok(())),
+        }
+    }
+
+    fn output() -> JobOutput {
+        JobOutput {
+            out: String::new(),
+            success: true,
+        }
+    }
+
+    #[test]
+    fn queued_jobs_finish_in_order() {
+        let mut jobs = Jobs::new(true);
+        for display in ["first", "second", "third"] {
+            jobs.spawn(
+                job(display),
+                Box::new(move || {
+                    Ok(JobOutput {
+                        out: display.to_string(),
+                        success: true,
+                    })
+                }),
+            );
+        }
+
+        assert!(jobs.is_running());
+        let finished = std::iter::from_fn(|| jobs.recv_queued())
+            .map(|(job, result)| (job.display, result.unwrap().out))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            finished,
+            [
+                ("first".into(), "first".to_string()),
+                ("second".into(), "second".to_string()),
+                ("third".into(), "third".to_string())
+            ]
+        );
+        assert!(!jobs.is_running());
+    }
+
+    #[test]
+    fn dropped_quiet_jobs_are_not_run() {
+        let mut jobs = Jobs::new(true);
+        let (go, wait) = mpsc::channel::<()>();
+        let ran = Arc::new(AtomicBool::new(false));
+        let quiet_ran = Arc::clone(&ran);
+
+        jobs.spawn(
+            job("push"),
+            Box::new(move || {
+                wait.recv().unwrap();
+                Ok(output())
+            }),
+        );
+        jobs.spawn(
+            Job {
+                quiet: true,
+                ..job("fetch")
+            },
+            Box::new(move || {
+                quiet_ran.store(true, Ordering::SeqCst);
+                Ok(output())
+            }),
+        );
+
+        jobs.drop_quiet();
+        go.send(()).unwrap();
+
+        let (done, _) = jobs.recv_queued().unwrap();
+        assert_eq!(done.display, "push");
+        assert!(jobs.recv_queued().is_none());
+        assert!(jobs.is_idle());
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn detached_jobs_are_not_waited_on() {
+        let mut jobs = Jobs::new(true);
+        let (_go, wait) = mpsc::channel::<()>();
+        jobs.spawn_detached(
+            job("hook"),
+            Box::new(move || {
+                let _ = wait.recv();
+                Ok(output())
+            }),
+        );
+
+        assert!(jobs.is_running());
+        assert!(jobs.recv_queued().is_none());
+    }
+
+    #[test]
+    fn inline_jobs_finish_immediately() {
+        let mut jobs = Jobs::new(false);
+        jobs.spawn(job("inline"), Box::new(|| Err("failed".to_string())));
+
+        let (job, result) = jobs.try_recv().unwrap();
+        assert_eq!(job.display, "inline");
+        assert_eq!(result.err().unwrap(), "failed");
+    }
+}
@@ -1,3 +1,6 @@
+use crate::git::index_flags::IndexFlag;
+use crate::ops::ignore::IgnorePattern;
+use crate::ops::tag::TagKind;
 use crate::ops::Op;
 use crate::ops::SubmenuOp;
 use crossterm::event::{self, KeyCode, KeyModifiers};
@@ -57,6 +60,7 @@ impl Keybind {
                 KeyCode::Up => "↑".to_string(),
                 KeyCode::Down => "↓".to_string(),
                 KeyCode::Tab => "tab".to_string(),
+                KeyCode::Char(' ') => "spc".to_string(),
                 KeyCode::Delete => "del".to_string(),
                 KeyCode::Insert => "ins".to_string(),
                 KeyCode::F(n) => format!("F{}", n),
@@ -76,9 +80,16 @@ pub(crate) const KEYBINDS: &[Keybind] = &[
     // Generic
     Keybind::nomod(SubmenuOp::Any, Char('q'), Op::Quit),
     Keybind::nomod(SubmenuOp::Any, Esc, Op::Quit),
-    Keybind::nomod(SubmenuOp::None, Char('g'), Op::Refresh),
+    Keybind::nomod(SubmenuOp::None, Char('g'), Op::Submenu(SubmenuOp::Go)),
+    Keybind::nomod(SubmenuOp::Go, Char('g'), Op::Refresh),
+    Keybind::shift(SubmenuOp::None, Char('G'), Op::ScanUntracked),
+    Keybind::ctrl(SubmenuOp::None, Char('z'), Op::Suspend),
+    Keybind::shift(SubmenuOp::None, Char('S'), Op::Shell),
+    Keybind::shift(SubmenuOp::None, Char('Q'), Op::RecordMacro),
+    Keybind::nomod(SubmenuOp::None, Char('@'), Op::ReplayMacro),
     // Editor
     Keybind::nomod(SubmenuOp::None, Tab, Op::ToggleSection),
+    Keybind::nomod(SubmenuOp::None, Char(' '), Op::ToggleMark),
     Keybind::nomod(SubmenuOp::None, Char('k'), Op::SelectPrevious),
     Keybind::nomod(SubmenuOp::None, Char('p'), Op::SelectPrevious),
     Keybind::nomod(SubmenuOp::None, KeyCode::Up, Op::SelectPrevious),
@@ -87,24 +98,138 @@ pub(crate) const KEYBINDS: &[Keybind] = &[
     Keybind::nomod(SubmenuOp::None, KeyCode::Down, Op::SelectNext),
     Keybind::ctrl(SubmenuOp::None, Char('u'), Op::HalfPageUp),
     Keybind::ctrl(SubmenuOp::None, Char('d'), Op::HalfPageDown),
+    // Tabs
+    Keybind::nomod(SubmenuOp::Go, Char('n'), Op::NewTab),
+    Keybind::nomod(SubmenuOp::Go, Char('t'), Op::NextTab),
+    Keybind::shift(SubmenuOp::Go, Char('T'), Op::PreviousTab),
+    Keybind::nomod(SubmenuOp::Go, Char('1'), Op::GoToTab(1)),
+    Keybind::nomod(SubmenuOp::Go, Char('2'), Op::GoToTab(2)),
+    Keybind::nomod(SubmenuOp::Go, Char('3'), Op::GoToTab(3)),
+    Keybind::nomod(SubmenuOp::Go, Char('4'), Op::GoToTab(4)),
+    Keybind::nomod(SubmenuOp::Go, Char('5'), Op::GoToTab(5)),
+    Keybind::nomod(SubmenuOp::Go, Char('6'), Op::GoToTab(6)),
+    Keybind::nomod(SubmenuOp::Go, Char('7'), Op::GoToTab(7)),
+    Keybind::nomod(SubmenuOp::Go, Char('8'), Op::GoToTab(8)),
+    Keybind::nomod(SubmenuOp::Go, Char('9'), Op::GoToTab(9)),
     // Help
     Keybind::nomod(SubmenuOp::None, Char('h'), Op::Submenu(SubmenuOp::Help)),
+    // Bisect
+    Keybind::shift(SubmenuOp::None, Char('B'), Op::Submenu(SubmenuOp::Bisect)),
+    Keybind::nomod(SubmenuOp::Bisect, Char('s'), Op::BisectStart),
+    Keybind::nomod(SubmenuOp::Bisect, Char('g'), Op::BisectGood),
+    Keybind::nomod(SubmenuOp::Bisect, Char('b'), Op::BisectBad),
+    Keybind::nomod(SubmenuOp::Bisect, Char('k'), Op::BisectSkip),
+    Keybind::nomod(SubmenuOp::Bisect, Char('x'), Op::BisectRun),
+    Keybind::nomod(SubmenuOp::Bisect, Char('r'), Op::BisectReset),
     // Branch
     Keybind::nomod(SubmenuOp::None, Char('b'), Op::Submenu(SubmenuOp::Branch)),
     Keybind::nomod(SubmenuOp::Branch, Char('b'), Op::Checkout),
     Keybind::nomod(SubmenuOp::Branch, Char('c'), Op::CheckoutNewBranch),
+    Keybind::nomod(SubmenuOp::Branch, Char('-'), Op::CheckoutPrevious),
+    Keybind::nomod(SubmenuOp::Branch, Char('p'), Op::CheckoutPullRequest),
+    // Cherry-pick
+    Keybind::shift(
+        SubmenuOp::None,
+        Char('A'),
+        Op::Submenu(SubmenuOp::CherryPick),
+    ),
+    Keybind::nomod(
+        SubmenuOp::CherryPick,
+        Char('n'),
+        Op::ToggleArg("--no-commit"),
+    ),
+    Keybind::nomod(SubmenuOp::CherryPick, Char('x'), Op::ToggleArg("-x")),
+    Keybind::shift(SubmenuOp::CherryPick, Char('A'), Op::CherryPick),
+    Keybind::nomod(SubmenuOp::CherryPick, Char('r'), Op::CherryPickRange),
+    Keybind::nomod(SubmenuOp::CherryPick, Char('c'), Op::CherryPickContinue),
+    Keybind::nomod(SubmenuOp::CherryPick, Char('s'), Op::CherryPickSkip),
+    Keybind::nomod(SubmenuOp::CherryPick, Char('a'), Op::CherryPickAbort),
+    // Clean
+    Keybind::nomod(SubmenuOp::None, Char('x'), Op::Submenu(SubmenuOp::Clean)),
+    Keybind::nomod(SubmenuOp::Clean, Char('x'), Op::ShowClean),
+    Keybind::nomod(SubmenuOp::Clean, Char('i'), Op::ShowCleanIgnored),
+    Keybind::nomod(SubmenuOp::Clean, Char('k'), Op::Clean),
     // Commit
     Keybind::nomod(SubmenuOp::None, Char('c'), Op::Submenu(SubmenuOp::Commit)),
     Keybind::nomod(SubmenuOp::Commit, Char('c'), Op::Commit),
     Keybind::nomod(SubmenuOp::Commit, Char('a'), Op::CommitAmend),
+    Keybind::nomod(SubmenuOp::Commit, Char('t'), Op::CommitConventional),
     Keybind::nomod(SubmenuOp::Commit, Char('f'), Op::CommitFixup),
+    // Diff
+    Keybind::nomod(SubmenuOp::None, Char('d'), Op::Submenu(SubmenuOp::Diff)),
+    Keybind::nomod(SubmenuOp::Diff, Char('w'), Op::CompareWorktree),
+    Keybind::nomod(SubmenuOp::Diff, Char('h'), Op::CompareHead),
     // Fetch
     Keybind::nomod(SubmenuOp::None, Char('f'), Op::Submenu(SubmenuOp::Fetch)),
     Keybind::nomod(SubmenuOp::Fetch, Char('a'), Op::FetchAll),
+    // Ignore
+    Keybind::nomod(SubmenuOp::None, Char('i'), Op::Submenu(SubmenuOp::Ignore)),
+    Keybind::nomod(
+        SubmenuOp::Ignore,
+        Char('i'),
+        Op::Ignore(IgnorePattern::Path),
+    ),
+    Keybind::nomod(
+        SubmenuOp::Ignore,
+        Char('e'),
+        Op::Ignore(IgnorePattern::Extension),
+    ),
+    Keybind::nomod(
+        SubmenuOp::Ignore,
+        Char('d'),
+        Op::Ignore(IgnorePattern::Directory),
+    ),
+    Keybind::shift(
+        SubmenuOp::Ignore,
+        Char('I'),
+        Op::IgnoreLocally(IgnorePattern::Path),
+    ),
+    Keybind::shift(
+        SubmenuOp::Ignore,
+        Char('E'),
+        Op::IgnoreLocally(IgnorePattern::Extension),
+    ),
+    Keybind::shift(
+        SubmenuOp::Ignore,
+        Char('D'),
+        Op::IgnoreLocally(IgnorePattern::Directory),
+    ),
+    Keybind::nomod(SubmenuOp::Ignore, Char('s'), Op::ToggleIgnored),
+    Keybind::nomod(SubmenuOp::Ignore, Char('a'), Op::ForceAdd),
+    Keybind::nomod(
+        SubmenuOp::Ignore,
+        Char('w'),
+        Op::ToggleIndexFlag(IndexFlag::SkipWorktree),
+    ),
+    Keybind::nomod(
+        SubmenuOp::Ignore,
+        Char('u'),
+        Op::ToggleIndexFlag(IndexFlag::AssumeUnchanged),
+    ),
     // Log
     Keybind::nomod(SubmenuOp::None, Char('l'), Op::Submenu(SubmenuOp::Log)),
     Keybind::nomod(SubmenuOp::Log, Char('l'), Op::LogCurrent),
     Keybind::nomod(SubmenuOp::Log, Char('o'), Op::LogOther),
+    Keybind::nomod(SubmenuOp::Log, Char('b'), Op::Blame),
+    Keybind::nomod(SubmenuOp::Log, Char('p'), Op::BlameParent),
+    Keybind::nomod(SubmenuOp::Log, Char('t'), Op::TimeMachine),
+    Keybind::nomod(SubmenuOp::Log, Char('i'), Op::ShowInsights),
+    Keybind::nomod(SubmenuOp::None, Char('['), Op::OlderRevision),
+    Keybind::nomod(SubmenuOp::None, Char(']'), Op::NewerRevision),
+    // Notes
+    Keybind::shift(SubmenuOp::None, Char('T'), Op::Submenu(SubmenuOp::Notes)),
+    Keybind::nomod(SubmenuOp::Notes, Char('e'), Op::NoteEdit),
+    Keybind::nomod(SubmenuOp::Notes, Char('k'), Op::NoteRemove),
+    // Patch
+    Keybind::shift(SubmenuOp::None, Char('W'), Op::Submenu(SubmenuOp::Patch)),
+    Keybind::nomod(SubmenuOp::Patch, Char('p'), Op::FormatPatch),
+    Keybind::shift(SubmenuOp::Patch, Char('P'), Op::FormatPatchRange),
+    Keybind::nomod(SubmenuOp::Patch, Char('a'), Op::ApplyPatch),
+    Keybind::nomod(SubmenuOp::Patch, Char('i'), Op::ApplyPatchCached),
+    Keybind::nomod(SubmenuOp::Patch, Char('w'), Op::Am),
+    Keybind::nomod(SubmenuOp::Patch, Char('c'), Op::AmContinue),
+    Keybind::nomod(SubmenuOp::Patch, Char('s'), Op::AmSkip),
+    Keybind::nomod(SubmenuOp::Patch, Char('k'), Op::AmAbort),
     // Pull
     Keybind::shift(SubmenuOp::None, Char('F'), Op::Submenu(SubmenuOp::Pull)),
     Keybind::nomod(SubmenuOp::Pull, Char('p'), Op::Pull),
@@ -117,15 +242,77 @@ pub(crate) const KEYBINDS: &[Keybind] = &[
     Keybind::nomod(SubmenuOp::Rebase, Char('a'), Op::RebaseAbort),
     Keybind::nomod(SubmenuOp::Rebase, Char('c'), Op::RebaseContinue),
     Keybind::nomod(SubmenuOp::Rebase, Char('f'), Op::RebaseAutosquash),
+    // Recent repositories
+    Keybind::shift(SubmenuOp::None, Char('R'), Op::ShowRecentRepos),
     // Reset
     Keybind::shift(SubmenuOp::None, Char('X'), Op::Submenu(SubmenuOp::Reset)),
     Keybind::nomod(SubmenuOp::Reset, Char('s'), Op::ResetSoft),
     Keybind::nomod(SubmenuOp::Reset, Char('m'), Op::ResetMixed),
     Keybind::nomod(SubmenuOp::Reset, Char('h'), Op::ResetHard),
+    Keybind::nomod(SubmenuOp::Reset, Char('k'), Op::ResetKeep),
+    // Revert
+    Keybind::shift(SubmenuOp::None, Char('V'), Op::Submenu(SubmenuOp::Revert)),
+    Keybind::nomod(SubmenuOp::Revert, Char('n'), Op::ToggleArg("--no-commit")),
+    Keybind::shift(SubmenuOp::Revert, Char('V'), Op::Revert),
+    Keybind::nomod(SubmenuOp::Revert, Char('r'), Op::RevertRange),
+    Keybind::nomod(SubmenuOp::Revert, Char('c'), Op::RevertContinue),
+    Keybind::nomod(SubmenuOp::Revert, Char('a'), Op::RevertAbort),
     // Show
     Keybind::nomod(SubmenuOp::None, Enter, Op::Show),
+    Keybind::nomod(SubmenuOp::None, Char('e'), Op::OpenInEditor),
+    Keybind::shift(SubmenuOp::None, Char('O'), Op::Browse),
+    Keybind::shift(SubmenuOp::None, Char('C'), Op::BrowseCi),
     // Show refs
     Keybind::nomod(SubmenuOp::None, Char('y'), Op::ShowRefs),
+    // Git command
+    Keybind::nomod(SubmenuOp::None, Char('!'), Op::GitCommand),
+    // Snapshots and trash
+    Keybind::shift(SubmenuOp::None, Char('Z'), Op::ShowSnapshots),
+    Keybind::shift(SubmenuOp::None, Char('D'), Op::ShowTrash),
+    Keybind::nomod(SubmenuOp::None, Char('a'), Op::Restore),
+    // Submodule
+    Keybind::nomod(
+        SubmenuOp::None,
+        Char('o'),
+        Op::Submenu(SubmenuOp::Submodule),
+    ),
+    Keybind::nomod(SubmenuOp::Submodule, Char('l'), Op::ShowSubmodules),
+    Keybind::nomod(SubmenuOp::Submodule, Char('i'), Op::SubmoduleInit),
+    Keybind::nomod(SubmenuOp::Submodule, Char('u'), Op::SubmoduleUpdate),
+    Keybind::nomod(SubmenuOp::Submodule, Char('s'), Op::SubmoduleSync),
+    Keybind::nomod(SubmenuOp::Submodule, Char('e'), Op::SubmoduleEnter),
+    // Tag
+    Keybind::nomod(SubmenuOp::None, Char('t'), Op::Submenu(SubmenuOp::Tag)),
+    Keybind::nomod(SubmenuOp::Tag, Char('l'), Op::ShowTags),
+    Keybind::nomod(
+        SubmenuOp::Tag,
+        Char('t'),
+        Op::CreateTag(TagKind::Lightweight),
+    ),
+    Keybind::nomod(SubmenuOp::Tag, Char('a'), Op::CreateTag(TagKind::Annotated)),
+    Keybind::nomod(SubmenuOp::Tag, Char('s'), Op::CreateTag(TagKind::Signed)),
+    Keybind::nomod(SubmenuOp::Tag, Char('k'), Op::DeleteTag),
+    Keybind::nomod(SubmenuOp::Tag, Char('p'), Op::PushTag),
+    Keybind::shift(SubmenuOp::Tag, Char('P'), Op::PushTags),
+    Keybind::shift(SubmenuOp::Tag, Char('K'), Op::DeleteRemoteTag),
+    // Undo
+    Keybind::shift(SubmenuOp::None, Char('U'), Op::Undo),
+    // Worktree
+    Keybind::nomod(SubmenuOp::None, Char('w'), Op::Submenu(SubmenuOp::Worktree)),
+    Keybind::nomod(SubmenuOp::Worktree, Char('l'), Op::ShowWorktrees),
+    Keybind::nomod(SubmenuOp::Worktree, Char('a'), Op::WorktreeAdd),
+    Keybind::nomod(SubmenuOp::Worktree, Char('k'), Op::WorktreeRemove),
+    Keybind::nomod(SubmenuOp::Worktree, Char('g'), Op::WorktreeSwitch),
+    // Yank
+    Keybind::shift(SubmenuOp::None, Char('Y'), Op::Submenu(SubmenuOp::Yank)),
+    Keybind::nomod(SubmenuOp::Yank, Char('y'), Op::YankHash),
+    Keybind::nomod(SubmenuOp::Yank, Char('s'), Op::YankShortHash),
+    Keybind::nomod(SubmenuOp::Yank, Char('f'), Op::YankPath),
+    Keybind::nomod(SubmenuOp::Yank, Char('u'), Op::YankUrl),
+    // Pull requests
+    Keybind::shift(SubmenuOp::None, Char('N'), Op::ShowPullRequests),
+    // Rename
+    Keybind::nomod(SubmenuOp::None, Char('m'), Op::RenameFile),
     // Discard
     Keybind::shift(SubmenuOp::None, Char('K'), Op::Discard),
     // Target actions
@@ -1,26 +1,36 @@
 pub mod cli;
 pub mod config;
+mod forge;
 mod git;
 mod git2_opts;
+mod hooks;
+mod images;
 mod items;
+mod jobs;
 mod keybinds;
 mod ops;
+mod print;
 mod prompt;
+mod recent;
 mod screen;
+mod session;
 pub mod state;
 pub mod term;
 mod ui;
+mod watcher;
+mod welcome;
 
 use crossterm::event::{self};
-use git2::Repository;
 use items::Item;
 use itertools::Itertools;
 use ops::{Action, Op, SubmenuOp};
+use ratatui::layout::Rect;
 use state::State;
-use std::{borrow::Cow, error::Error, iter, path::PathBuf, process::Command, rc::Rc};
+use std::{borrow::Cow, env, error::Error, io, iter, process::Command, rc::Rc, time::Duration};
 use term::Term;
 
 const APP_NAME: &str = "gitu";
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 pub type Res<T> = Result<T, Box<dyn Error>>;
 
@@ -38,24 +48,59 @@ fn command_args(cmd: &Command) -> Cow<'static, str> {
         .into()
 }
 
-pub fn run(args: &cli::Args, term: &mut Term) -> Res<()> {
-    log::debug!("Finding git dir");
-    let dir = PathBuf::from(
-        String::from_utf8(
-            Command::new("git")
-                .args(["rev-parse", "--show-toplevel"])
-                .output()?
-                .stdout,
-        )?
-        .trim_end(),
-    );
+/// A command to run again, `Command` can't be cloned.
+pub(crate) fn copy_cmd(cmd: &Command) -> Command {
+    let mut copy = Command::new(cmd.get_program());
+    copy.args(cmd.get_args());
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => copy.env(key, value),
+            None => copy.env_remove(key),
+        };
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        copy.current_dir(dir);
+    }
+    copy
+}
 
-    log::debug!("Opening repo");
-    let repo = Repository::open_from_env()?;
-    repo.set_workdir(&dir, false)?;
+pub fn run(args: &cli::Args, term: &mut Term) -> Res<()> {
+    // Like `git -C`, everything else (including relative paths given) happens from there
+    if let Some(dir) = &args.dir {
+        env::set_current_dir(dir)
+            .map_err(|err| format!("Couldn't change directory to {}: {}", dir.display(), err))?;
+    }
 
     log::debug!("Initializing config");
-    let config = config::init_config()?;
+    let mut config = config::init_config()?;
+
+    // Searches parent directories too, like git does
+    log::debug!("Opening repo");
+    let repo = match git::open_from_env() {
+        Ok(repo) => repo,
+        Err(err) if err.code() == git2::ErrorCode::NotFound && args.recent => config
+            .recent_repos_file
+            .as_deref()
+            .and_then(recent::open_latest)
+            .ok_or(err)?,
+        Err(err) if err.code() == git2::ErrorCode::NotFound && !args.print => {
+            match welcome::run(term, &config, env::current_dir()?)? {
+                Some(repo) => repo,
+                None => return Ok(()),
+            }
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if args.print {
+        // What's printed should be complete, rather than show what's still loading
+        config.general.background_jobs = false;
+        config.general.refresh_on_file_change = false;
+
+        let size = term.size().unwrap_or(Rect::new(0, 0, 80, 24));
+        let mut state = state::State::create(repo, size, args, config)?;
+        state.screen_mut().expand_all();
+        return print::print(&mut io::stdout(), &state.config, state.screen());
+    }
 
     log::debug!("Creating initial state");
     let mut state = state::State::create(repo, term.size()?, args, config)?;
@@ -63,18 +108,19 @@ pub fn run(args: &cli::Args, term: &mut Term) -> Res<()> {
     log::debug!("Drawing initial frame");
     term.draw(|frame| ui::ui(frame, &mut state))?;
 
-    if args.print {
-        return Ok(());
-    }
-
     while !state.quit {
-        log::debug!("Awaiting event");
-        let event = event::read()?;
-
-        log::debug!("Updating");
-        state.update(term, &[event])?;
+        // Poll rather than block, so that finished background jobs get picked up
+        let events = if event::poll(EVENT_POLL_INTERVAL)? {
+            vec![event::read()?]
+        } else {
+            vec![]
+        };
+
+        state.update(term, &events)?;
     }
 
+    state.await_commands(term);
+
     Ok(())
 }
 
@@ -91,7 +137,10 @@ pub(crate) fn handle_op(state: &mut State, op: Op, term: &mut Term) -> Res<()> {
 
 fn close_submenu(state: &mut State, op: Op) {
     match op {
-        Op::Submenu(_) => (),
-        _ => state.pending_submenu_op = SubmenuOp::None,
+        Op::Submenu(_) | Op::ToggleArg(_) => (),
+        _ => {
+            state.pending_submenu_op = SubmenuOp::None;
+            state.pending_args.clear();
+        }
     }
 }
@@ -2,11 +2,50 @@ use clap::Parser;
 use gitu::{cli::Args, term, Res};
 use log::LevelFilter;
 use ratatui::Terminal;
-use std::{backtrace::Backtrace, panic};
+use std::{
+    backtrace::Backtrace,
+    io::{stderr, IsTerminal},
+    panic,
+    process::ExitCode,
+};
 
-pub fn main() -> Res<()> {
+/// Something went wrong while running, the message says what.
+const EXIT_ERROR: u8 = 1;
+/// Interactive use needs a terminal to draw on, which is stderr.
+const EXIT_NOT_A_TERMINAL: u8 = 3;
+/// There's no repository to open, where one is required.
+const EXIT_NOT_A_REPOSITORY: u8 = 4;
+
+pub fn main() -> ExitCode {
     let args = Args::parse();
 
+    if !args.print && !args.version && !stderr().is_terminal() {
+        eprintln!("gitu needs a terminal to run in, use --print to print a view instead");
+        return ExitCode::from(EXIT_NOT_A_TERMINAL);
+    }
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::from(exit_code(err.as_ref()))
+        }
+    }
+}
+
+fn exit_code(err: &(dyn std::error::Error + 'static)) -> u8 {
+    match err.downcast_ref::<git2::Error>() {
+        Some(err)
+            if err.code() == git2::ErrorCode::NotFound
+                && err.class() == git2::ErrorClass::Repository =>
+        {
+            EXIT_NOT_A_REPOSITORY
+        }
+        _ => EXIT_ERROR,
+    }
+}
+
+fn run(args: &Args) -> Res<()> {
     if args.version {
         // Setting cargo_suffix enables falling back to Cargo.toml for version
         // `cargo install --locked gitu` would fail otherwise, as there's no git repo
@@ -21,15 +60,18 @@ pub fn main() -> Res<()> {
     panic::set_hook(Box::new(|panic_info| {
         term::cleanup_alternate_screen();
         term::cleanup_raw_mode();
+        term::cleanup_title();
 
         eprintln!("{}", panic_info);
         eprintln!("trace: \n{}", Backtrace::force_capture());
     }));
 
     if args.print {
-        setup_term_and_run(&args)?;
+        setup_term_and_run(args)?;
     } else {
-        term::alternate_screen(|| term::raw_mode(|| setup_term_and_run(&args)))?
+        term::saved_title(|| {
+            term::alternate_screen(|| term::raw_mode(|| setup_term_and_run(args)))
+        })?
     }
 
     Ok(())
@@ -40,7 +82,9 @@ fn setup_term_and_run(args: &Args) -> Res<()> {
     let mut terminal = Terminal::new(term::backend())?;
 
     // Prevents cursor flash when opening gitu
-    terminal.hide_cursor()?;
+    if !args.print {
+        terminal.hide_cursor()?;
+    }
 
     log::debug!("Starting app");
     gitu::run(args, &mut terminal)
@@ -0,0 +1,131 @@
+use super::{cmd, Action, OpTrait};
+use crate::{
+    git, items::TargetData, prompt::PromptData, screen::RefreshScope, state::State, term::Term, Res,
+};
+use derive_more::Display;
+use std::{process::Command, rc::Rc};
+use tui_prompts::State as _;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Start bisect")]
+pub(crate) struct BisectStart;
+impl OpTrait for BisectStart {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            state.prompt.set(PromptData {
+                prompt_text: "Bisect bad revision (default HEAD):".into(),
+                update_fn: Rc::new(bad_prompt_update),
+            });
+            Ok(())
+        }))
+    }
+}
+
+fn bad_prompt_update(state: &mut State, term: &mut Term) -> Res<()> {
+    if state.prompt.state.status().is_done() {
+        let bad = match state.prompt.state.value() {
+            "" => "HEAD".to_string(),
+            value => value.to_string(),
+        };
+        state.prompt.reset(term)?;
+
+        let prompt_text = match default_good(state) {
+            Some(good) => format!("Bisect good revision (default {}):", good).into(),
+            None => "Bisect good revision:".into(),
+        };
+
+        state.prompt.set(PromptData {
+            prompt_text,
+            update_fn: Rc::new(move |state, term| good_prompt_update(state, term, &bad)),
+        });
+    }
+    Ok(())
+}
+
+fn good_prompt_update(state: &mut State, term: &mut Term, bad: &str) -> Res<()> {
+    if state.prompt.state.status().is_done() {
+        let input = state.prompt.state.value().to_string();
+        let good = match (input.as_str(), default_good(state)) {
+            ("", Some(default)) => default.to_string(),
+            (value, _) => value.to_string(),
+        };
+
+        let mut cmd = Command::new("git");
+        cmd.args(["bisect", "start", bad, &good]);
+
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
+        state.prompt.reset(term)?;
+    }
+    Ok(())
+}
+
+fn default_good(state: &State) -> Option<&str> {
+    match &state.screen().get_selected_item().target_data {
+        Some(TargetData::Commit(commit)) => Some(commit),
+        Some(TargetData::Branch(branch) | TargetData::Tag(branch)) => Some(branch),
+        _ => None,
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Mark good")]
+pub(crate) struct BisectGood;
+impl OpTrait for BisectGood {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(cmd(vec![], git::bisect_good_cmd, RefreshScope::All))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Mark bad")]
+pub(crate) struct BisectBad;
+impl OpTrait for BisectBad {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(cmd(vec![], git::bisect_bad_cmd, RefreshScope::All))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Skip")]
+pub(crate) struct BisectSkip;
+impl OpTrait for BisectSkip {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(cmd(vec![], git::bisect_skip_cmd, RefreshScope::All))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Reset bisect")]
+pub(crate) struct BisectReset;
+impl OpTrait for BisectReset {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(cmd(vec![], git::bisect_reset_cmd, RefreshScope::All))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Run command")]
+pub(crate) struct BisectRun;
+impl OpTrait for BisectRun {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            state.prompt.set(PromptData {
+                prompt_text: "Bisect with command (exits 0 if good):".into(),
+                update_fn: Rc::new(run_prompt_update),
+            });
+            Ok(())
+        }))
+    }
+}
+
+fn run_prompt_update(state: &mut State, term: &mut Term) -> Res<()> {
+    if state.prompt.state.status().is_done() {
+        let command = state.prompt.state.value().to_string();
+        let mut cmd = Command::new("git");
+        cmd.args(["bisect", "run", "sh", "-c", &command]);
+
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
+        state.prompt.reset(term)?;
+    }
+    Ok(())
+}
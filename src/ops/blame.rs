@@ -0,0 +1,51 @@
+use super::{Action, OpTrait};
+use crate::{items::TargetData, screen, state::State, term::Term};
+use derive_more::Display;
+use std::{path::PathBuf, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Blame")]
+pub(crate) struct Blame;
+impl OpTrait for Blame {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        match target {
+            Some(TargetData::File(f)) => goto_blame_screen(f.clone(), None),
+            Some(TargetData::Delta(d)) => goto_blame_screen(d.new_file.clone(), None),
+            Some(TargetData::Hunk(h)) => goto_blame_screen(h.new_file.clone(), None),
+            _ => None,
+        }
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Blame at parent")]
+pub(crate) struct BlameParent;
+impl OpTrait for BlameParent {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        match target {
+            Some(TargetData::BlameLine(line)) => {
+                goto_blame_screen(line.path.clone(), Some(format!("{}^", line.commit)))
+            }
+            _ => None,
+        }
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+fn goto_blame_screen(path: PathBuf, reference: Option<String>) -> Option<Action> {
+    Some(Rc::new(move |state: &mut State, term: &mut Term| {
+        state.screens.push(screen::blame::create(
+            Rc::clone(&state.config),
+            Rc::clone(&state.repo),
+            term.size()?,
+            path.clone(),
+            reference.clone(),
+        )?);
+        Ok(())
+    }))
+}
@@ -0,0 +1,141 @@
+use super::{Action, OpTrait};
+use crate::{
+    command_args,
+    forge::{self, Forge},
+    git::log::LogFilter,
+    items::TargetData,
+    state::State,
+    CmdMetaBuffer, Res,
+};
+use derive_more::Display;
+use git2::{BranchType, Repository};
+use std::{process::Stdio, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Browse")]
+pub(crate) struct Browse;
+impl OpTrait for Browse {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let target = target.cloned();
+
+        Some(Rc::new(move |state, _term| {
+            let url = url(&state.repo, target.as_ref())?;
+            open(state, &url)
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+/// Where the CI of a commit failed, or ran if it didn't, as shown next to it.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Browse CI run")]
+pub(crate) struct BrowseCi;
+impl OpTrait for BrowseCi {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let Some(TargetData::Commit(hash)) = target.cloned() else {
+            return None;
+        };
+
+        Some(Rc::new(move |state, _term| {
+            let url = match state.ci_statuses.get(&hash) {
+                Some(status) if !status.url.is_empty() => status.url.clone(),
+                _ => return Err("There's no CI run known of for this commit".into()),
+            };
+            open(state, &url)
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+fn open(state: &mut State, url: &str) -> Res<()> {
+    let mut cmd = forge::open_url_cmd(url);
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+    cmd.spawn()?;
+
+    state.cmd_meta_buffer = Some(CmdMetaBuffer {
+        args: command_args(&cmd),
+        out: Some(String::new()),
+    });
+
+    Ok(())
+}
+
+/// Where `target` can be seen on the repository's forge, the current branch if there's none.
+pub(crate) fn url(repo: &Repository, target: Option<&TargetData>) -> Res<String> {
+    // Another repository altogether, likely with a forge of its own
+    if let Some(TargetData::Repository(path)) = target {
+        return url(&Repository::open(path)?, None);
+    }
+
+    let forge = Forge::of_repo(repo)?;
+
+    Ok(match target {
+        Some(TargetData::Commit(hash)) => forge.commit_url(hash),
+        Some(TargetData::BlameLine(line)) => forge.file_url(
+            &line.commit.to_string(),
+            &line.path,
+            Some(line.line_number as u32),
+        ),
+        Some(TargetData::Branch(branch)) => forge.branch_url(&remote_name(repo, branch)),
+        Some(TargetData::Tag(tag)) => forge.branch_url(tag),
+        Some(TargetData::File(file)) => forge.file_url(&head_ref(repo)?, file, None),
+        Some(TargetData::Delta(delta)) => forge.file_url(&head_ref(repo)?, &delta.new_file, None),
+        Some(TargetData::Hunk(hunk)) => forge.file_url(
+            &head_ref(repo)?,
+            &hunk.new_file,
+            Some(hunk.first_diff_line()),
+        ),
+        Some(TargetData::LogFilter(LogFilter::Path(path))) => {
+            forge.file_url(&head_ref(repo)?, path, None)
+        }
+        Some(TargetData::LogFilter(LogFilter::Author(_))) => {
+            return Err("Authors can't be browsed".into())
+        }
+        Some(TargetData::PullRequest(pull_request)) => pull_request.url.clone(),
+        Some(TargetData::Submodule(path)) => {
+            let submodules = repo.submodules()?;
+            let submodule = submodules
+                .iter()
+                .find(|submodule| submodule.path() == path)
+                .ok_or("No such submodule")?;
+            let url = submodule.url().ok_or("Submodule url is not valid utf-8")?;
+            let forge =
+                Forge::from_url(url).ok_or_else(|| format!("Don't know how to browse {}", url))?;
+            match submodule.head_id() {
+                Some(id) => forge.commit_url(&id.to_string()),
+                None => forge.branch_url("HEAD"),
+            }
+        }
+        Some(TargetData::Worktree(path)) => forge.branch_url(&head_ref(&Repository::open(path)?)?),
+        Some(TargetData::Repository(_)) => unreachable!(),
+        Some(TargetData::Snapshot(_)) => return Err("Snapshots only exist locally".into()),
+        Some(TargetData::Trashed(_)) => return Err("The trash only exists locally".into()),
+        None => forge.branch_url(&head_ref(repo)?),
+    })
+}
+
+/// The current branch, or commit if there's none.
+fn head_ref(repo: &Repository) -> Res<String> {
+    let head = repo.head()?;
+    Ok(match head.shorthand() {
+        Some(branch) if head.is_branch() => branch.to_string(),
+        _ => head.peel_to_commit()?.id().to_string(),
+    })
+}
+
+/// What a branch is called on its remote, `origin/main` being just `main`.
+fn remote_name(repo: &Repository, branch: &str) -> String {
+    match repo.find_branch(branch, BranchType::Remote) {
+        Ok(_) => branch
+            .split_once('/')
+            .map_or(branch, |(_remote, name)| name)
+            .to_string(),
+        Err(_) => branch.to_string(),
+    }
+}
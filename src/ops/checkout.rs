@@ -1,5 +1,7 @@
-use super::{Action, OpTrait};
-use crate::{items::TargetData, prompt::PromptData, state::State, term::Term, Res};
+use super::{snapshot::snapshot, Action, OpTrait};
+use crate::{
+    items::TargetData, prompt::PromptData, screen::RefreshScope, state::State, term::Term, Res,
+};
 use derive_more::Display;
 use std::{process::Command, rc::Rc};
 use tui_prompts::State as _;
@@ -35,9 +37,11 @@ fn checkout_prompt_update(state: &mut State, term: &mut Term) -> Res<()> {
         };
 
         let mut cmd = Command::new("git");
-        cmd.args(["checkout", &branch_or_revision]);
+        cmd.args(["checkout", branch_or_revision]);
 
-        state.run_external_cmd(term, &[], cmd)?;
+        // In case changes in the worktree don't survive the switch
+        snapshot(state, format!("before checking out {}", branch_or_revision));
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
         state.prompt.reset(term)?;
     }
     Ok(())
@@ -45,12 +49,27 @@ fn checkout_prompt_update(state: &mut State, term: &mut Term) -> Res<()> {
 
 fn default_branch_or_revision(state: &State) -> Option<&str> {
     match &state.screen().get_selected_item().target_data {
-        Some(TargetData::Branch(branch)) => Some(branch),
+        Some(TargetData::Branch(branch) | TargetData::Tag(branch)) => Some(branch),
         Some(TargetData::Commit(commit)) => Some(commit),
         _ => None,
     }
 }
 
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Checkout previous branch")]
+pub(crate) struct CheckoutPrevious;
+impl OpTrait for CheckoutPrevious {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let mut cmd = Command::new("git");
+            cmd.args(["checkout", "-"]);
+
+            snapshot(state, "before checking out the previous branch".to_string());
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+        }))
+    }
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
 #[display(fmt = "Checkout new branch")]
 pub(crate) struct CheckoutNewBranch;
@@ -72,7 +91,7 @@ fn checkout_new_branch_prompt_update(state: &mut State, term: &mut Term) -> Res<
         let mut cmd = Command::new("git");
         cmd.args(["checkout", "-b", &name]);
 
-        state.run_external_cmd(term, &[], cmd)?;
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
         state.prompt.reset(term)?;
     }
     Ok(())
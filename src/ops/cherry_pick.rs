@@ -0,0 +1,81 @@
+use super::{commit_reference, Action, OpTrait};
+use crate::{git, items::TargetData, screen::RefreshScope, state::State, term::Term};
+use derive_more::Display;
+use std::{process::Command, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Cherry-pick")]
+pub(crate) struct CherryPick;
+impl OpTrait for CherryPick {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let commit = commit_reference(target)?;
+        cherry_pick(commit)
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Cherry-pick up to here")]
+pub(crate) struct CherryPickRange;
+impl OpTrait for CherryPickRange {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        // Everything leading up to the commit that isn't already in HEAD
+        let commit = commit_reference(target)?;
+        cherry_pick(format!("HEAD..{}", commit))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+fn cherry_pick(revisions: String) -> Option<Action> {
+    Some(Rc::new(move |state: &mut State, term: &mut Term| {
+        let mut cmd = Command::new("git");
+        cmd.arg("cherry-pick");
+        cmd.args(&state.pending_args);
+        cmd.arg(&revisions);
+
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+    }))
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Cherry-pick continue")]
+pub(crate) struct CherryPickContinue;
+impl OpTrait for CherryPickContinue {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            // Opens an editor for the message of a resolved commit
+            state.issue_subscreen_command(term, git::cherry_pick_continue_cmd())?;
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Cherry-pick skip")]
+pub(crate) struct CherryPickSkip;
+impl OpTrait for CherryPickSkip {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(super::cmd(
+            vec![],
+            git::cherry_pick_skip_cmd,
+            RefreshScope::All,
+        ))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Cherry-pick abort")]
+pub(crate) struct CherryPickAbort;
+impl OpTrait for CherryPickAbort {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(super::cmd(
+            vec![],
+            git::cherry_pick_abort_cmd,
+            RefreshScope::All,
+        ))
+    }
+}
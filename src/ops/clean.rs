@@ -0,0 +1,144 @@
+use super::{snapshot::snapshot_with_ignored, Action, OpTrait};
+use crate::{
+    git, items::TargetData, jobs::JobOutput, prompt::PromptData, screen, screen::RefreshScope,
+    state::State, term::Term, ErrorBuffer, Res,
+};
+use derive_more::Display;
+use std::{path::PathBuf, rc::Rc};
+use tui_prompts::State as _;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Show untracked files")]
+pub(crate) struct ShowClean;
+impl OpTrait for ShowClean {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(goto_clean_screen(false))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Show untracked and ignored files")]
+pub(crate) struct ShowCleanIgnored;
+impl OpTrait for ShowCleanIgnored {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(goto_clean_screen(true))
+    }
+}
+
+fn goto_clean_screen(ignored: bool) -> Action {
+    Rc::new(move |state: &mut State, term: &mut Term| {
+        state.screens.push(screen::clean::create(
+            Rc::clone(&state.config),
+            Rc::clone(&state.repo),
+            term.size()?,
+            ignored,
+        )?);
+        Ok(())
+    })
+}
+
+/// Removes the marked files and directories, or the selected one if none are marked.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Clean")]
+pub(crate) struct Clean;
+impl OpTrait for Clean {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let selected = match target {
+            Some(TargetData::File(f)) => Some(f.clone()),
+            _ => None,
+        };
+
+        Some(Rc::new(move |state: &mut State, _term: &mut Term| {
+            let marked = state
+                .screen()
+                .marked_targets()
+                .into_iter()
+                .filter_map(|target| match target {
+                    TargetData::File(f) => Some(f.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            let paths = if marked.is_empty() {
+                selected.clone().into_iter().collect()
+            } else {
+                marked
+            };
+
+            let described = match &paths[..] {
+                [] => return Err("Mark the files to clean, or select one".into()),
+                [path] => git::display_path(path).into_owned(),
+                paths => format!("{} files", paths.len()),
+            };
+
+            state.prompt.set(PromptData {
+                prompt_text: format!("Really clean {}? (y or n)", described).into(),
+                update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+                    if state.prompt.state.status().is_pending() {
+                        match state.prompt.state.value() {
+                            "y" => {
+                                clean(state, term, &paths, &described)?;
+                                state.prompt.reset(term)?;
+                            }
+                            "" => (),
+                            _ => {
+                                state.error_buffer = Some(ErrorBuffer("Clean aborted".to_string()));
+                                state.prompt.reset(term)?;
+                            }
+                        }
+                    }
+                    Ok(())
+                }),
+            });
+
+            Ok(())
+        }))
+    }
+}
+
+/// Into the trash if it's enabled, as discarded files are, or else gone for good.
+/// Snapshotted first either way, ignored files included.
+fn clean(state: &mut State, term: &mut Term, paths: &[PathBuf], described: &str) -> Res<()> {
+    let ignored = paths
+        .iter()
+        .filter(|path| state.repo.status_should_ignore(path).unwrap_or(false))
+        .cloned()
+        .collect::<Vec<_>>();
+    let any_ignored = !ignored.is_empty();
+    snapshot_with_ignored(state, format!("before cleaning {}", described), ignored);
+
+    if state.config.general.trash.enabled {
+        trash(state, paths.to_vec(), described);
+        return Ok(());
+    }
+
+    state.run_external_cmd(
+        term,
+        &[],
+        git::clean_cmd(paths, any_ignored),
+        RefreshScope::All,
+    )
+}
+
+/// Queued like commands are, so that it's only gone once the snapshot queued before is taken.
+fn trash(state: &mut State, paths: Vec<PathBuf>, described: &str) {
+    let dirs = git::RepoDirs::of(&state.repo);
+    let retention_days = state.config.general.trash.retention_days;
+
+    state.spawn_job(
+        format!("Clean {}", described),
+        Box::new(move || {
+            let repo = dirs.open().map_err(|err| err.to_string())?;
+            for path in &paths {
+                git::trash::move_to_trash(&repo, path, retention_days)
+                    .map_err(|err| err.to_string())?;
+            }
+
+            Ok(JobOutput {
+                out: String::new(),
+                success: true,
+            })
+        }),
+        Box::new(|state, _term, _output| state.refresh_screen(&RefreshScope::All)),
+    );
+}
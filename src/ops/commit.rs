@@ -1,7 +1,17 @@
-use super::{subscreen_arg, Action, OpTrait};
-use crate::{git, items::TargetData, state::State, term::Term};
+use super::{offer_no_verify, Action, OpTrait};
+use crate::{
+    git::{self, commit_lint},
+    hooks::Hook,
+    items::TargetData,
+    prompt::PromptData,
+    state::State,
+    term::Term,
+    ErrorBuffer, Res,
+};
 use derive_more::Display;
+use itertools::Itertools;
 use std::{process::Command, rc::Rc};
+use tui_prompts::State as _;
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
 #[display(fmt = "Commit")]
@@ -12,12 +22,88 @@ impl OpTrait for Commit {
             let mut cmd = Command::new("git");
             cmd.args(["commit"]);
 
-            state.issue_subscreen_command(term, cmd)?;
+            edit_and_lint(state, term, cmd, None)
+        }))
+    }
+}
+
+/// Asks for the type and scope first, tab completing ones used before, and then
+/// has the message start with them.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Commit conventional")]
+pub(crate) struct CommitConventional;
+impl OpTrait for CommitConventional {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            let (types, scopes) = commit_lint::recent_types_and_scopes(&state.repo)?;
+            let completions = types.clone();
+
+            state.prompt.set_with_completions(
+                PromptData {
+                    prompt_text: format!("Type ({}):", suggested(&types)).into(),
+                    update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+                        if state.prompt.state.status().is_done() {
+                            let kind = match state.prompt.state.value().trim() {
+                                "" => types[0].clone(),
+                                kind => kind.to_string(),
+                            };
+                            state.prompt.reset(term)?;
+                            ask_scope(state, kind, scopes.clone());
+                        }
+                        Ok(())
+                    }),
+                },
+                completions,
+            );
             Ok(())
         }))
     }
 }
 
+fn ask_scope(state: &mut State, kind: String, scopes: Vec<String>) {
+    let prompt_text = if scopes.is_empty() {
+        format!("Scope of {} (none if left empty):", kind)
+    } else {
+        format!(
+            "Scope of {} ({}, none if left empty):",
+            kind,
+            suggested(&scopes)
+        )
+    };
+
+    state.prompt.set_with_completions(
+        PromptData {
+            prompt_text: prompt_text.into(),
+            update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+                if state.prompt.state.status().is_done() {
+                    let subject = match state.prompt.state.value().trim() {
+                        "" => format!("{}: ", kind),
+                        scope => format!("{}({}): ", kind, scope),
+                    };
+                    state.prompt.reset(term)?;
+
+                    let mut cmd = Command::new("git");
+                    cmd.args(["commit"]);
+                    edit_and_lint(state, term, cmd, Some(subject))?;
+                }
+                Ok(())
+            }),
+        },
+        scopes,
+    );
+}
+
+/// The first few to pick from, the first is what's picked when left empty.
+fn suggested(all: &[String]) -> String {
+    let mut suggested = all.iter().take(SUGGESTED).join(", ");
+    if all.len() > SUGGESTED {
+        suggested.push('…');
+    }
+    suggested
+}
+
+const SUGGESTED: usize = 5;
+
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
 #[display(fmt = "Commit amend")]
 pub(crate) struct CommitAmend;
@@ -27,8 +113,7 @@ impl OpTrait for CommitAmend {
             let mut cmd = Command::new("git");
             cmd.args(["commit", "--amend"]);
 
-            state.issue_subscreen_command(term, cmd)?;
-            Ok(())
+            edit_and_lint(state, term, cmd, None)
         }))
     }
 }
@@ -38,8 +123,10 @@ impl OpTrait for CommitAmend {
 pub(crate) struct CommitFixup;
 impl OpTrait for CommitFixup {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
-        let action = match target {
-            Some(TargetData::Commit(r)) => subscreen_arg(git::commit_fixup_cmd, r.into()),
+        let action: Action = match target.cloned() {
+            Some(TargetData::Commit(r)) => Rc::new(move |state: &mut State, term: &mut Term| {
+                commit_and_run_hook(state, term, git::commit_fixup_cmd(r.as_ref()))
+            }),
             _ => return None,
         };
 
@@ -49,3 +136,124 @@ impl OpTrait for CommitFixup {
         true
     }
 }
+
+fn commit_and_run_hook(state: &mut State, term: &mut Term, mut cmd: Command) -> Res<()> {
+    let retry = crate::copy_cmd(&cmd);
+    let trace = git::hook_failure::trace(&mut cmd, &state.repo);
+    let success = state.issue_logged_subscreen_command(term, cmd)?.success();
+    let failed_hook = git::hook_failure::failed_hook(&trace);
+
+    if success {
+        state.run_hook(Hook::AfterCommit);
+    } else if let Some(hook) = failed_hook {
+        offer_no_verify(state, hook, retry, commit_and_run_hook);
+    } else {
+        // Git didn't say why, all there is to go by is the lock being there
+        state.recover_from_index_lock(Rc::new(move |state, term| {
+            commit_and_run_hook(state, term, crate::copy_cmd(&retry))
+        }));
+    }
+
+    Ok(())
+}
+
+/// With a linter configured, the message is linted after it's written and before it's
+/// committed. If there's something wrong, it's for the user to decide what to do about it.
+/// The message starts out as `draft` if there's one.
+fn edit_and_lint(
+    state: &mut State,
+    term: &mut Term,
+    mut cmd: Command,
+    draft: Option<String>,
+) -> Res<()> {
+    let Some(linter) = state.config.general.commit_lint.clone() else {
+        if let Some(draft) = draft {
+            cmd.arg("--edit").arg("--message").arg(draft);
+        }
+        return commit_and_run_hook(state, term, cmd);
+    };
+
+    let cleanup = commit_lint::Cleanup::of(&state.repo)?;
+    let edit = commit_lint::edit_cmd(
+        &state.repo,
+        crate::copy_cmd(&cmd),
+        &cleanup,
+        draft.as_deref(),
+    )?;
+    state.issue_logged_subscreen_command(term, edit)?;
+
+    let Some(edited) = commit_lint::take_edited(&state.repo) else {
+        // Git stopped before there was anything to edit, it said why
+        state.recover_from_index_lock(Rc::new(move |state, term| {
+            edit_and_lint(state, term, crate::copy_cmd(&cmd), None)
+        }));
+        return Ok(());
+    };
+
+    // Git failing was the editor's doing, it isn't worth showing
+    state.cmd_meta_buffer = None;
+
+    let message = cleanup.message(&edited);
+    if message.is_empty() {
+        state.error_buffer = Some(ErrorBuffer(
+            "Aborting commit due to empty commit message".to_string(),
+        ));
+        return Ok(());
+    }
+
+    let problems = commit_lint::lint(&state.repo, &linter, &message)?;
+    if problems.is_empty() {
+        let commit = commit_lint::commit_cmd(&state.repo, cmd, &message)?;
+        return commit_and_run_hook(state, term, commit);
+    }
+
+    offer_commit_anyway(state, cmd, cleanup, message, linter, problems);
+    Ok(())
+}
+
+fn offer_commit_anyway(
+    state: &mut State,
+    cmd: Command,
+    cleanup: commit_lint::Cleanup,
+    message: String,
+    linter: String,
+    problems: Vec<String>,
+) {
+    let more = match problems.len() {
+        1 => String::new(),
+        n => format!(" (and {} more)", n - 1),
+    };
+
+    let mut cmd = Some(cmd);
+    state.prompt.set(PromptData {
+        prompt_text: format!(
+            "{}{}. Edit (e), commit anyway (y) or cancel (n)?",
+            problems[0].trim_end_matches('.'),
+            more
+        )
+        .into(),
+        update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+            if state.prompt.state.status().is_pending() {
+                match state.prompt.state.value() {
+                    "e" => {
+                        state.prompt.reset(term)?;
+                        if let Some(cmd) = cmd.take() {
+                            let draft = cleanup.draft(&message, &linter, &problems);
+                            edit_and_lint(state, term, cmd, Some(draft))?;
+                        }
+                    }
+                    "y" => {
+                        state.prompt.reset(term)?;
+                        if let Some(cmd) = cmd.take() {
+                            let commit = commit_lint::commit_cmd(&state.repo, cmd, &message)?;
+                            commit_and_run_hook(state, term, commit)?;
+                        }
+                    }
+                    "" => (),
+                    _ => state.prompt.reset(term)?,
+                }
+            }
+            Ok(())
+        }),
+    });
+}
@@ -0,0 +1,68 @@
+use super::{Action, OpTrait};
+use crate::{git, items::TargetData, prompt::PromptData, screen, state::State, term::Term, Res};
+use derive_more::Display;
+use std::rc::Rc;
+use tui_prompts::State as _;
+
+/// Asks for the ref, the upstream being the default, to see what a push would bring there.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Compare worktree with ref")]
+pub(crate) struct CompareWorktree;
+impl OpTrait for CompareWorktree {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            ask_reference(state, "Compare worktree with", true);
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Compare HEAD with ref")]
+pub(crate) struct CompareHead;
+impl OpTrait for CompareHead {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            ask_reference(state, "Compare HEAD with", false);
+            Ok(())
+        }))
+    }
+}
+
+fn ask_reference(state: &mut State, text: &str, worktree: bool) {
+    let upstream = git::upstream_name(&state.repo);
+    let prompt_text = match &upstream {
+        Some(upstream) => format!("{} (default {}):", text, upstream).into(),
+        None => format!("{}:", text).into(),
+    };
+
+    state.prompt.set(PromptData {
+        prompt_text,
+        update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+            if state.prompt.state.status().is_done() {
+                let reference = match (state.prompt.state.value().trim(), &upstream) {
+                    ("", Some(default)) => default.clone(),
+                    (value, _) => value.to_string(),
+                };
+                state.prompt.reset(term)?;
+                compare(state, term, reference, worktree)?;
+            }
+            Ok(())
+        }),
+    });
+}
+
+fn compare(state: &mut State, term: &mut Term, reference: String, worktree: bool) -> Res<()> {
+    if reference.is_empty() {
+        return Err("There's no upstream, give a ref to compare with".into());
+    }
+
+    state.screens.push(screen::compare::create(
+        Rc::clone(&state.config),
+        Rc::clone(&state.repo),
+        term.size()?,
+        reference,
+        worktree,
+    )?);
+    Ok(())
+}
@@ -1,5 +1,8 @@
-use super::{cmd, cmd_arg, Action, OpTrait};
-use crate::{git, items::TargetData, prompt::PromptData, state::State, term::Term, ErrorBuffer};
+use super::{cmd, cmd_arg, snapshot::snapshot_first, trash::trash_first, Action, OpTrait};
+use crate::{
+    git, items::TargetData, jobs::JobOutput, prompt::PromptData, screen::RefreshScope,
+    state::State, term::Term, ErrorBuffer,
+};
 use derive_more::Display;
 use std::{path::PathBuf, rc::Rc};
 use tui_prompts::State as _;
@@ -10,26 +13,38 @@ pub(crate) struct Discard;
 impl OpTrait for Discard {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
         let mut action = match target.cloned() {
-            Some(TargetData::Branch(r)) => cmd_arg(git::discard_branch, r.into()),
-            Some(TargetData::File(f)) => Rc::new(move |state: &mut State, _term: &mut Term| {
-                let path = PathBuf::from_iter([
-                    state.repo.workdir().expect("No workdir").to_path_buf(),
-                    f.clone(),
-                ]);
-                std::fs::remove_file(path)?;
-                state.screen_mut().update()
-            }),
-            Some(TargetData::Delta(d)) => {
-                if d.old_file == d.new_file {
-                    cmd_arg(git::checkout_file_cmd, d.old_file.into())
-                } else {
-                    // TODO Discard file move
-                    return None;
-                }
+            Some(TargetData::Branch(r)) => {
+                cmd_arg(git::discard_branch, r.into(), RefreshScope::All)
+            }
+            Some(TargetData::File(f)) => snapshot_first(
+                format!("before discarding {}", f.display()),
+                Rc::new(move |state: &mut State, _term: &mut Term| {
+                    discard_untracked(state, f.clone());
+                    Ok(())
+                }),
+            ),
+            Some(TargetData::Delta(d)) if d.old_file == d.new_file => {
+                let scope = RefreshScope::Paths(vec![d.old_file.clone()]);
+                snapshot_first(
+                    format!("before discarding changes to {}", d.old_file.display()),
+                    trash_first(
+                        d.old_file.clone(),
+                        cmd_arg(git::checkout_file_cmd, d.old_file.into(), scope),
+                    ),
+                )
             }
-            Some(TargetData::Hunk(h)) => cmd(
-                h.format_patch().into_bytes(),
-                git::discard_unstaged_patch_cmd,
+            // TODO Discard file move
+            Some(TargetData::Delta(_)) => return None,
+            Some(TargetData::Hunk(h)) => snapshot_first(
+                format!("before discarding a hunk of {}", h.new_file.display()),
+                trash_first(
+                    h.new_file.clone(),
+                    cmd(
+                        h.patch_bytes(),
+                        git::discard_unstaged_patch_cmd,
+                        RefreshScope::Paths(vec![h.new_file.clone()]),
+                    ),
+                ),
             ),
             _ => return None,
         };
@@ -66,3 +81,32 @@ impl OpTrait for Discard {
         true
     }
 }
+
+/// Queued like commands are, so that it's only gone once a snapshot queued before is taken.
+fn discard_untracked(state: &mut State, file: PathBuf) {
+    let dirs = git::RepoDirs::of(&state.repo);
+    let trash = state.config.general.trash.enabled;
+    let retention_days = state.config.general.trash.retention_days;
+    let scope = RefreshScope::Paths(vec![file.clone()]);
+
+    state.spawn_job(
+        format!("Discard {}", file.display()),
+        Box::new(move || {
+            let repo = dirs.open().map_err(|err| err.to_string())?;
+            let result = if trash {
+                git::trash::move_to_trash(&repo, &file, retention_days)
+            } else {
+                let path =
+                    PathBuf::from_iter([repo.workdir().expect("No workdir").to_path_buf(), file]);
+                std::fs::remove_file(path).map_err(Into::into)
+            };
+            result.map_err(|err| err.to_string())?;
+
+            Ok(JobOutput {
+                out: String::new(),
+                success: true,
+            })
+        }),
+        Box::new(move |state, _term, _output| state.refresh_screen(&scope)),
+    );
+}
@@ -1,5 +1,5 @@
 use super::{Action, OpTrait, SubmenuOp};
-use crate::items::TargetData;
+use crate::{hooks::Hook, items::TargetData, screen::RefreshScope};
 use derive_more::Display;
 use std::rc::Rc;
 
@@ -25,12 +25,47 @@ impl OpTrait for Submenu {
     }
 }
 
+/// Switches an argument of the commands in the open submenu on or off.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "{}", _0)]
+pub(crate) struct ToggleArg(pub &'static str);
+impl OpTrait for ToggleArg {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        let arg = self.0;
+        Some(Rc::new(move |state, _term| {
+            if let Some(index) = state.pending_args.iter().position(|a| *a == arg) {
+                state.pending_args.remove(index);
+            } else {
+                state.pending_args.push(arg);
+            }
+            Ok(())
+        }))
+    }
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
 #[display(fmt = "Refresh")]
 pub(crate) struct Refresh;
 impl OpTrait for Refresh {
     fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
-        Some(Rc::new(|state, _term| state.screen_mut().update()))
+        Some(Rc::new(|state, _term| {
+            state.refresh_screen(&RefreshScope::All)?;
+            state.run_hook(Hook::OnRefresh);
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Scan untracked")]
+pub(crate) struct ScanUntracked;
+impl OpTrait for ScanUntracked {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state, _term| {
+            state.refresh_screen(&RefreshScope::ScanUntracked)?;
+            state.run_hook(Hook::OnRefresh);
+            Ok(())
+        }))
     }
 }
 
@@ -46,6 +81,15 @@ impl OpTrait for ToggleSection {
     }
 }
 
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Toggle mark")]
+pub(crate) struct ToggleMark;
+impl OpTrait for ToggleMark {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state, _term| state.screen_mut().toggle_mark()))
+    }
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
 #[display(fmt = "Select previous")]
 pub(crate) struct SelectPrevious;
@@ -1,5 +1,5 @@
 use super::{Action, OpTrait};
-use crate::items::TargetData;
+use crate::{items::TargetData, screen::RefreshScope};
 use derive_more::Display;
 use std::{process::Command, rc::Rc};
 
@@ -12,7 +12,7 @@ impl OpTrait for FetchAll {
             let mut cmd = Command::new("git");
             cmd.args(["fetch", "--all"]);
 
-            state.run_external_cmd(term, &[], cmd)?;
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
             Ok(())
         }))
     }
@@ -0,0 +1,137 @@
+use super::{Action, OpTrait};
+use crate::{
+    items::TargetData, prompt::PromptData, screen::RefreshScope, state::State, term::Term, Res,
+};
+use derive_more::Display;
+use std::{process::Command, rc::Rc};
+use tui_prompts::State as _;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Run git command")]
+pub(crate) struct GitCommand;
+impl OpTrait for GitCommand {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            state.prompt.set(PromptData {
+                prompt_text: "Run git command:".into(),
+                update_fn: Rc::new(git_command_prompt_update),
+            });
+            Ok(())
+        }))
+    }
+}
+
+fn git_command_prompt_update(state: &mut State, term: &mut Term) -> Res<()> {
+    if state.prompt.state.status().is_done() {
+        let input = state.prompt.state.value().to_string();
+        state.prompt.reset(term)?;
+
+        let mut args = split_args(&input)?;
+        // Typing out the "git" is a hard habit to break
+        if args.first().is_some_and(|arg| arg == "git") {
+            args.remove(0);
+        }
+
+        if args.is_empty() {
+            return Err("No git command given".into());
+        }
+
+        let opens_editor = opens_editor(&args);
+        let mut cmd = Command::new("git");
+        cmd.args(args);
+
+        if opens_editor {
+            state.issue_subscreen_command(term, cmd)?;
+        } else {
+            state.run_streamed_cmd(term, cmd, RefreshScope::All)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether git opens an editor to run `args`, which then needs the terminal.
+fn opens_editor(args: &[String]) -> bool {
+    let has = |flags: &[&str]| {
+        args.iter().skip(1).any(|arg| {
+            flags
+                .iter()
+                .any(|flag| arg == flag || arg.starts_with(&format!("{}=", flag)))
+        })
+    };
+
+    has(&["-e", "--edit"])
+        || match args[0].as_str() {
+            "commit" => !has(&[
+                "-m",
+                "--message",
+                "-F",
+                "--file",
+                "-C",
+                "--reuse-message",
+                "--no-edit",
+            ]),
+            "rebase" => has(&["-i", "--interactive"]),
+            "revert" => !has(&["--no-edit", "--continue", "--abort", "--skip", "--quit"]),
+            _ => false,
+        }
+}
+
+/// Splits a command line into arguments the way a shell would, minus expansions.
+fn split_args(input: &str) -> Res<Vec<String>> {
+    let mut args = vec![];
+    let mut arg = None::<String>;
+    let mut quote = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    arg.get_or_insert_with(String::new).push(escaped);
+                }
+            }
+            (Some(_), c) => arg.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                arg.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(arg.take()),
+            (None, c) => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err("Unterminated quote".into());
+    }
+
+    args.extend(arg);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{opens_editor, split_args};
+
+    #[test]
+    fn split_args_like_a_shell() {
+        assert_eq!(
+            split_args(r#"commit -m "a message" --author='A <a@b>' \"x ''"#).unwrap(),
+            ["commit", "-m", "a message", "--author=A <a@b>", "\"x", ""]
+        );
+        assert!(split_args("log 'oops").is_err());
+    }
+
+    #[test]
+    fn commands_opening_an_editor() {
+        let opens = |input| opens_editor(&split_args(input).unwrap());
+        assert!(opens("commit"));
+        assert!(opens("commit --amend"));
+        assert!(opens("rebase -i HEAD~3"));
+        assert!(opens("merge -e main"));
+        assert!(!opens("commit -m 'a message'"));
+        assert!(!opens("commit --message=fix"));
+        assert!(!opens("rebase main"));
+        assert!(!opens("log --oneline"));
+    }
+}
@@ -0,0 +1,202 @@
+use super::{cmd_arg, Action, OpTrait};
+use crate::{
+    git::{self, index_flags::IndexFlag},
+    items::TargetData,
+    screen::{self, RefreshScope},
+    CmdMetaBuffer, Res,
+};
+use derive_more::Display;
+use std::{
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum IgnorePattern {
+    /// Just the file itself, `/dir/file.ext`
+    Path,
+    /// Every file with the same extension, `*.ext`
+    Extension,
+    /// The directory the file is in, `/dir/`
+    Directory,
+}
+
+/// Appends a pattern matching an untracked file to the root `.gitignore`.
+/// Files that shouldn't be ignored for everyone go in `.git/info/exclude` when `local` is set.
+pub(crate) struct Ignore {
+    pub(crate) pattern: IgnorePattern,
+    pub(crate) local: bool,
+}
+
+impl Display for Ignore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self.pattern {
+            IgnorePattern::Path => "Ignore path",
+            IgnorePattern::Extension => "Ignore extension",
+            IgnorePattern::Directory => "Ignore directory",
+        })?;
+
+        if self.local {
+            f.write_str(" locally")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl OpTrait for Ignore {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let Some(TargetData::File(file)) = target else {
+            return None;
+        };
+
+        let pattern = pattern(self.pattern, file)?;
+        let local = self.local;
+
+        Some(Rc::new(move |state, _term| {
+            let ignore_file = if local {
+                state.repo.path().join("info").join("exclude")
+            } else {
+                state.repo.workdir().expect("No workdir").join(".gitignore")
+            };
+
+            append_line(&ignore_file, &pattern)?;
+
+            let shown_path = ignore_file
+                .strip_prefix(state.repo.workdir().expect("No workdir"))
+                .unwrap_or(&ignore_file);
+
+            state.cmd_meta_buffer = Some(CmdMetaBuffer {
+                args: format!("Added {} to {}", pattern, shown_path.display()).into(),
+                out: Some(String::new()),
+            });
+
+            state.refresh_screen(&RefreshScope::All)
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+/// Ignored files show up in the status while this is on, to find out what ignores them.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Toggle ignored files")]
+pub(crate) struct ToggleIgnored;
+impl OpTrait for ToggleIgnored {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state, _term| {
+            state.screen_mut().collapse(screen::status::IGNORED_SECTION);
+            state.refresh_screen(&RefreshScope::ToggleIgnored)
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Force add")]
+pub(crate) struct ForceAdd;
+impl OpTrait for ForceAdd {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let Some(TargetData::File(file)) = target else {
+            return None;
+        };
+
+        Some(cmd_arg(
+            git::force_add_file_cmd,
+            file.clone().into(),
+            RefreshScope::Paths(vec![file.clone()]),
+        ))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+/// Sets the bit if it isn't set, clears it otherwise.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Toggle {}", _0)]
+pub(crate) struct ToggleIndexFlag(pub IndexFlag);
+impl OpTrait for ToggleIndexFlag {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let file = match target {
+            Some(TargetData::File(f)) => f.clone(),
+            Some(TargetData::Delta(d)) => d.new_file.clone(),
+            _ => return None,
+        };
+        let flag = self.0;
+
+        Some(Rc::new(move |state, term| {
+            let set = git::index_flags::is_set(&state.repo, &file, flag)?;
+            state.run_external_cmd(
+                term,
+                &[],
+                git::index_flags::set_cmd(flag, !set, file.as_os_str()),
+                RefreshScope::All,
+            )
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+fn pattern(pattern: IgnorePattern, file: &Path) -> Option<String> {
+    let path = file.to_str()?;
+
+    match pattern {
+        IgnorePattern::Path => Some(format!("/{}", path)),
+        IgnorePattern::Extension => file
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|_| !path.ends_with('/'))
+            .map(|ext| format!("*.{}", ext)),
+        IgnorePattern::Directory => {
+            let parent = PathBuf::from(path.trim_end_matches('/'));
+            let parent = parent.parent()?.to_str()?;
+            (!parent.is_empty()).then(|| format!("/{}/", parent))
+        }
+    }
+}
+
+fn append_line(file: &Path, line: &str) -> Res<()> {
+    let existing = fs::read_to_string(file).unwrap_or_default();
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut out = OpenOptions::new().create(true).append(true).open(file)?;
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        writeln!(out)?;
+    }
+    writeln!(out, "{}", line)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pattern, IgnorePattern};
+    use std::path::Path;
+
+    #[test]
+    fn patterns_are_anchored_to_the_root() {
+        let file = Path::new("src/generated/out.rs");
+        assert_eq!(
+            pattern(IgnorePattern::Path, file).as_deref(),
+            Some("/src/generated/out.rs")
+        );
+        assert_eq!(
+            pattern(IgnorePattern::Extension, file).as_deref(),
+            Some("*.rs")
+        );
+        assert_eq!(
+            pattern(IgnorePattern::Directory, file).as_deref(),
+            Some("/src/generated/")
+        );
+        assert_eq!(pattern(IgnorePattern::Directory, Path::new("top")), None);
+        assert_eq!(pattern(IgnorePattern::Extension, Path::new("dir.d/")), None);
+    }
+}
@@ -21,7 +21,7 @@ pub(crate) struct LogOther;
 impl OpTrait for LogOther {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
         match target.cloned() {
-            Some(TargetData::Commit(r) | TargetData::Branch(r)) => {
+            Some(TargetData::Commit(r) | TargetData::Branch(r) | TargetData::Tag(r)) => {
                 Some(Rc::new(move |state, _term| {
                     goto_log_screen(state, Some(r.clone()));
                     Ok(())
@@ -35,6 +35,23 @@ impl OpTrait for LogOther {
     }
 }
 
+/// Walks all of the history of HEAD, which takes a while in big repositories.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Insights")]
+pub(crate) struct ShowInsights;
+impl OpTrait for ShowInsights {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            state.screens.push(screen::insights::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?);
+            Ok(())
+        }))
+    }
+}
+
 fn goto_log_screen(state: &mut State, reference: Option<String>) {
     state.screens.drain(1..);
     let size = state.screens.last().unwrap().size;
@@ -44,6 +61,7 @@ fn goto_log_screen(state: &mut State, reference: Option<String>) {
             Rc::clone(&state.repo),
             size,
             reference,
+            None,
         )
         .expect("Couldn't create screen"),
     );
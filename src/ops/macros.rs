@@ -0,0 +1,50 @@
+use super::{Action, OpTrait};
+use crate::items::TargetData;
+use derive_more::Display;
+use std::rc::Rc;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Record macro")]
+pub(crate) struct RecordMacro;
+impl OpTrait for RecordMacro {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state, _term| {
+            match state.recording.take() {
+                Some(keys) => state.keyboard_macro = keys,
+                None => state.recording = Some(vec![]),
+            }
+            Ok(())
+        }))
+    }
+}
+
+/// Presses the keys of the last macro again. Like in vim, it stops at the first error,
+/// so that replaying over many items doesn't go on once it runs out of them.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Replay macro")]
+pub(crate) struct ReplayMacro;
+impl OpTrait for ReplayMacro {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state, term| {
+            if state.recording.is_some() {
+                return Err("Can't replay a macro while recording one".into());
+            }
+            if state.keyboard_macro.is_empty() {
+                return Err("No macro recorded yet".into());
+            }
+
+            for key in state.keyboard_macro.clone() {
+                state.handle_key(term, key)?;
+                state.update_prompt(term)?;
+                // What a key does should be done before the next one acts on the screen
+                state.await_jobs(term);
+
+                if state.quit || state.error_buffer.is_some() {
+                    break;
+                }
+            }
+
+            Ok(())
+        }))
+    }
+}
@@ -1,25 +1,55 @@
-use crate::{items::TargetData, state::State, term::Term, Res};
+use crate::{
+    git, items::TargetData, prompt::PromptData, screen::RefreshScope, state::State, term::Term,
+    ErrorBuffer, Res,
+};
 use std::{
     ffi::{OsStr, OsString},
     fmt::Display,
     process::Command,
     rc::Rc,
 };
+use tui_prompts::State as _;
 
+pub(crate) mod bisect;
+pub(crate) mod blame;
+pub(crate) mod browse;
 pub(crate) mod checkout;
+pub(crate) mod cherry_pick;
+pub(crate) mod clean;
 pub(crate) mod commit;
+pub(crate) mod compare;
 pub(crate) mod discard;
 pub(crate) mod editor;
 pub(crate) mod fetch;
+pub(crate) mod git_command;
+pub(crate) mod ignore;
 pub(crate) mod log;
+pub(crate) mod macros;
+pub(crate) mod notes;
+pub(crate) mod patch;
 pub(crate) mod pull;
+pub(crate) mod pull_requests;
 pub(crate) mod push;
 pub(crate) mod rebase;
+pub(crate) mod recent;
+pub(crate) mod rename;
 pub(crate) mod reset;
+pub(crate) mod restore;
+pub(crate) mod revert;
+pub(crate) mod shell;
 pub(crate) mod show;
 pub(crate) mod show_refs;
+pub(crate) mod snapshot;
 pub(crate) mod stage;
+pub(crate) mod submodule;
+pub(crate) mod tab;
+pub(crate) mod tag;
+pub(crate) mod time_machine;
+pub(crate) mod trash;
+pub(crate) mod undo;
 pub(crate) mod unstage;
+pub(crate) mod worktree;
+pub(crate) mod yank;
 
 pub(crate) type Action = Rc<dyn FnMut(&mut State, &mut Term) -> Res<()>>;
 
@@ -39,36 +69,122 @@ pub(crate) trait OpTrait: Display {
 pub(crate) enum Op {
     Quit,
     Refresh,
+    ScanUntracked,
+    ToggleIgnored,
+    Shell,
+    Suspend,
+    RecordMacro,
+    ReplayMacro,
+    NewTab,
+    NextTab,
+    PreviousTab,
+    GoToTab(usize),
+    ToggleArg(&'static str),
 
     ToggleSection,
+    ToggleMark,
     SelectNext,
     SelectPrevious,
     HalfPageUp,
     HalfPageDown,
 
+    Am,
+    AmAbort,
+    AmContinue,
+    AmSkip,
+    ApplyPatch,
+    ApplyPatchCached,
+    BisectBad,
+    BisectGood,
+    BisectReset,
+    BisectRun,
+    BisectSkip,
+    BisectStart,
     Checkout,
     CheckoutNewBranch,
+    CheckoutPrevious,
+    CherryPickAbort,
+    CherryPickContinue,
+    CherryPickSkip,
     Commit,
     CommitAmend,
+    CommitConventional,
     FetchAll,
+    GitCommand,
     LogCurrent,
+    ShowInsights,
     Pull,
     Push,
     RebaseAbort,
     RebaseContinue,
+    RevertAbort,
+    RevertContinue,
+    ShowPullRequests,
     ShowRefs,
+    ShowSubmodules,
+    ShowTags,
+    CreateTag(tag::TagKind),
+    PushTags,
+    SubmoduleInit,
+    SubmoduleSync,
+    SubmoduleUpdate,
+    ShowWorktrees,
+    ShowRecentRepos,
+    ShowSnapshots,
+    ShowTrash,
+    ShowClean,
+    ShowCleanIgnored,
+    Restore,
+    Undo,
+    WorktreeAdd,
+    OlderRevision,
+    NewerRevision,
 
+    Blame,
+    BlameParent,
+    TimeMachine,
+    Browse,
+    BrowseCi,
+    CheckoutPullRequest,
+    CherryPick,
+    CherryPickRange,
+    Clean,
     CommitFixup,
+    CompareWorktree,
+    CompareHead,
+    DeleteRemoteTag,
+    DeleteTag,
     Discard,
+    ForceAdd,
+    ToggleIndexFlag(git::index_flags::IndexFlag),
+    FormatPatch,
+    FormatPatchRange,
+    Ignore(ignore::IgnorePattern),
+    IgnoreLocally(ignore::IgnorePattern),
     LogOther,
+    NoteEdit,
+    NoteRemove,
+    OpenInEditor,
     RebaseAutosquash,
     RebaseInteractive,
+    RenameFile,
     ResetSoft,
     ResetMixed,
     ResetHard,
+    ResetKeep,
+    Revert,
+    RevertRange,
     Show,
+    PushTag,
     Stage,
+    SubmoduleEnter,
     Unstage,
+    WorktreeRemove,
+    WorktreeSwitch,
+    YankHash,
+    YankShortHash,
+    YankPath,
+    YankUrl,
 
     Submenu(SubmenuOp),
 }
@@ -76,17 +192,30 @@ pub(crate) enum Op {
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
 pub(crate) enum SubmenuOp {
     Any,
+    Bisect,
     Branch,
+    CherryPick,
+    Clean,
     Commit,
+    Diff,
     Fetch,
+    Go,
     Help,
+    Ignore,
     Log,
+    Notes,
+    Patch,
     #[default]
     None,
     Pull,
     Push,
     Rebase,
     Reset,
+    Revert,
+    Submodule,
+    Tag,
+    Worktree,
+    Yank,
 }
 
 impl Op {
@@ -95,35 +224,127 @@ impl Op {
             Op::Quit => Box::new(editor::Quit),
             Op::Submenu(submenu) => Box::new(editor::Submenu(submenu)),
             Op::Refresh => Box::new(editor::Refresh),
+            Op::ScanUntracked => Box::new(editor::ScanUntracked),
+            Op::ToggleIgnored => Box::new(ignore::ToggleIgnored),
+            Op::Shell => Box::new(shell::Shell),
+            Op::Suspend => Box::new(shell::Suspend),
+            Op::RecordMacro => Box::new(macros::RecordMacro),
+            Op::ReplayMacro => Box::new(macros::ReplayMacro),
+            Op::NewTab => Box::new(tab::NewTab),
+            Op::NextTab => Box::new(tab::NextTab),
+            Op::PreviousTab => Box::new(tab::PreviousTab),
+            Op::GoToTab(number) => Box::new(tab::GoToTab(number)),
+            Op::ToggleArg(arg) => Box::new(editor::ToggleArg(arg)),
             Op::ToggleSection => Box::new(editor::ToggleSection),
+            Op::ToggleMark => Box::new(editor::ToggleMark),
             Op::SelectNext => Box::new(editor::SelectNext),
             Op::SelectPrevious => Box::new(editor::SelectPrevious),
             Op::HalfPageUp => Box::new(editor::HalfPageUp),
             Op::HalfPageDown => Box::new(editor::HalfPageDown),
 
+            Op::Am => Box::new(patch::Am),
+            Op::AmAbort => Box::new(patch::AmAbort),
+            Op::AmContinue => Box::new(patch::AmContinue),
+            Op::AmSkip => Box::new(patch::AmSkip),
+            Op::ApplyPatch => Box::new(patch::ApplyPatch),
+            Op::ApplyPatchCached => Box::new(patch::ApplyPatchCached),
+            Op::BisectBad => Box::new(bisect::BisectBad),
+            Op::BisectGood => Box::new(bisect::BisectGood),
+            Op::BisectReset => Box::new(bisect::BisectReset),
+            Op::BisectRun => Box::new(bisect::BisectRun),
+            Op::BisectSkip => Box::new(bisect::BisectSkip),
+            Op::BisectStart => Box::new(bisect::BisectStart),
             Op::Checkout => Box::new(checkout::Checkout),
             Op::CheckoutNewBranch => Box::new(checkout::CheckoutNewBranch),
+            Op::CheckoutPrevious => Box::new(checkout::CheckoutPrevious),
+            Op::CherryPickAbort => Box::new(cherry_pick::CherryPickAbort),
+            Op::CherryPickContinue => Box::new(cherry_pick::CherryPickContinue),
+            Op::CherryPickSkip => Box::new(cherry_pick::CherryPickSkip),
             Op::Commit => Box::new(commit::Commit),
             Op::CommitAmend => Box::new(commit::CommitAmend),
+            Op::CommitConventional => Box::new(commit::CommitConventional),
             Op::FetchAll => Box::new(fetch::FetchAll),
+            Op::GitCommand => Box::new(git_command::GitCommand),
             Op::LogCurrent => Box::new(log::LogCurrent),
+            Op::ShowInsights => Box::new(log::ShowInsights),
             Op::Pull => Box::new(pull::Pull),
             Op::Push => Box::new(push::Push),
             Op::RebaseAbort => Box::new(rebase::RebaseAbort),
             Op::RebaseContinue => Box::new(rebase::RebaseContinue),
+            Op::RevertAbort => Box::new(revert::RevertAbort),
+            Op::RevertContinue => Box::new(revert::RevertContinue),
+            Op::ShowPullRequests => Box::new(pull_requests::ShowPullRequests),
             Op::ShowRefs => Box::new(show_refs::ShowRefs),
+            Op::ShowSubmodules => Box::new(submodule::ShowSubmodules),
+            Op::ShowTags => Box::new(tag::ShowTags),
+            Op::CreateTag(kind) => Box::new(tag::CreateTag(kind)),
+            Op::PushTags => Box::new(tag::PushTags),
+            Op::SubmoduleInit => Box::new(submodule::SubmoduleInit),
+            Op::SubmoduleSync => Box::new(submodule::SubmoduleSync),
+            Op::SubmoduleUpdate => Box::new(submodule::SubmoduleUpdate),
+            Op::ShowWorktrees => Box::new(worktree::ShowWorktrees),
+            Op::ShowRecentRepos => Box::new(recent::ShowRecentRepos),
+            Op::ShowSnapshots => Box::new(snapshot::ShowSnapshots),
+            Op::ShowTrash => Box::new(trash::ShowTrash),
+            Op::ShowClean => Box::new(clean::ShowClean),
+            Op::ShowCleanIgnored => Box::new(clean::ShowCleanIgnored),
+            Op::Restore => Box::new(restore::Restore),
+            Op::Undo => Box::new(undo::Undo),
+            Op::WorktreeAdd => Box::new(worktree::WorktreeAdd),
+            Op::OlderRevision => Box::new(time_machine::OlderRevision),
+            Op::NewerRevision => Box::new(time_machine::NewerRevision),
 
+            Op::Blame => Box::new(blame::Blame),
+            Op::BlameParent => Box::new(blame::BlameParent),
+            Op::TimeMachine => Box::new(time_machine::TimeMachine),
+            Op::Browse => Box::new(browse::Browse),
+            Op::BrowseCi => Box::new(browse::BrowseCi),
+            Op::CheckoutPullRequest => Box::new(pull_requests::CheckoutPullRequest),
+            Op::CherryPick => Box::new(cherry_pick::CherryPick),
+            Op::Clean => Box::new(clean::Clean),
+            Op::CherryPickRange => Box::new(cherry_pick::CherryPickRange),
             Op::CommitFixup => Box::new(commit::CommitFixup),
+            Op::CompareWorktree => Box::new(compare::CompareWorktree),
+            Op::CompareHead => Box::new(compare::CompareHead),
+            Op::DeleteRemoteTag => Box::new(tag::DeleteRemoteTag),
+            Op::DeleteTag => Box::new(tag::DeleteTag),
             Op::Discard => Box::new(discard::Discard),
+            Op::ForceAdd => Box::new(ignore::ForceAdd),
+            Op::ToggleIndexFlag(flag) => Box::new(ignore::ToggleIndexFlag(flag)),
+            Op::FormatPatch => Box::new(patch::FormatPatch),
+            Op::FormatPatchRange => Box::new(patch::FormatPatchRange),
+            Op::Ignore(pattern) => Box::new(ignore::Ignore {
+                pattern,
+                local: false,
+            }),
+            Op::IgnoreLocally(pattern) => Box::new(ignore::Ignore {
+                pattern,
+                local: true,
+            }),
             Op::LogOther => Box::new(log::LogOther),
+            Op::NoteEdit => Box::new(notes::NoteEdit),
+            Op::NoteRemove => Box::new(notes::NoteRemove),
+            Op::OpenInEditor => Box::new(show::OpenInEditor),
             Op::RebaseAutosquash => Box::new(rebase::RebaseAutosquash),
             Op::RebaseInteractive => Box::new(rebase::RebaseInteractive),
+            Op::RenameFile => Box::new(rename::RenameFile),
             Op::ResetSoft => Box::new(reset::ResetSoft),
             Op::ResetMixed => Box::new(reset::ResetMixed),
             Op::ResetHard => Box::new(reset::ResetHard),
+            Op::ResetKeep => Box::new(reset::ResetKeep),
+            Op::Revert => Box::new(revert::Revert),
+            Op::RevertRange => Box::new(revert::RevertRange),
             Op::Show => Box::new(show::Show),
+            Op::PushTag => Box::new(tag::PushTag),
             Op::Stage => Box::new(stage::Stage),
+            Op::SubmoduleEnter => Box::new(submodule::SubmoduleEnter),
             Op::Unstage => Box::new(unstage::Unstage),
+            Op::WorktreeRemove => Box::new(worktree::WorktreeRemove),
+            Op::WorktreeSwitch => Box::new(worktree::WorktreeSwitch),
+            Op::YankHash => Box::new(yank::YankHash),
+            Op::YankShortHash => Box::new(yank::YankShortHash),
+            Op::YankPath => Box::new(yank::YankPath),
+            Op::YankUrl => Box::new(yank::YankUrl),
         }
     }
 }
@@ -132,28 +353,93 @@ impl Display for SubmenuOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(match self {
             SubmenuOp::Any => "Any",
+            SubmenuOp::Bisect => "Bisect",
             SubmenuOp::Branch => "Branch",
+            SubmenuOp::CherryPick => "Cherry-pick",
+            SubmenuOp::Clean => "Clean",
             SubmenuOp::Commit => "Commit",
+            SubmenuOp::Diff => "Diff",
             SubmenuOp::Fetch => "Fetch",
+            SubmenuOp::Go => "Go",
             SubmenuOp::Help => "Help",
+            SubmenuOp::Ignore => "Ignore",
             SubmenuOp::Log => "Log",
+            SubmenuOp::Notes => "Notes",
+            SubmenuOp::Patch => "Patch",
             SubmenuOp::None => "None",
             SubmenuOp::Pull => "Pull",
             SubmenuOp::Push => "Push",
             SubmenuOp::Rebase => "Rebase",
             SubmenuOp::Reset => "Reset",
+            SubmenuOp::Revert => "Revert",
+            SubmenuOp::Submodule => "Submodule",
+            SubmenuOp::Tag => "Tag",
+            SubmenuOp::Worktree => "Worktree",
+            SubmenuOp::Yank => "Yank",
         })
     }
 }
 
-pub(crate) fn cmd(input: Vec<u8>, command: fn() -> Command) -> Action {
-    Rc::new(move |state, term| state.run_external_cmd(term, &input, command()))
+/// The commit a target refers to, if any.
+pub(crate) fn commit_reference(target: Option<&TargetData>) -> Option<String> {
+    match target {
+        Some(TargetData::Commit(r) | TargetData::Branch(r) | TargetData::Tag(r)) => Some(r.clone()),
+        Some(TargetData::BlameLine(line)) => Some(line.commit.to_string()),
+        Some(TargetData::Snapshot(id)) => Some(id.clone()),
+        _ => None,
+    }
+}
+
+pub(crate) fn cmd(input: Vec<u8>, command: fn() -> Command, scope: RefreshScope) -> Action {
+    Rc::new(move |state, term| state.run_external_cmd(term, &input, command(), scope.clone()))
 }
 
-pub(crate) fn cmd_arg(command: fn(&OsStr) -> Command, arg: OsString) -> Action {
-    Rc::new(move |state, term| state.run_external_cmd(term, &[], command(&arg)))
+pub(crate) fn cmd_arg(
+    command: fn(&OsStr) -> Command,
+    arg: OsString,
+    scope: RefreshScope,
+) -> Action {
+    Rc::new(move |state, term| state.run_external_cmd(term, &[], command(&arg), scope.clone()))
 }
 
 pub(crate) fn subscreen_arg(command: fn(&OsStr) -> Command, arg: OsString) -> Action {
-    Rc::new(move |state, term| state.issue_subscreen_command(term, command(&arg)))
+    Rc::new(move |state, term| {
+        state.issue_subscreen_command(term, command(&arg))?;
+        Ok(())
+    })
+}
+
+/// The command was rejected by `hook`, which its output in the command log explains.
+/// Offer to `run` the command again with `--no-verify`, if that skips the hook.
+pub(crate) fn offer_no_verify(
+    state: &mut State,
+    hook: String,
+    cmd: Command,
+    run: fn(&mut State, &mut Term, Command) -> Res<()>,
+) {
+    if !git::hook_failure::is_skippable(&hook) {
+        state.error_buffer = Some(ErrorBuffer(format!("The {} hook failed", hook)));
+        return;
+    }
+
+    let mut retry = Some(cmd);
+    state.prompt.set(PromptData {
+        prompt_text: format!("The {} hook failed. Retry with --no-verify? (y or n)", hook).into(),
+        update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+            if state.prompt.state.status().is_pending() {
+                match state.prompt.state.value() {
+                    "y" => {
+                        state.prompt.reset(term)?;
+                        if let Some(mut cmd) = retry.take() {
+                            cmd.arg("--no-verify");
+                            run(state, term, cmd)?;
+                        }
+                    }
+                    "" => (),
+                    _ => state.prompt.reset(term)?,
+                }
+            }
+            Ok(())
+        }),
+    });
 }
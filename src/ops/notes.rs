@@ -0,0 +1,47 @@
+use super::{Action, OpTrait};
+use crate::{items::TargetData, screen::RefreshScope, state::State, term::Term};
+use derive_more::Display;
+use std::{process::Command, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Edit note")]
+pub(crate) struct NoteEdit;
+impl OpTrait for NoteEdit {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let commit = commit(target)?;
+
+        Some(Rc::new(move |state: &mut State, term: &mut Term| {
+            let mut cmd = Command::new("git");
+            cmd.args(["notes", "edit", &commit]);
+
+            state.issue_subscreen_command(term, cmd)?;
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Remove note")]
+pub(crate) struct NoteRemove;
+impl OpTrait for NoteRemove {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let commit = commit(target)?;
+
+        Some(Rc::new(move |state: &mut State, term: &mut Term| {
+            let mut cmd = Command::new("git");
+            cmd.args(["notes", "remove", &commit]);
+
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+        }))
+    }
+}
+
+/// The selected commit, or HEAD if there's none.
+fn commit(target: Option<&TargetData>) -> Option<String> {
+    match target {
+        Some(TargetData::Commit(r) | TargetData::Branch(r) | TargetData::Tag(r)) => Some(r.clone()),
+        Some(TargetData::BlameLine(line)) => Some(line.commit.to_string()),
+        None => Some("HEAD".to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,148 @@
+use super::{cmd, Action, OpTrait};
+use crate::{
+    git, items::TargetData, prompt::PromptData, screen::RefreshScope, state::State, term::Term, Res,
+};
+use derive_more::Display;
+use std::{process::Command, rc::Rc};
+use tui_prompts::State as _;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Format patch")]
+pub(crate) struct FormatPatch;
+impl OpTrait for FormatPatch {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let Some(TargetData::Commit(commit)) = target else {
+            return None;
+        };
+
+        let commit = commit.clone();
+        Some(Rc::new(move |state: &mut State, term: &mut Term| {
+            let mut cmd = Command::new("git");
+            cmd.args(["format-patch", "-1", &commit]);
+
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Format patches since")]
+pub(crate) struct FormatPatchRange;
+impl OpTrait for FormatPatchRange {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let Some(TargetData::Commit(since) | TargetData::Branch(since) | TargetData::Tag(since)) =
+            target
+        else {
+            return None;
+        };
+
+        let range = format!("{}..HEAD", since);
+        Some(Rc::new(move |state: &mut State, term: &mut Term| {
+            let mut cmd = Command::new("git");
+            cmd.args(["format-patch", &range]);
+
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Apply patch")]
+pub(crate) struct ApplyPatch;
+impl OpTrait for ApplyPatch {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        patch_file_prompt("Apply patch", &["apply"])
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Apply patch to index")]
+pub(crate) struct ApplyPatchCached;
+impl OpTrait for ApplyPatchCached {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        patch_file_prompt("Apply patch to index", &["apply", "--cached"])
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Apply mailbox")]
+pub(crate) struct Am;
+impl OpTrait for Am {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        patch_file_prompt("Apply mailbox", &["am", "--3way"])
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Am continue")]
+pub(crate) struct AmContinue;
+impl OpTrait for AmContinue {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(cmd(vec![], git::am_continue_cmd, RefreshScope::All))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Am skip")]
+pub(crate) struct AmSkip;
+impl OpTrait for AmSkip {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(cmd(vec![], git::am_skip_cmd, RefreshScope::All))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Am abort")]
+pub(crate) struct AmAbort;
+impl OpTrait for AmAbort {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(cmd(vec![], git::am_abort_cmd, RefreshScope::All))
+    }
+}
+
+/// Asks for a patch file, suggesting the selected file, and runs `git <args> <file>`.
+fn patch_file_prompt(text: &'static str, args: &'static [&'static str]) -> Option<Action> {
+    Some(Rc::new(move |state: &mut State, _term: &mut Term| {
+        let prompt_text = match default_file(state) {
+            Some(file) => format!("{} (default {}):", text, file).into(),
+            None => format!("{}:", text).into(),
+        };
+
+        state.prompt.set(PromptData {
+            prompt_text,
+            update_fn: Rc::new(move |state, term| patch_file_prompt_update(state, term, args)),
+        });
+        Ok(())
+    }))
+}
+
+fn patch_file_prompt_update(state: &mut State, term: &mut Term, args: &[&str]) -> Res<()> {
+    if state.prompt.state.status().is_done() {
+        let input = state.prompt.state.value().to_string();
+        let file = match (input.as_str(), default_file(state)) {
+            ("", Some(default)) => default,
+            (value, _) => value.to_string(),
+        };
+
+        let mut cmd = Command::new("git");
+        cmd.args(args);
+        cmd.arg(file);
+
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
+        state.prompt.reset(term)?;
+    }
+    Ok(())
+}
+
+fn default_file(state: &State) -> Option<String> {
+    match &state.screen().get_selected_item().target_data {
+        Some(TargetData::File(file)) => Some(file.to_string_lossy().to_string()),
+        _ => None,
+    }
+}
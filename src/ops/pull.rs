@@ -1,5 +1,5 @@
 use super::{Action, OpTrait};
-use crate::{items::TargetData, state::State, term::Term};
+use crate::{items::TargetData, screen::RefreshScope, state::State, term::Term};
 use derive_more::Display;
 use std::{process::Command, rc::Rc};
 
@@ -12,7 +12,7 @@ impl OpTrait for Pull {
             let mut cmd = Command::new("git");
             cmd.args(["pull"]);
 
-            state.run_external_cmd(term, &[], cmd)?;
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
             Ok(())
         }))
     }
@@ -0,0 +1,64 @@
+use super::{Action, OpTrait};
+use crate::{
+    forge::{self, Forge},
+    items::TargetData,
+    screen::{self, RefreshScope},
+    state::State,
+    term::Term,
+};
+use derive_more::Display;
+use std::{process::Command, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Show pull requests")]
+pub(crate) struct ShowPullRequests;
+impl OpTrait for ShowPullRequests {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let screen = screen::pull_requests::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?;
+
+            state.screens.drain(1..);
+            state.screens.push(screen);
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Checkout pull request")]
+pub(crate) struct CheckoutPullRequest;
+impl OpTrait for CheckoutPullRequest {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let Some(TargetData::PullRequest(pull_request)) = target else {
+            return None;
+        };
+        let number = pull_request.number;
+
+        Some(Rc::new(move |state: &mut State, term: &mut Term| {
+            let forge = Forge::of_repo(&state.repo)?;
+            let remote = forge::remote_name(&state.repo)?;
+            let branch = format!("pr-{}", number);
+
+            // Fetching the forge's own ref also works for pull requests from forks
+            let mut cmd = Command::new("git");
+            cmd.args([
+                "fetch".to_string(),
+                remote,
+                format!("+{}:{}", forge.pull_request_ref(number), branch),
+            ]);
+
+            state.run_external_cmd_then(term, &[], cmd, RefreshScope::All, move |state, term| {
+                let mut cmd = Command::new("git");
+                cmd.args(["checkout", &branch]);
+                state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+            })
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
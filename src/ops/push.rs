@@ -1,5 +1,7 @@
-use super::{Action, OpTrait};
-use crate::{items::TargetData, state::State, term::Term};
+use super::{offer_no_verify, Action, OpTrait};
+use crate::{
+    git, hooks::Hook, items::TargetData, screen::RefreshScope, state::State, term::Term, Res,
+};
 use derive_more::Display;
 use std::{process::Command, rc::Rc};
 
@@ -12,8 +14,32 @@ impl OpTrait for Push {
             let mut cmd = Command::new("git");
             cmd.args(["push"]);
 
-            state.run_external_cmd(term, &[], cmd)?;
-            Ok(())
+            push(state, term, cmd)
         }))
     }
 }
+
+fn push(state: &mut State, term: &mut Term, mut cmd: Command) -> Res<()> {
+    let retry = crate::copy_cmd(&cmd);
+    let trace = git::hook_failure::trace(&mut cmd, &state.repo);
+    let success_trace = trace.clone();
+
+    state.run_external_cmd_or(
+        term,
+        &[],
+        cmd,
+        RefreshScope::All,
+        move |state, _term| {
+            // Nothing failed, this only removes the trace
+            git::hook_failure::failed_hook(&success_trace);
+            state.run_hook(Hook::AfterPush);
+            Ok(())
+        },
+        move |state, _term, _output| {
+            if let Some(hook) = git::hook_failure::failed_hook(&trace) {
+                offer_no_verify(state, hook, retry, push);
+            }
+            Ok(())
+        },
+    )
+}
@@ -1,5 +1,5 @@
 use super::{subscreen_arg, Action, OpTrait};
-use crate::{items::TargetData, state::State, term::Term};
+use crate::{items::TargetData, screen::RefreshScope, state::State, term::Term};
 use derive_more::Display;
 use std::{
     ffi::{OsStr, OsString},
@@ -31,7 +31,7 @@ impl OpTrait for RebaseAbort {
             let mut cmd = Command::new("git");
             cmd.args(["rebase", "--abort"]);
 
-            state.run_external_cmd(term, &[], cmd)?;
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
             Ok(())
         }))
     }
@@ -43,7 +43,7 @@ pub(crate) struct RebaseInteractive;
 impl OpTrait for RebaseInteractive {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
         let action = match target {
-            Some(TargetData::Commit(r) | TargetData::Branch(r)) => {
+            Some(TargetData::Commit(r) | TargetData::Branch(r) | TargetData::Tag(r)) => {
                 subscreen_arg(rebase_interactive_cmd, r.into())
             }
             _ => return None,
@@ -80,7 +80,7 @@ pub(crate) struct RebaseAutosquash;
 impl OpTrait for RebaseAutosquash {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
         let action = match target {
-            Some(TargetData::Commit(r) | TargetData::Branch(r)) => {
+            Some(TargetData::Commit(r) | TargetData::Branch(r) | TargetData::Tag(r)) => {
                 subscreen_arg(rebase_autosquash_cmd, r.into())
             }
             _ => return None,
@@ -101,7 +101,7 @@ fn rebase_autosquash_cmd(reference: &OsStr) -> Command {
         OsStr::new("--autosquash"),
         OsStr::new("--keep-empty"),
         OsStr::new("--autostash"),
-        &reference,
+        reference,
     ]);
     cmd
 }
@@ -0,0 +1,23 @@
+use super::{Action, OpTrait};
+use crate::{items::TargetData, screen, state::State, term::Term};
+use derive_more::Display;
+use std::rc::Rc;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Recent repositories")]
+pub(crate) struct ShowRecentRepos;
+impl OpTrait for ShowRecentRepos {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let screen = screen::recent::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?;
+
+            state.screens.drain(1..);
+            state.screens.push(screen);
+            Ok(())
+        }))
+    }
+}
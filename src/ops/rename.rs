@@ -0,0 +1,68 @@
+use super::{Action, OpTrait};
+use crate::{
+    git, items::TargetData, prompt::PromptData, screen::RefreshScope, state::State, term::Term, Res,
+};
+use derive_more::Display;
+use std::{fs, path::PathBuf, rc::Rc};
+use tui_prompts::State as _;
+
+/// Tracked files are moved with `git mv`, which stages the rename. Untracked ones are
+/// just moved. Tab fills in the current path, to edit rather than type it all.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Rename file")]
+pub(crate) struct RenameFile;
+impl OpTrait for RenameFile {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let file = match target {
+            Some(TargetData::File(f)) => f.clone(),
+            Some(TargetData::Delta(d)) => d.new_file.clone(),
+            _ => return None,
+        };
+
+        Some(Rc::new(move |state: &mut State, _term: &mut Term| {
+            let from = file.clone();
+            state.prompt.set_with_completions(
+                PromptData {
+                    prompt_text: format!("Rename {} to:", git::display_path(&file)).into(),
+                    update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+                        if state.prompt.state.status().is_done() {
+                            let to = PathBuf::from(state.prompt.state.value().trim());
+                            state.prompt.reset(term)?;
+                            rename(state, term, from.clone(), to)?;
+                        }
+                        Ok(())
+                    }),
+                },
+                vec![git::display_path(&file).into_owned()],
+            );
+            Ok(())
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+fn rename(state: &mut State, term: &mut Term, from: PathBuf, to: PathBuf) -> Res<()> {
+    if to.as_os_str().is_empty() || to == from {
+        return Err("Give the file a new path to rename it".into());
+    }
+
+    let workdir = state.repo.workdir().ok_or("Renaming requires a worktree")?;
+    if workdir.join(&to).exists() {
+        return Err(format!("{} exists already", to.display()).into());
+    }
+    // Git refuses to move into a directory that isn't there
+    if let Some(parent) = workdir.join(&to).parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let scope = RefreshScope::Paths(vec![from.clone(), to.clone()]);
+    let tracked = state.repo.index()?.get_path(&from, 0).is_some();
+    if tracked {
+        state.run_external_cmd(term, &[], git::mv_cmd(&from, &to), scope)
+    } else {
+        fs::rename(workdir.join(&from), workdir.join(&to))?;
+        state.refresh_screen(&scope)
+    }
+}
@@ -1,60 +1,115 @@
-use super::{cmd_arg, OpTrait};
-use crate::{git, items::TargetData, Action};
+use super::{cmd_arg, commit_reference, snapshot::snapshot_first, OpTrait};
+use crate::{
+    git, items::TargetData, prompt::PromptData, screen::RefreshScope, state::State, term::Term,
+    Action, ErrorBuffer,
+};
 use derive_more::Display;
+use std::{ffi::OsStr, process::Command, rc::Rc};
+use tui_prompts::State as _;
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
-#[display(fmt = "Reset soft")]
+#[display(fmt = "Soft: keep index, worktree")]
 pub(crate) struct ResetSoft;
 impl OpTrait for ResetSoft {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
-        let action = match target {
-            Some(TargetData::Commit(r) | TargetData::Branch(r)) => {
-                cmd_arg(git::reset_soft_cmd, r.into())
-            }
-            _ => return None,
-        };
-
-        Some(action)
-    }
-    fn is_target_op(&self) -> bool {
-        true
+        Some(reset("soft", git::reset_soft_cmd, target))
     }
 }
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
-#[display(fmt = "Reset mixed")]
+#[display(fmt = "Mixed: keep worktree")]
 pub(crate) struct ResetMixed;
 impl OpTrait for ResetMixed {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
-        let action = match target {
-            Some(TargetData::Commit(r) | TargetData::Branch(r)) => {
-                cmd_arg(git::reset_mixed_cmd, r.into())
-            }
-            _ => return None,
-        };
-
-        Some(action)
-    }
-    fn is_target_op(&self) -> bool {
-        true
+        Some(reset("mixed", git::reset_mixed_cmd, target))
     }
 }
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
-#[display(fmt = "Reset hard")]
+#[display(fmt = "Hard: discard all changes")]
 pub(crate) struct ResetHard;
 impl OpTrait for ResetHard {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
-        let action = match target {
-            Some(TargetData::Commit(r) | TargetData::Branch(r)) => {
-                cmd_arg(git::reset_hard_cmd, r.into())
-            }
-            _ => return None,
-        };
+        Some(reset("hard", git::reset_hard_cmd, target))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Keep: keep local changes")]
+pub(crate) struct ResetKeep;
+impl OpTrait for ResetKeep {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        Some(reset("keep", git::reset_keep_cmd, target))
+    }
+}
 
-        Some(action)
+/// Reset to the selected commit, or to one asked for if there's none.
+fn reset(
+    mode: &'static str,
+    command: fn(&OsStr) -> Command,
+    target: Option<&TargetData>,
+) -> Action {
+    if let Some(reference) = commit_reference(target) {
+        return reset_to(mode, command, reference);
     }
-    fn is_target_op(&self) -> bool {
-        true
+
+    Rc::new(move |state: &mut State, _term: &mut Term| {
+        state.prompt.set(PromptData {
+            prompt_text: format!("Reset {} to:", mode).into(),
+            update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+                if state.prompt.state.status().is_done() {
+                    let reference = state.prompt.state.value().to_string();
+                    state.prompt.reset(term)?;
+
+                    if reference.is_empty() {
+                        return Err("No revision given".into());
+                    }
+
+                    Rc::get_mut(&mut reset_to(mode, command, reference)).unwrap()(state, term)?;
+                }
+                Ok(())
+            }),
+        });
+        Ok(())
+    })
+}
+
+fn reset_to(mode: &'static str, command: fn(&OsStr) -> Command, reference: String) -> Action {
+    let action = cmd_arg(command, reference.clone().into(), RefreshScope::All);
+    if mode != "hard" {
+        return action;
     }
+
+    // The only kind of reset that throws away uncommitted changes
+    confirm(
+        format!("Really hard reset to {}? (y or n)", reference),
+        snapshot_first(format!("before hard reset to {}", reference), action),
+    )
+}
+
+fn confirm(prompt_text: String, mut action: Action) -> Action {
+    let update_fn = Rc::new(move |state: &mut State, term: &mut Term| {
+        if state.prompt.state.status().is_pending() {
+            match state.prompt.state.value() {
+                "y" => {
+                    state.prompt.reset(term)?;
+                    Rc::get_mut(&mut action).unwrap()(state, term)?;
+                }
+                "" => (),
+                _ => {
+                    state.error_buffer = Some(ErrorBuffer("Reset aborted".to_string()));
+                    state.prompt.reset(term)?;
+                }
+            }
+        }
+        Ok(())
+    });
+
+    Rc::new(move |state: &mut State, _term: &mut Term| {
+        state.prompt.set(PromptData {
+            prompt_text: prompt_text.clone().into(),
+            update_fn: update_fn.clone(),
+        });
+        Ok(())
+    })
 }
@@ -0,0 +1,42 @@
+use super::{snapshot::snapshot_first, Action, OpTrait};
+use crate::{
+    git::{snapshot, trash},
+    items::TargetData,
+    screen::RefreshScope,
+    state::State,
+    term::Term,
+};
+use derive_more::Display;
+use std::rc::Rc;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Restore")]
+pub(crate) struct Restore;
+impl OpTrait for Restore {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        match target.cloned() {
+            Some(TargetData::Snapshot(id)) => Some(snapshot_first(
+                format!("before restoring {}", &id[..7]),
+                Rc::new(move |state: &mut State, term: &mut Term| {
+                    state.run_external_cmd(
+                        term,
+                        &[],
+                        snapshot::restore_cmd(id.as_ref()),
+                        RefreshScope::All,
+                    )
+                }),
+            )),
+            Some(TargetData::Trashed(trashed)) => {
+                Some(Rc::new(move |state: &mut State, _term: &mut Term| {
+                    let retention_days = state.config.general.trash.retention_days;
+                    trash::restore(&state.repo, &trashed, retention_days)?;
+                    state.refresh_screen(&RefreshScope::All)
+                }))
+            }
+            _ => None,
+        }
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
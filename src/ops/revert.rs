@@ -0,0 +1,65 @@
+use super::{commit_reference, Action, OpTrait};
+use crate::{git, items::TargetData, screen::RefreshScope, state::State, term::Term};
+use derive_more::Display;
+use std::{process::Command, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Revert commit")]
+pub(crate) struct Revert;
+impl OpTrait for Revert {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let commit = commit_reference(target)?;
+        revert(commit)
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Revert down to here")]
+pub(crate) struct RevertRange;
+impl OpTrait for RevertRange {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        // The commit itself and everything on top of it, newest first
+        let commit = commit_reference(target)?;
+        revert(format!("{}^..HEAD", commit))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+fn revert(revisions: String) -> Option<Action> {
+    Some(Rc::new(move |state: &mut State, term: &mut Term| {
+        let mut cmd = Command::new("git");
+        cmd.arg("revert");
+        cmd.args(&state.pending_args);
+        cmd.arg(&revisions);
+
+        // Opens an editor for each revert commit's message
+        state.issue_subscreen_command(term, cmd)?;
+        Ok(())
+    }))
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Revert continue")]
+pub(crate) struct RevertContinue;
+impl OpTrait for RevertContinue {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            state.issue_subscreen_command(term, git::revert_continue_cmd())?;
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Revert abort")]
+pub(crate) struct RevertAbort;
+impl OpTrait for RevertAbort {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(super::cmd(vec![], git::revert_abort_cmd, RefreshScope::All))
+    }
+}
@@ -0,0 +1,59 @@
+use super::{Action, OpTrait};
+use crate::{git, items::TargetData, screen::RefreshScope, term};
+use derive_more::Display;
+use std::{env, ffi::OsString, process::Command, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Suspend")]
+pub(crate) struct Suspend;
+impl OpTrait for Suspend {
+    #[cfg(unix)]
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state, term| {
+            // Stops gitu like C-z would have without raw mode, until the shell resumes it
+            let mut cmd = Command::new("kill");
+            cmd.args(["-TSTP", &std::process::id().to_string()]);
+            term::suspended(term, || Ok(cmd.status()?))?;
+
+            state.refresh_screen(&RefreshScope::All)
+        }))
+    }
+
+    /// There's no job control to hand back to, a shell will have to do.
+    #[cfg(not(unix))]
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        Shell.get_action(target)
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Open shell")]
+pub(crate) struct Shell;
+impl OpTrait for Shell {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state, term| {
+            let shell = env::var_os("SHELL").unwrap_or_else(default_shell);
+            let mut cmd = Command::new(&shell);
+            git::in_repo(&mut cmd, &state.repo);
+
+            // The exit status is that of whatever was typed last, nothing to report
+            term::suspended(term, || Ok(cmd.status()?)).map_err(|err| {
+                format!(
+                    "Couldn't open shell {} due to: {}",
+                    shell.to_string_lossy(),
+                    err
+                )
+            })?;
+
+            state.refresh_screen(&RefreshScope::All)
+        }))
+    }
+}
+
+fn default_shell() -> OsString {
+    if cfg!(windows) {
+        env::var_os("COMSPEC").unwrap_or_else(|| "cmd.exe".into())
+    } else {
+        "sh".into()
+    }
+}
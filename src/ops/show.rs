@@ -1,7 +1,14 @@
-use super::OpTrait;
-use crate::{items::TargetData, screen, Action};
+use super::{submodule, worktree, OpTrait};
+use crate::{
+    forge::PullRequest,
+    git::log::LogFilter,
+    items::TargetData,
+    screen::{self, RefreshScope},
+    term, Action, ErrorBuffer,
+};
 use derive_more::Display;
-use std::{path::Path, process::Command, rc::Rc};
+use git2::Repository;
+use std::{env, ffi::OsStr, path::Path, process::Command, rc::Rc};
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
 #[display(fmt = "Show")]
@@ -9,10 +16,21 @@ pub(crate) struct Show;
 impl OpTrait for Show {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
         match target {
-            Some(TargetData::Commit(r) | TargetData::Branch(r)) => goto_show_screen(r.clone()),
+            Some(TargetData::Commit(r) | TargetData::Branch(r) | TargetData::Tag(r)) => {
+                goto_show_screen(r.clone())
+            }
+            Some(TargetData::BlameLine(line)) => goto_show_screen(line.commit.to_string()),
+            Some(TargetData::Snapshot(id)) => goto_show_screen(id.clone()),
             Some(TargetData::File(u)) => editor(u.as_path(), None),
+            Some(TargetData::Trashed(trashed)) => editor(trashed.path.as_path(), None),
             Some(TargetData::Delta(d)) => editor(d.new_file.as_path(), None),
             Some(TargetData::Hunk(h)) => editor(h.new_file.as_path(), Some(h.first_diff_line())),
+            Some(TargetData::LogFilter(filter)) => goto_filtered_log_screen(filter.clone()),
+            Some(TargetData::PullRequest(pr)) => goto_pull_request_screen(pr.clone()),
+            Some(TargetData::Submodule(path)) => submodule::enter(path.clone()),
+            Some(TargetData::Worktree(path) | TargetData::Repository(path)) => {
+                worktree::switch(path.clone())
+            }
             None => None,
         }
     }
@@ -36,40 +54,151 @@ fn goto_show_screen(r: String) -> Option<Action> {
     }))
 }
 
-fn editor(file: &Path, line: Option<u32>) -> Option<Action> {
-    let file = file.to_str().unwrap().to_string();
+fn goto_filtered_log_screen(filter: LogFilter) -> Option<Action> {
+    Some(Rc::new(move |state, term| {
+        state.screens.push(screen::log::create(
+            Rc::clone(&state.config),
+            Rc::clone(&state.repo),
+            term.size()?,
+            None,
+            Some(filter.clone()),
+        )?);
+        Ok(())
+    }))
+}
 
+fn goto_pull_request_screen(pull_request: PullRequest) -> Option<Action> {
     Some(Rc::new(move |state, term| {
-        const EDITOR_VARS: [&str; 3] = ["GIT_EDITOR", "VISUAL", "EDITOR"];
-        let configured_editor = EDITOR_VARS
-            .into_iter()
-            .find_map(|var| std::env::var(var).ok());
+        state.screens.push(screen::pull_requests::create_details(
+            Rc::clone(&state.config),
+            Rc::clone(&state.repo),
+            term.size()?,
+            pull_request.clone(),
+        )?);
+        Ok(())
+    }))
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Open in editor")]
+pub(crate) struct OpenInEditor;
+impl OpTrait for OpenInEditor {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        match target {
+            Some(TargetData::File(u)) => editor(u.as_path(), None),
+            Some(TargetData::Delta(d)) => editor(d.new_file.as_path(), None),
+            Some(TargetData::Hunk(h)) => editor(h.new_file.as_path(), Some(h.first_diff_line())),
+            _ => None,
+        }
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
 
-        let Some(editor) = configured_editor else {
+fn editor(file: &Path, line: Option<u32>) -> Option<Action> {
+    let file = file.as_os_str().to_os_string();
+
+    Some(Rc::new(move |state, term| {
+        let Some(editor) = configured_editor(&state.repo) else {
             return Err(format!(
-                "No editor environment variable set ({})",
+                "No editor configured (GIT_EDITOR, core.editor, {})",
                 EDITOR_VARS.join(", ")
             )
             .into());
         };
 
-        let mut cmd = Command::new(editor.clone());
-        let args = match line {
-            Some(line) => match editor.as_str() {
-                "vi" | "vim" | "nvim" | "nano" => {
-                    vec![format!("+{}", line), file.to_string()]
-                }
-                _ => vec![format!("{}:{}", file, line)],
-            },
-            None => vec![file.to_string()],
+        let Some(workdir) = state.repo.workdir() else {
+            return Err("There's no worktree to open files in".into());
         };
 
-        cmd.args(args);
+        let mut cmd = editor_cmd(&editor, &file, line);
+        cmd.current_dir(workdir);
 
-        state
-            .issue_subscreen_command(term, cmd)
+        let status = term::suspended(term, || Ok(cmd.status()?))
             .map_err(|err| format!("Couldn't open editor {} due to: {}", editor, err))?;
 
-        state.screen_mut().update()
+        if !status.success() {
+            state.error_buffer = Some(ErrorBuffer(format!("{} exited with {}", editor, status)));
+        }
+
+        state.refresh_screen(&RefreshScope::All)
     }))
 }
+
+const EDITOR_VARS: [&str; 2] = ["VISUAL", "EDITOR"];
+
+/// The editor git itself would use.
+fn configured_editor(repo: &Repository) -> Option<String> {
+    env::var("GIT_EDITOR")
+        .ok()
+        .or_else(|| repo.config().ok()?.get_string("core.editor").ok())
+        .into_iter()
+        .chain(EDITOR_VARS.into_iter().filter_map(|var| env::var(var).ok()))
+        .find(|editor| !editor.trim().is_empty())
+}
+
+/// The editor is run through a shell like git does, as it may come with arguments of its own.
+fn editor_cmd(editor: &str, file: &OsStr, line: Option<u32>) -> Command {
+    let program = editor
+        .split_whitespace()
+        .next()
+        .map(|program| Path::new(program).file_name().unwrap_or_default())
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    // The file may not be valid UTF-8, so it's never formatted into a string
+    let file_at = |line: u32| {
+        let mut arg = file.to_os_string();
+        arg.push(format!(":{}", line));
+        arg
+    };
+
+    let args = match line {
+        Some(line) => match program {
+            "vi" | "vim" | "nvim" | "nano" | "emacs" | "emacsclient" | "kak" => {
+                vec![format!("+{}", line).into(), file.to_os_string()]
+            }
+            "code" | "codium" => vec!["--goto".into(), file_at(line)],
+            _ => vec![file_at(line)],
+        },
+        None => vec![file.to_os_string()],
+    };
+
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", editor]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &format!("{} \"$@\"", editor), editor]);
+        cmd
+    };
+
+    cmd.args(args);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::editor_cmd;
+
+    #[test]
+    fn editor_gets_line_in_its_own_syntax() {
+        let args = |editor: &str| {
+            editor_cmd(editor, "src/main.rs".as_ref(), Some(12))
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .skip(3)
+                .collect::<Vec<_>>()
+        };
+
+        if cfg!(windows) {
+            return;
+        }
+
+        assert_eq!(args("/usr/bin/nvim"), ["+12", "src/main.rs"]);
+        assert_eq!(args("code --wait"), ["--goto", "src/main.rs:12"]);
+        assert_eq!(args("subl"), ["src/main.rs:12"]);
+    }
+}
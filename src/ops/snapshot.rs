@@ -0,0 +1,62 @@
+use super::{Action, OpTrait};
+use crate::{git, items::TargetData, jobs::JobOutput, screen, state::State, term::Term};
+use derive_more::Display;
+use std::{path::PathBuf, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Show snapshots")]
+pub(crate) struct ShowSnapshots;
+impl OpTrait for ShowSnapshots {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let screen = screen::snapshots::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?;
+
+            state.screens.drain(1..);
+            state.screens.push(screen);
+            Ok(())
+        }))
+    }
+}
+
+/// Take a snapshot of the worktree before running `action`, which throws changes away.
+/// Turned off with `general.snapshots`.
+pub(crate) fn snapshot_first(message: String, mut action: Action) -> Action {
+    Rc::new(move |state: &mut State, term: &mut Term| {
+        snapshot(state, message.clone());
+        Rc::get_mut(&mut action).unwrap()(state, term)
+    })
+}
+
+/// Queue a snapshot of the worktree, for commands queued after it to only run once it's taken.
+/// Should it fail, that's shown, but doesn't hold up what comes next.
+pub(crate) fn snapshot(state: &mut State, message: String) {
+    snapshot_with_ignored(state, message, vec![]);
+}
+
+/// Like `snapshot`, with the `ignored` files and directories in it too.
+pub(crate) fn snapshot_with_ignored(state: &mut State, message: String, ignored: Vec<PathBuf>) {
+    if !state.config.general.snapshots {
+        return;
+    }
+
+    let dirs = git::RepoDirs::of(&state.repo);
+    state.spawn_job(
+        format!("Snapshot {}", message),
+        Box::new(move || {
+            dirs.open()
+                .map_err(Into::into)
+                .and_then(|repo| git::snapshot::create(&repo, &message, &ignored))
+                .map_err(|err| format!("Couldn't snapshot the worktree: {}", err))?;
+
+            Ok(JobOutput {
+                out: String::new(),
+                success: true,
+            })
+        }),
+        Box::new(|_state, _term, _output| Ok(())),
+    );
+}
@@ -1,5 +1,5 @@
 use super::{cmd, cmd_arg, OpTrait};
-use crate::{git, items::TargetData, Action};
+use crate::{git, items::TargetData, screen::RefreshScope, Action};
 use derive_more::Display;
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
@@ -8,9 +8,18 @@ pub(crate) struct Stage;
 impl OpTrait for Stage {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
         let action = match target.cloned() {
-            Some(TargetData::File(u)) => cmd_arg(git::stage_file_cmd, u.into()),
-            Some(TargetData::Delta(d)) => cmd_arg(git::stage_file_cmd, d.new_file.into()),
-            Some(TargetData::Hunk(h)) => cmd(h.format_patch().into_bytes(), git::stage_patch_cmd),
+            Some(TargetData::File(u)) => {
+                let scope = RefreshScope::Paths(vec![u.clone()]);
+                cmd_arg(git::stage_file_cmd, u.into(), scope)
+            }
+            Some(TargetData::Delta(d)) => {
+                let scope = RefreshScope::Paths(vec![d.old_file, d.new_file.clone()]);
+                cmd_arg(git::stage_file_cmd, d.new_file.into(), scope)
+            }
+            Some(TargetData::Hunk(h)) => {
+                let scope = RefreshScope::Paths(vec![h.new_file.clone()]);
+                cmd(h.patch_bytes(), git::stage_patch_cmd, scope)
+            }
             _ => return None,
         };
 
@@ -0,0 +1,111 @@
+use super::{Action, OpTrait};
+use crate::{
+    items::TargetData, screen, screen::RefreshScope, state::State, term, term::Term, ErrorBuffer,
+};
+use derive_more::Display;
+use std::{env, path::PathBuf, process::Command, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Show submodules")]
+pub(crate) struct ShowSubmodules;
+impl OpTrait for ShowSubmodules {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let screen = screen::submodules::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?;
+
+            state.screens.drain(1..);
+            state.screens.push(screen);
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Init submodules")]
+pub(crate) struct SubmoduleInit;
+impl OpTrait for SubmoduleInit {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        submodule_cmd(&["init"], target)
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Update submodules")]
+pub(crate) struct SubmoduleUpdate;
+impl OpTrait for SubmoduleUpdate {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        submodule_cmd(&["update", "--init", "--recursive"], target)
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Sync submodule urls")]
+pub(crate) struct SubmoduleSync;
+impl OpTrait for SubmoduleSync {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        submodule_cmd(&["sync", "--recursive"], target)
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Enter submodule")]
+pub(crate) struct SubmoduleEnter;
+impl OpTrait for SubmoduleEnter {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        match target {
+            Some(TargetData::Submodule(path)) => enter(path.clone()),
+            _ => None,
+        }
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+/// Runs `git submodule <args>` for the selected submodule, or all of them if there's none.
+fn submodule_cmd(args: &'static [&'static str], target: Option<&TargetData>) -> Option<Action> {
+    let path = match target {
+        Some(TargetData::Submodule(path)) => Some(path.clone()),
+        _ => None,
+    };
+
+    Some(Rc::new(move |state, term| {
+        let mut cmd = Command::new("git");
+        cmd.arg("submodule");
+        cmd.args(args);
+        if let Some(path) = &path {
+            cmd.arg("--");
+            cmd.arg(path);
+        }
+
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+    }))
+}
+
+/// Opens another gitu in the submodule, returning here once it quits.
+pub(crate) fn enter(path: PathBuf) -> Option<Action> {
+    Some(Rc::new(move |state, term| {
+        let dir = state.repo.workdir().expect("No workdir").join(&path);
+        if !dir.join(".git").exists() {
+            return Err(format!("Submodule {} isn't initialized", path.display()).into());
+        }
+
+        let mut cmd = Command::new(env::current_exe()?);
+        cmd.current_dir(&dir);
+
+        let status = term::suspended(term, || Ok(cmd.status()?))?;
+        if !status.success() {
+            state.error_buffer = Some(ErrorBuffer(format!(
+                "gitu in {} exited with {}",
+                path.display(),
+                status
+            )));
+        }
+
+        state.refresh_screen(&RefreshScope::All)
+    }))
+}
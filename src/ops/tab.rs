@@ -0,0 +1,64 @@
+use super::{Action, OpTrait};
+use crate::{items::TargetData, screen, state::State, term::Term};
+use derive_more::Display;
+use std::rc::Rc;
+
+/// Opens the status in a tab of its own, after the current one.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "New tab")]
+pub(crate) struct NewTab;
+impl OpTrait for NewTab {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let screen = screen::status::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?;
+
+            state.new_tab(screen);
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Next tab")]
+pub(crate) struct NextTab;
+impl OpTrait for NextTab {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            state.select_tab((state.tab() + 1) % state.tab_count())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Previous tab")]
+pub(crate) struct PreviousTab;
+impl OpTrait for PreviousTab {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            let count = state.tab_count();
+            state.select_tab((state.tab() + count - 1) % count)
+        }))
+    }
+}
+
+/// Tabs are numbered from 1, like they're shown. Bound to each digit, which are listed
+/// together as they're labeled the same.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Go to tab")]
+pub(crate) struct GoToTab(pub usize);
+impl OpTrait for GoToTab {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        let number = self.0;
+        Some(Rc::new(move |state: &mut State, _term: &mut Term| {
+            if number > state.tab_count() {
+                return Err(format!("There's no tab {}", number).into());
+            }
+
+            state.select_tab(number - 1)
+        }))
+    }
+}
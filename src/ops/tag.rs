@@ -0,0 +1,181 @@
+use super::{Action, OpTrait};
+use crate::{
+    forge, items::TargetData, prompt::PromptData, screen, screen::RefreshScope, state::State,
+    term::Term, Res,
+};
+use derive_more::Display;
+use std::{process::Command, rc::Rc};
+use tui_prompts::State as _;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Show tags")]
+pub(crate) struct ShowTags;
+impl OpTrait for ShowTags {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let screen = screen::tags::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?;
+
+            state.screens.drain(1..);
+            state.screens.push(screen);
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum TagKind {
+    Lightweight,
+    Annotated,
+    Signed,
+}
+
+/// Tags the selected commit, or HEAD if there's none.
+pub(crate) struct CreateTag(pub(crate) TagKind);
+
+impl std::fmt::Display for CreateTag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self.0 {
+            TagKind::Lightweight => "Create tag",
+            TagKind::Annotated => "Create annotated tag",
+            TagKind::Signed => "Create signed tag",
+        })
+    }
+}
+
+impl OpTrait for CreateTag {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        let kind = self.0;
+
+        Some(Rc::new(move |state: &mut State, _term: &mut Term| {
+            let prompt_text = match tagged_revision(state) {
+                Some(revision) => format!("Tag {} as:", revision).into(),
+                None => "Tag HEAD as:".into(),
+            };
+
+            state.prompt.set(PromptData {
+                prompt_text,
+                update_fn: Rc::new(move |state, term| create_prompt_update(state, term, kind)),
+            });
+            Ok(())
+        }))
+    }
+}
+
+fn create_prompt_update(state: &mut State, term: &mut Term, kind: TagKind) -> Res<()> {
+    if state.prompt.state.status().is_done() {
+        let name = state.prompt.state.value().to_string();
+        let revision = tagged_revision(state).unwrap_or("HEAD").to_string();
+        state.prompt.reset(term)?;
+
+        let mut cmd = Command::new("git");
+        cmd.arg("tag");
+        match kind {
+            TagKind::Lightweight => (),
+            TagKind::Annotated => {
+                cmd.arg("--annotate");
+            }
+            TagKind::Signed => {
+                cmd.arg("--sign");
+            }
+        }
+        cmd.args([&name, &revision]);
+
+        if kind == TagKind::Lightweight {
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
+        } else {
+            // Opens an editor for the message
+            state.issue_subscreen_command(term, cmd)?;
+        }
+    }
+    Ok(())
+}
+
+fn tagged_revision(state: &State) -> Option<&str> {
+    match &state.screen().get_selected_item().target_data {
+        Some(TargetData::Commit(r) | TargetData::Branch(r) | TargetData::Tag(r)) => Some(r),
+        _ => None,
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Delete tag")]
+pub(crate) struct DeleteTag;
+impl OpTrait for DeleteTag {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        tag_cmd(target, |_state, tag| {
+            let mut cmd = Command::new("git");
+            cmd.args(["tag", "--delete", tag]);
+            Ok(cmd)
+        })
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Push tag")]
+pub(crate) struct PushTag;
+impl OpTrait for PushTag {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        tag_cmd(target, |state, tag| {
+            let mut cmd = Command::new("git");
+            cmd.args(["push", &forge::remote_name(&state.repo)?]);
+            cmd.arg(format!("refs/tags/{}", tag));
+            Ok(cmd)
+        })
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Delete tag on remote")]
+pub(crate) struct DeleteRemoteTag;
+impl OpTrait for DeleteRemoteTag {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        tag_cmd(target, |state, tag| {
+            let mut cmd = Command::new("git");
+            cmd.args(["push", "--delete", &forge::remote_name(&state.repo)?]);
+            cmd.arg(format!("refs/tags/{}", tag));
+            Ok(cmd)
+        })
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Push all tags")]
+pub(crate) struct PushTags;
+impl OpTrait for PushTags {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let mut cmd = Command::new("git");
+            cmd.args(["push", "--tags", &forge::remote_name(&state.repo)?]);
+
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+        }))
+    }
+}
+
+fn tag_cmd(
+    target: Option<&TargetData>,
+    command: fn(&State, &str) -> Res<Command>,
+) -> Option<Action> {
+    let Some(TargetData::Tag(tag)) = target else {
+        return None;
+    };
+    let tag = tag.clone();
+
+    Some(Rc::new(move |state: &mut State, term: &mut Term| {
+        let cmd = command(state, &tag)?;
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+    }))
+}
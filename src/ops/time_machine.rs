@@ -0,0 +1,106 @@
+use super::{Action, OpTrait};
+use crate::{items::TargetData, screen, session::View, state::State, term::Term, Res};
+use derive_more::Display;
+use std::{path::Path, rc::Rc};
+
+/// Starts at the last commit that changed the file, or at the one that changed the line.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Time machine")]
+pub(crate) struct TimeMachine;
+impl OpTrait for TimeMachine {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let (path, commit) = match target {
+            Some(TargetData::File(f)) => (f.clone(), None),
+            Some(TargetData::Delta(d)) => (d.new_file.clone(), None),
+            Some(TargetData::Hunk(h)) => (h.new_file.clone(), None),
+            Some(TargetData::BlameLine(line)) => (line.path.clone(), Some(line.commit.to_string())),
+            _ => return None,
+        };
+
+        Some(Rc::new(move |state: &mut State, term: &mut Term| {
+            let commit = match &commit {
+                Some(commit) => commit.clone(),
+                None => last_change(state, &path)?,
+            };
+
+            state.screens.push(screen::time_machine::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+                path.clone(),
+                commit,
+            )?);
+            Ok(())
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Older revision")]
+pub(crate) struct OlderRevision;
+impl OpTrait for OlderRevision {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            step(state, term, 1)
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Newer revision")]
+pub(crate) struct NewerRevision;
+impl OpTrait for NewerRevision {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            step(state, term, -1)
+        }))
+    }
+}
+
+fn last_change(state: &State, path: &Path) -> Res<String> {
+    let head = state.repo.head()?.peel_to_commit()?.id();
+    let history = crate::git::log::file_history(&state.repo, head, path)?;
+
+    Ok(history
+        .first()
+        .ok_or_else(|| format!("{} hasn't been committed yet", path.display()))?
+        .to_string())
+}
+
+/// Replaces the time machine on top with one `by` revisions further back, forward if negative.
+fn step(state: &mut State, term: &mut Term, by: isize) -> Res<()> {
+    let Some(View::TimeMachine { path, commit }) = state.screen().view().cloned() else {
+        return Err("Step through revisions in the time machine".into());
+    };
+
+    let commit = state.repo.revparse_single(&commit)?.peel_to_commit()?.id();
+    let history = screen::time_machine::history(&state.repo, &path, commit)?;
+    let revision = history
+        .iter()
+        .position(|&id| id == commit)
+        .map(|revision| revision as isize + by);
+
+    let Some(&to) = revision.and_then(|revision| history.get(usize::try_from(revision).ok()?))
+    else {
+        return Err(if by > 0 {
+            "There's no older revision"
+        } else {
+            "There's no newer revision"
+        }
+        .into());
+    };
+
+    let screen = screen::time_machine::create(
+        Rc::clone(&state.config),
+        Rc::clone(&state.repo),
+        term.size()?,
+        path,
+        to.to_string(),
+    )?;
+    state.screens.pop();
+    state.screens.push(screen);
+    Ok(())
+}
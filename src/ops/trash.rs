@@ -0,0 +1,37 @@
+use super::{Action, OpTrait};
+use crate::{git, items::TargetData, screen, state::State, term::Term};
+use derive_more::Display;
+use std::{path::PathBuf, rc::Rc};
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Show trash")]
+pub(crate) struct ShowTrash;
+impl OpTrait for ShowTrash {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let screen = screen::trash::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?;
+
+            state.screens.drain(1..);
+            state.screens.push(screen);
+            Ok(())
+        }))
+    }
+}
+
+/// Copy `file` into the trash before running `action`, which throws away its changes.
+/// Turned off with `general.trash.enabled`.
+pub(crate) fn trash_first(file: PathBuf, mut action: Action) -> Action {
+    Rc::new(move |state: &mut State, term: &mut Term| {
+        let trash = &state.config.general.trash;
+        if trash.enabled {
+            git::trash::copy_to_trash(&state.repo, &file, trash.retention_days)
+                .map_err(|err| format!("Couldn't copy {} to the trash: {}", file.display(), err))?;
+        }
+
+        Rc::get_mut(&mut action).unwrap()(state, term)
+    })
+}
@@ -0,0 +1,73 @@
+use super::{Action, OpTrait};
+use crate::{
+    git::{self, undo},
+    items::TargetData,
+    prompt::PromptData,
+    screen::{self, RefreshScope},
+    state::State,
+    term::Term,
+    ErrorBuffer,
+};
+use derive_more::Display;
+use std::rc::Rc;
+use tui_prompts::State as _;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Undo")]
+pub(crate) struct Undo;
+impl OpTrait for Undo {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let Some(undo) = undo::last_operation(&state.repo)? else {
+                return Err("Nothing to undo".into());
+            };
+
+            // What would change is previewed, until answering the prompt
+            let from = git::short_hash(&undo.from.to_string());
+            let to = git::short_hash(&undo.to.to_string());
+            state.screens.push(screen::diff::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+                format!("{}..{}", from, to),
+            )?);
+
+            let target = undo.to.to_string();
+            state.prompt.set(PromptData {
+                prompt_text: format!(
+                    "Undo '{}', moving {} back to {}? (y or n)",
+                    undo.message, undo.reference, to
+                )
+                .into(),
+                update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+                    if !state.prompt.state.status().is_pending() {
+                        return Ok(());
+                    }
+
+                    match state.prompt.state.value() {
+                        "" => return Ok(()),
+                        "y" => {
+                            state.screens.pop();
+                            state.prompt.reset(term)?;
+                            state.run_external_cmd(
+                                term,
+                                &[],
+                                undo::undo_cmd(target.as_ref()),
+                                RefreshScope::All,
+                            )?;
+                        }
+                        _ => {
+                            state.screens.pop();
+                            state.prompt.reset(term)?;
+                            state.error_buffer = Some(ErrorBuffer("Undo aborted".to_string()));
+                        }
+                    }
+
+                    Ok(())
+                }),
+            });
+
+            Ok(())
+        }))
+    }
+}
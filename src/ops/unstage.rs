@@ -1,6 +1,7 @@
-use super::{cmd, cmd_arg, OpTrait};
-use crate::{git, items::TargetData, Action};
+use super::{cmd, OpTrait};
+use crate::{git, items::TargetData, screen::RefreshScope, state::State, term::Term, Action};
 use derive_more::Display;
+use std::rc::Rc;
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
 #[display(fmt = "Unstage")]
@@ -8,8 +9,26 @@ pub(crate) struct Unstage;
 impl OpTrait for Unstage {
     fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
         let action = match target.cloned() {
-            Some(TargetData::Delta(d)) => cmd_arg(git::unstage_file_cmd, d.new_file.into()),
-            Some(TargetData::Hunk(h)) => cmd(h.format_patch().into_bytes(), git::unstage_patch_cmd),
+            Some(TargetData::Delta(d)) => {
+                let scope = RefreshScope::Paths(vec![d.old_file.clone(), d.new_file.clone()]);
+                let renamed_from = (d.old_file != d.new_file).then_some(d.old_file);
+                let file = d.new_file.into_os_string();
+                Rc::new(move |state: &mut State, term: &mut Term| {
+                    let command = if git::unborn_branch(&state.repo).is_some() {
+                        git::unstage_new_file_cmd(&file)
+                    } else {
+                        let mut command = git::unstage_file_cmd(&file);
+                        // Or it'd still be deleted in the index
+                        command.args(&renamed_from);
+                        command
+                    };
+                    state.run_external_cmd(term, &[], command, scope.clone())
+                })
+            }
+            Some(TargetData::Hunk(h)) => {
+                let scope = RefreshScope::Paths(vec![h.new_file.clone()]);
+                cmd(h.patch_bytes(), git::unstage_patch_cmd, scope)
+            }
             _ => return None,
         };
 
@@ -0,0 +1,150 @@
+use super::{Action, OpTrait};
+use crate::{
+    git, items::TargetData, prompt::PromptData, screen, screen::RefreshScope, state::State,
+    term::Term, Res,
+};
+use derive_more::Display;
+use git2::Repository;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    rc::Rc,
+};
+use tui_prompts::State as _;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Show worktrees")]
+pub(crate) struct ShowWorktrees;
+impl OpTrait for ShowWorktrees {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, term: &mut Term| {
+            let screen = screen::worktrees::create(
+                Rc::clone(&state.config),
+                Rc::clone(&state.repo),
+                term.size()?,
+            )?;
+
+            state.screens.drain(1..);
+            state.screens.push(screen);
+            Ok(())
+        }))
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Add worktree")]
+pub(crate) struct WorktreeAdd;
+impl OpTrait for WorktreeAdd {
+    fn get_action(&self, _target: Option<&TargetData>) -> Option<Action> {
+        Some(Rc::new(|state: &mut State, _term: &mut Term| {
+            let prompt_text = if let Some(branch) = default_branch(state) {
+                format!("Add worktree for branch (default {}):", branch).into()
+            } else {
+                "Add worktree for branch:".into()
+            };
+
+            state.prompt.set(PromptData {
+                prompt_text,
+                update_fn: Rc::new(add_prompt_update),
+            });
+            Ok(())
+        }))
+    }
+}
+
+fn add_prompt_update(state: &mut State, term: &mut Term) -> Res<()> {
+    if state.prompt.state.status().is_done() {
+        let input = state.prompt.state.value().to_string();
+        let branch = match (input.as_str(), default_branch(state)) {
+            ("", Some(default)) => default.to_string(),
+            (value, _) => value.to_string(),
+        };
+
+        // Next to the current worktree (or bare repository), named after the branch
+        let path = sibling_path(git::command_dir(&state.repo), &branch);
+
+        let mut cmd = Command::new("git");
+        cmd.args(["worktree", "add"]);
+        cmd.arg(path);
+        cmd.arg(&branch);
+
+        state.run_external_cmd(term, &[], cmd, RefreshScope::All)?;
+        state.prompt.reset(term)?;
+    }
+    Ok(())
+}
+
+fn default_branch(state: &State) -> Option<&str> {
+    match &state.screen().get_selected_item().target_data {
+        Some(TargetData::Branch(branch)) => Some(branch),
+        _ => None,
+    }
+}
+
+fn sibling_path(workdir: &Path, branch: &str) -> PathBuf {
+    let workdir = workdir.components().as_path();
+    let name = workdir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    workdir.with_file_name(format!("{}-{}", name, branch.replace('/', "-")))
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Remove worktree")]
+pub(crate) struct WorktreeRemove;
+impl OpTrait for WorktreeRemove {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let Some(TargetData::Worktree(path)) = target else {
+            return None;
+        };
+        let path = path.clone();
+
+        Some(Rc::new(move |state: &mut State, term: &mut Term| {
+            let mut cmd = Command::new("git");
+            cmd.args(["worktree", "remove"]);
+            cmd.arg(&path);
+
+            state.run_external_cmd(term, &[], cmd, RefreshScope::All)
+        }))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Switch to worktree")]
+pub(crate) struct WorktreeSwitch;
+impl OpTrait for WorktreeSwitch {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        match target {
+            Some(TargetData::Worktree(path)) => switch(path.clone()),
+            _ => None,
+        }
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+pub(crate) fn switch(path: PathBuf) -> Option<Action> {
+    Some(Rc::new(move |state: &mut State, term: &mut Term| {
+        state.switch_repo(Repository::open(&path)?, term.size()?)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sibling_path;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn sibling_path_is_named_after_branch() {
+        assert_eq!(
+            sibling_path(Path::new("/src/gitu/"), "fix/thing"),
+            PathBuf::from("/src/gitu-fix-thing")
+        );
+    }
+}
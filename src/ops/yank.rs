@@ -0,0 +1,82 @@
+use super::{browse, commit_reference, Action, OpTrait};
+use crate::{items::TargetData, state::State, term, term::Term, CmdMetaBuffer, Res};
+use derive_more::Display;
+use git2::Repository;
+use std::rc::Rc;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Copy hash")]
+pub(crate) struct YankHash;
+impl OpTrait for YankHash {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let r = commit_reference(target)?;
+        yank(move |repo| Ok(repo.revparse_single(&r)?.peel_to_commit()?.id().to_string()))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Copy short hash")]
+pub(crate) struct YankShortHash;
+impl OpTrait for YankShortHash {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let r = commit_reference(target)?;
+        yank(move |repo| {
+            let commit = repo.revparse_single(&r)?.peel_to_commit()?;
+            let short_id = commit.as_object().short_id()?;
+            Ok(short_id.as_str().unwrap().to_string())
+        })
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Copy path")]
+pub(crate) struct YankPath;
+impl OpTrait for YankPath {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let path = match target {
+            Some(TargetData::File(f)) => f.clone(),
+            Some(TargetData::Delta(d)) => d.new_file.clone(),
+            Some(TargetData::Hunk(h)) => h.new_file.clone(),
+            Some(TargetData::BlameLine(line)) => line.path.clone(),
+            _ => return None,
+        };
+
+        yank(move |_repo| Ok(path.to_string_lossy().to_string()))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display(fmt = "Copy url")]
+pub(crate) struct YankUrl;
+impl OpTrait for YankUrl {
+    fn get_action(&self, target: Option<&TargetData>) -> Option<Action> {
+        let target = target.cloned();
+        yank(move |repo| browse::url(repo, target.as_ref()))
+    }
+    fn is_target_op(&self) -> bool {
+        true
+    }
+}
+
+fn yank<F: Fn(&Repository) -> Res<String> + 'static>(text: F) -> Option<Action> {
+    Some(Rc::new(move |state: &mut State, term: &mut Term| {
+        let text = text(&state.repo)?;
+        term::copy_to_clipboard(term, &text)?;
+
+        state.cmd_meta_buffer = Some(CmdMetaBuffer {
+            args: "Copied to clipboard".into(),
+            out: Some(text),
+        });
+
+        Ok(())
+    }))
+}
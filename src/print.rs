@@ -0,0 +1,81 @@
+use crate::{config::Config, screen::Screen, Res};
+use crossterm::style::{Attribute, ContentStyle, StyledContent};
+use ratatui::{
+    style::{Modifier, Style},
+    text::Line,
+};
+use std::io::{self, Write};
+
+const ATTRIBUTES: [(Modifier, Attribute); 6] = [
+    (Modifier::BOLD, Attribute::Bold),
+    (Modifier::DIM, Attribute::Dim),
+    (Modifier::ITALIC, Attribute::Italic),
+    (Modifier::UNDERLINED, Attribute::Underlined),
+    (Modifier::REVERSED, Attribute::Reverse),
+    (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+];
+
+/// Writes what `screen` shows to `out` once, with the same colors, but without any selection.
+/// Collapsed sections only show their header, as they would when opening gitu.
+pub(crate) fn print(out: &mut impl Write, config: &Config, screen: &Screen) -> Res<()> {
+    let result = screen
+        .visible_lines(&config.style)
+        .try_for_each(|line| writeln!(out, "{}", ansi_line(&line)))
+        .and_then(|()| out.flush());
+
+    match result {
+        // Whatever read the output has seen enough, like `head` would
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+fn ansi_line(line: &Line) -> String {
+    line.spans
+        .iter()
+        .map(|span| {
+            let style = line.style.patch(span.style);
+            StyledContent::new(content_style(style), span.content.as_ref()).to_string()
+        })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+fn content_style(style: Style) -> ContentStyle {
+    let mut content_style = ContentStyle::new();
+    content_style.foreground_color = style.fg.map(Into::into);
+    content_style.background_color = style.bg.map(Into::into);
+
+    for (modifier, attribute) in ATTRIBUTES {
+        if style.add_modifier.contains(modifier) {
+            content_style.attributes.set(attribute);
+        }
+    }
+
+    content_style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ansi_line;
+    use ratatui::{
+        style::{Color, Style, Stylize},
+        text::{Line, Span},
+    };
+
+    #[test]
+    fn lines_are_styled_with_ansi_codes() {
+        let line = Line::from(vec![
+            Span::styled("main", Style::new().fg(Color::Green)),
+            Span::raw(" "),
+            Span::raw("message").bold(),
+            Span::raw("   "),
+        ]);
+
+        assert_eq!(
+            ansi_line(&line),
+            "\u{1b}[38;5;2mmain\u{1b}[39m \u{1b}[1mmessage\u{1b}[0m"
+        );
+    }
+}
@@ -12,6 +12,8 @@ pub(crate) struct PromptData {
 pub(crate) struct Prompt {
     pub(crate) data: Option<PromptData>,
     pub(crate) state: TextState<'static>,
+    /// What tab completes to, the first match being the likeliest
+    completions: Vec<String>,
 }
 
 impl Prompt {
@@ -19,6 +21,7 @@ impl Prompt {
         Prompt {
             data: None,
             state: TextState::new(),
+            completions: vec![],
         }
     }
 
@@ -27,9 +30,41 @@ impl Prompt {
         self.state.focus();
     }
 
+    pub(crate) fn set_with_completions(&mut self, data: PromptData, completions: Vec<String>) {
+        self.set(data);
+        self.completions = completions;
+    }
+
+    /// Complete what's typed so far as far as the completions starting with it agree,
+    /// to all of the first one when nothing's typed.
+    pub(crate) fn complete(&mut self) {
+        let typed = self.state.value();
+        let mut matches = self.completions.iter().filter(|c| c.starts_with(typed));
+        let Some(first) = matches.next() else {
+            return;
+        };
+
+        let completed = if typed.is_empty() {
+            first.clone()
+        } else {
+            matches.fold(first.clone(), |common, other| {
+                common
+                    .chars()
+                    .zip(other.chars())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect()
+            })
+        };
+
+        *self.state.position_mut() = completed.len();
+        *self.state.value_mut() = completed;
+    }
+
     pub(crate) fn reset<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Res<()> {
         self.data = None;
         self.state = TextState::new();
+        self.completions = vec![];
         terminal.hide_cursor()?;
         Ok(())
     }
@@ -0,0 +1,71 @@
+use git2::Repository;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How many repositories are remembered, the oldest get forgotten first.
+const MAX_RECENT: usize = 20;
+
+/// Repositories opened before, most recent first.
+/// Stored as one path per line, so that it's easy to edit or clear by hand.
+pub(crate) fn list(file: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(file)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Move the repository to the top of the list.
+pub(crate) fn record(file: &Path, repo: &Repository) -> io::Result<()> {
+    let dir = repo.workdir().unwrap_or(repo.path()).canonicalize()?;
+
+    let mut repos = list(file);
+    repos.retain(|path| path != &dir);
+    repos.insert(0, dir);
+    repos.truncate(MAX_RECENT);
+
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents = repos
+        .iter()
+        .map(|path| format!("{}\n", path.display()))
+        .collect::<String>();
+
+    fs::write(file, contents)
+}
+
+/// The most recently opened repository that can still be opened.
+pub(crate) fn open_latest(file: &Path) -> Option<Repository> {
+    list(file)
+        .into_iter()
+        .find_map(|path| Repository::open(path).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{list, record};
+    use crate::git::test_repo::TestRepo;
+
+    #[test]
+    fn reopened_repo_moves_to_the_top() {
+        let test = TestRepo::at("first");
+        let file = test.dir.child("data").join("recent_repos");
+        let (first, second) = (&test.repo, test.init_other("second"));
+
+        record(&file, first).unwrap();
+        record(&file, &second).unwrap();
+        record(&file, first).unwrap();
+
+        let names = list(&file)
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["first", "second"]);
+    }
+}
@@ -0,0 +1,198 @@
+use super::Screen;
+use crate::{
+    config::Config,
+    git::blame::{self, BlameLine},
+    items::{Item, LazyItems, TargetData},
+    Res,
+};
+use git2::{Oid, Repository};
+use ratatui::{
+    prelude::Rect,
+    style::Style,
+    text::{Line, Span},
+};
+use std::{
+    path::PathBuf,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Lines are blamed this many at a time, further ones once they're expanded.
+const CHUNK_LINES: usize = 500;
+
+const AUTHOR_WIDTH: usize = 16;
+
+pub(crate) fn create(
+    config: Rc<Config>,
+    repo: Rc<Repository>,
+    size: Rect,
+    path: PathBuf,
+    reference: Option<String>,
+) -> Res<Screen> {
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let newest = repo
+                .revparse_single(reference.as_deref().unwrap_or("HEAD"))?
+                .peel_to_commit()?
+                .id();
+            let lines = Rc::new(blame::file_lines(&repo, newest, &path)?);
+
+            let header = Item {
+                id: "blame".into(),
+                display: Line::styled(
+                    match &reference {
+                        Some(reference) => format!("Blame {} at {}", path.display(), reference),
+                        None => format!("Blame {}", path.display()),
+                    },
+                    &config.style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            };
+
+            let chunk = Chunk {
+                config: Rc::clone(&config),
+                repo: Rc::clone(&repo),
+                newest,
+                path: path.clone(),
+                lines,
+                now: now(),
+            };
+
+            Ok([header].into_iter().chain(chunk.items(0)?).collect())
+        }),
+    )
+}
+
+#[derive(Clone)]
+struct Chunk {
+    config: Rc<Config>,
+    repo: Rc<Repository>,
+    newest: Oid,
+    path: PathBuf,
+    lines: Rc<Vec<String>>,
+    now: i64,
+}
+
+impl Chunk {
+    /// Lines from `start` on, the ones after this chunk in a collapsed section.
+    fn items(&self, start: usize) -> Res<Vec<Item>> {
+        let end = (start + CHUNK_LINES).min(self.lines.len());
+        let blamed = blame::blame(&self.repo, self.newest, &self.path, &self.lines, start..end)?;
+
+        let mut items = blamed
+            .into_iter()
+            .map(|line| self.line_item(line))
+            .collect::<Vec<_>>();
+
+        if end < self.lines.len() {
+            let rest = self.clone();
+            items.push(Item {
+                id: format!("blame_lines_{}", end).into(),
+                display: Line::styled(
+                    format!("Lines {}-{}", end + 1, self.lines.len()),
+                    &self.config.style.section_header,
+                ),
+                section: true,
+                default_collapsed: true,
+                depth: 0,
+                lazy_children: Some(LazyItems(Rc::new(move || {
+                    rest.items(end).unwrap_or_else(|err| {
+                        vec![Item {
+                            id: err.to_string().into(),
+                            display: err.to_string().into(),
+                            ..Default::default()
+                        }]
+                    })
+                }))),
+                ..Default::default()
+            });
+        }
+
+        Ok(items)
+    }
+
+    fn line_item(&self, line: BlameLine) -> Item {
+        let style = &self.config.style;
+        let heat = heat_style(&self.config, self.now - line.time);
+
+        let details = if line.first_in_hunk {
+            let author = line.author.chars().take(AUTHOR_WIDTH).collect::<String>();
+            vec![
+                Span::styled(line.commit.to_string()[..7].to_string(), heat),
+                Span::raw(" "),
+                Span::raw(format!("{:<width$}", author, width = AUTHOR_WIDTH)),
+                Span::raw(" "),
+                Span::styled(format!("{:<9}", format_age(self.now - line.time)), heat),
+            ]
+        } else {
+            vec![Span::raw(" ".repeat(7 + 1 + AUTHOR_WIDTH + 1 + 9))]
+        };
+
+        Item {
+            id: format!("blame_{}", line.line_number).into(),
+            display: Line::from(
+                details
+                    .into_iter()
+                    .chain([
+                        Span::styled(format!(" {:>4} ", line.line_number), &style.hunk_header),
+                        Span::raw(line.content.clone()),
+                    ])
+                    .collect::<Vec<_>>(),
+            ),
+            depth: 1,
+            target_data: Some(TargetData::BlameLine(line)),
+            ..Default::default()
+        }
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+const DAY: i64 = 24 * 60 * 60;
+const MONTH: i64 = 30 * DAY;
+const YEAR: i64 = 365 * DAY;
+
+fn heat_style(config: &Config, age: i64) -> Style {
+    let heat = &config.style.blame;
+    if age < MONTH {
+        (&heat.month).into()
+    } else if age < YEAR {
+        (&heat.year).into()
+    } else {
+        (&heat.older).into()
+    }
+}
+
+fn format_age(age: i64) -> String {
+    let (count, unit) = match age.max(0) {
+        age if age < 60 * 60 => (age / 60, "minute"),
+        age if age < DAY => (age / (60 * 60), "hour"),
+        age if age < MONTH => (age / DAY, "day"),
+        age if age < YEAR => (age / MONTH, "month"),
+        age => (age / YEAR, "year"),
+    };
+
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_age, DAY, YEAR};
+
+    #[test]
+    fn ages_use_the_largest_fitting_unit() {
+        assert_eq!(format_age(30), "0 minutes");
+        assert_eq!(format_age(2 * 60 * 60), "2 hours");
+        assert_eq!(format_age(DAY), "1 day");
+        assert_eq!(format_age(45 * DAY), "1 month");
+        assert_eq!(format_age(3 * YEAR + DAY), "3 years");
+    }
+}
@@ -0,0 +1,80 @@
+use super::Screen;
+use crate::{
+    config::Config,
+    git,
+    items::{Item, TargetData},
+    Res,
+};
+use git2::{Repository, StatusOptions};
+use ratatui::{prelude::Rect, text::Line};
+use std::{iter, path::PathBuf, rc::Rc};
+
+/// What `git clean -d` would remove, and with `ignored` what `-x` would as well.
+/// Untracked directories are shown as a whole, as they're cleaned.
+pub(crate) fn create(
+    config: Rc<Config>,
+    repo: Rc<Repository>,
+    size: Rect,
+    ignored: bool,
+) -> Res<Screen> {
+    Ok(Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let mut opts = StatusOptions::new();
+            opts.include_untracked(true)
+                .recurse_untracked_dirs(false)
+                .include_ignored(ignored)
+                .recurse_ignored_dirs(false);
+
+            let statuses = repo.statuses(Some(&mut opts))?;
+            let paths = |ignored: bool| {
+                statuses
+                    .iter()
+                    .filter(|entry| {
+                        if ignored {
+                            entry.status().is_ignored()
+                        } else {
+                            entry.status().is_wt_new()
+                        }
+                    })
+                    .map(|entry| git::path_from_bytes(entry.path_bytes()))
+                    .collect::<Vec<_>>()
+            };
+
+            let mut items = section(&config, "Untracked files", paths(false));
+            if ignored {
+                items.extend(section(&config, "Ignored files", paths(true)));
+            }
+
+            Ok(items)
+        }),
+    )?
+    .with_marking())
+}
+
+fn section(config: &Config, title: &str, paths: Vec<PathBuf>) -> Vec<Item> {
+    let style = &config.style;
+
+    iter::once(Item {
+        id: title.to_string().into(),
+        display: Line::styled(
+            format!("{} ({})", title, paths.len()),
+            &style.section_header,
+        ),
+        section: true,
+        depth: 0,
+        ..Default::default()
+    })
+    .chain(paths.into_iter().map(|path| {
+        let path_str = git::display_path(&path).into_owned();
+        Item {
+            id: format!("clean_{}", path_str).into(),
+            display: Line::styled(path_str, &style.file_header),
+            depth: 1,
+            target_data: Some(TargetData::File(path)),
+            ..Default::default()
+        }
+    }))
+    .collect()
+}
@@ -0,0 +1,118 @@
+use super::Screen;
+use crate::{
+    config::Config,
+    git::{self, log::LogRange},
+    items::{self, Item},
+    Res,
+};
+use git2::Repository;
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+use std::rc::Rc;
+
+/// Commits listed of those ahead and of those behind, there may be a whole history of them.
+const MAX_COMMITS: usize = 200;
+
+/// What differs between `reference` and the worktree, or HEAD when not `worktree`,
+/// followed by the commits one has that the other hasn't.
+pub(crate) fn create(
+    config: Rc<Config>,
+    repo: Rc<Repository>,
+    size: Rect,
+    reference: String,
+    worktree: bool,
+) -> Res<Screen> {
+    let cache = Rc::new(items::DiffCache::default());
+
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            cache.start_refresh();
+            let style = &config.style;
+            let (side, diff) = if worktree {
+                ("Worktree", git::diff_range(&repo, &reference)?)
+            } else {
+                (
+                    "HEAD",
+                    git::diff_range(&repo, &format!("{}..HEAD", reference))?,
+                )
+            };
+
+            let head = repo.head()?.peel_to_commit()?.id();
+            let other = repo.revparse_single(&reference)?.peel_to_commit()?.id();
+            let ahead = git::log::range(&repo, head, other, MAX_COMMITS)?;
+            let behind = git::log::range(&repo, other, head, MAX_COMMITS)?;
+
+            let mut items = vec![Item {
+                id: "compare".into(),
+                display: Line::from(vec![
+                    Span::styled(
+                        format!("{} compared with {}", side, reference),
+                        &style.section_header,
+                    ),
+                    format!(" ({})", diff.deltas.len()).into(),
+                ]),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            }];
+            items.extend(items::create_diff_items(
+                Rc::clone(&config),
+                &cache,
+                &diff,
+                &1,
+                false,
+            ));
+            items.extend(commits_section(
+                &config,
+                format!("Commits ahead of {}", reference),
+                &ahead,
+            ));
+            items.extend(commits_section(
+                &config,
+                format!("Commits behind {}", reference),
+                &behind,
+            ));
+
+            Ok(items)
+        }),
+    )
+}
+
+fn commits_section(config: &Config, title: String, log: &LogRange) -> Vec<Item> {
+    if log.entries.is_empty() {
+        return vec![];
+    }
+
+    let more = (log.more > 0).then(|| Item {
+        id: format!("{}_more", title).into(),
+        display: Line::raw(format!("and {} more", log.more)),
+        depth: 1,
+        unselectable: true,
+        ..Default::default()
+    });
+
+    let header = Item {
+        id: title.clone().into(),
+        display: Line::from(vec![
+            Span::styled(title, &config.style.section_header),
+            format!(" ({})", log.entries.len() + log.more).into(),
+        ]),
+        section: true,
+        depth: 0,
+        ..Default::default()
+    };
+
+    [items::blank_line(), header]
+        .into_iter()
+        .chain(
+            log.entries
+                .iter()
+                .map(|entry| items::log_entry(config, entry)),
+        )
+        .chain(more)
+        .collect()
+}
@@ -0,0 +1,48 @@
+use std::{iter, rc::Rc};
+
+use super::Screen;
+use crate::{
+    config::Config,
+    git,
+    items::{self, Item},
+    Res,
+};
+use git2::Repository;
+use ratatui::{prelude::Rect, text::Line};
+
+pub(crate) fn create(
+    config: Rc<Config>,
+    repo: Rc<Repository>,
+    size: Rect,
+    range: String,
+) -> Res<Screen> {
+    let cache = Rc::new(items::DiffCache::default());
+
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            cache.start_refresh();
+            let diff = git::diff_range(repo.as_ref(), &range)?;
+
+            Ok(iter::once(Item {
+                id: "diff".into(),
+                display: Line::styled(
+                    format!("Diff {} ({})", range, diff.deltas.len()),
+                    &config.style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            })
+            .chain(items::create_diff_items(
+                Rc::clone(&config),
+                &cache,
+                &diff,
+                &1,
+                false,
+            ))
+            .collect())
+        }),
+    )
+}
@@ -0,0 +1,119 @@
+use super::Screen;
+use crate::{
+    config::Config,
+    git::{
+        self,
+        insights::{self, Insights, WEEKS, WEEK_SECONDS},
+        log::LogFilter,
+    },
+    items::{self, Item, TargetData},
+    Res,
+};
+use git2::{Oid, Repository};
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+use std::{cell::RefCell, iter, rc::Rc};
+
+/// The widest an activity bar is drawn, for the busiest week.
+const MAX_BAR: usize = 40;
+
+/// Who committed the most, how busy the weeks up to HEAD were and which paths changed
+/// the most in them. Authors and paths are shown the log of.
+/// The history is only walked again once HEAD has moved.
+pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
+    let walked = RefCell::new(None::<(Option<Oid>, Rc<Insights>)>);
+
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+            let head = repo.head().ok().and_then(|head| head.target());
+            let insights = match &*walked.borrow() {
+                Some((walked_head, insights)) if *walked_head == head => Some(Rc::clone(insights)),
+                _ => None,
+            };
+            let insights = match insights {
+                Some(insights) => insights,
+                None => {
+                    let insights = Rc::new(insights::insights(&repo)?);
+                    walked.replace(Some((head, Rc::clone(&insights))));
+                    insights
+                }
+            };
+            let offset = chrono::FixedOffset::east_opt(insights.until.offset_minutes() * 60)
+                .ok_or("Invalid time zone of HEAD")?;
+
+            let header = |id: &str, title: String| Item {
+                id: id.to_string().into(),
+                display: Line::styled(title, &style.section_header),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            };
+
+            let busiest = insights.weeks.iter().copied().max().unwrap_or(0).max(1);
+            let activity = insights.weeks.iter().enumerate().map(|(week, &commits)| {
+                // Each week is named after the day it ends
+                let end = insights.until.seconds() - week as i64 * WEEK_SECONDS;
+                let end = chrono::DateTime::from_timestamp(end, 0)
+                    .map(|end| end.with_timezone(&offset).format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+                let bar = "█".repeat((commits * MAX_BAR).div_ceil(busiest));
+
+                Item {
+                    id: format!("insights_week_{}", week).into(),
+                    display: Line::from(vec![
+                        Span::raw(format!("{} {:>4} ", end, commits)),
+                        Span::styled(bar, &style.hunk_header),
+                    ]),
+                    depth: 1,
+                    unselectable: true,
+                    ..Default::default()
+                }
+            });
+
+            Ok(iter::once(header(
+                "insights_authors",
+                format!("Commits per author ({})", insights.authors.len()),
+            ))
+            .chain(insights.authors.iter().map(|(author, commits)| Item {
+                id: format!("insights_author_{}", author).into(),
+                display: Line::from(vec![
+                    Span::styled(format!("{:>6} ", commits), &style.hash),
+                    Span::raw(author.clone()),
+                ]),
+                depth: 1,
+                target_data: Some(TargetData::LogFilter(LogFilter::Author(author.clone()))),
+                ..Default::default()
+            }))
+            .chain([items::blank_line()])
+            .chain(iter::once(header(
+                "insights_activity",
+                format!("Commits per week, {} weeks up to HEAD", WEEKS),
+            )))
+            .chain(activity)
+            .chain([items::blank_line()])
+            .chain(iter::once(header(
+                "insights_paths",
+                format!("Largest changes, {} weeks up to HEAD", WEEKS),
+            )))
+            .chain(insights.paths.iter().map(|(path, lines)| {
+                let path_str = git::display_path(path).into_owned();
+                Item {
+                    id: format!("insights_path_{}", path_str).into(),
+                    display: Line::from(vec![
+                        Span::styled(format!("{:>6} ", lines), &style.hash),
+                        Span::styled(path_str, &style.file_header),
+                    ]),
+                    depth: 1,
+                    target_data: Some(TargetData::LogFilter(LogFilter::Path(path.clone()))),
+                    ..Default::default()
+                }
+            }))
+            .collect())
+        }),
+    )
+}
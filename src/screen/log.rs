@@ -1,18 +1,100 @@
 use super::Screen;
-use crate::{config::Config, items::log, Res};
+use crate::{
+    config::Config,
+    git::{
+        self,
+        log::{self, LogCursor, LogFilter},
+    },
+    items::{self, Item, LazyItems},
+    session::View,
+    Res,
+};
 use git2::Repository;
-use ratatui::prelude::Rect;
+use ratatui::{prelude::Rect, text::Line};
 use std::rc::Rc;
 
+/// Commits are listed this many at a time, older ones are loaded once asked for.
+const PAGE_SIZE: usize = 200;
+
 pub(crate) fn create(
     config: Rc<Config>,
     repo: Rc<Repository>,
     size: Rect,
     reference: Option<String>,
+    filter: Option<LogFilter>,
 ) -> Res<Screen> {
-    Screen::new(
+    let view = View::Log {
+        reference: reference.clone(),
+        filter: filter.clone(),
+    };
+
+    Ok(Screen::new(
         Rc::clone(&config),
         size,
-        Box::new(move || log(&config, &repo, usize::MAX, reference.clone())),
-    )
+        Box::new(move |_scope| {
+            let cursor = match &reference {
+                Some(reference) => LogCursor::at(&repo, reference)?,
+                None => match LogCursor::head(&repo)? {
+                    Some(cursor) => cursor,
+                    None => return Ok(vec![]),
+                },
+            };
+
+            let header = filter.as_ref().map(|filter| Item {
+                id: "log_filter".into(),
+                display: Line::styled(
+                    match filter {
+                        LogFilter::Author(name) => format!("Commits by {}", name),
+                        LogFilter::Path(path) => {
+                            format!("Commits changing {}", git::display_path(path))
+                        }
+                    },
+                    &config.style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            });
+
+            let cursor = cursor.filtered(filter.clone());
+            Ok(header
+                .into_iter()
+                .chain(page_items(Rc::clone(&config), Rc::clone(&repo), &cursor)?)
+                .collect())
+        }),
+    )?
+    .with_view(view))
+}
+
+/// A page of commits, followed by a collapsed section holding the next one.
+fn page_items(config: Rc<Config>, repo: Rc<Repository>, cursor: &LogCursor) -> Res<Vec<Item>> {
+    let page = log::page(&repo, cursor, PAGE_SIZE)?;
+
+    let mut items = page
+        .entries
+        .iter()
+        .map(|entry| items::log_entry(&config, entry))
+        .collect::<Vec<_>>();
+
+    if let Some(next) = page.next {
+        items.push(Item {
+            id: format!("older_commits_{}", next.offset()).into(),
+            display: Line::styled("Older commits", &config.style.section_header),
+            section: true,
+            default_collapsed: true,
+            depth: 0,
+            lazy_children: Some(LazyItems(Rc::new(move || {
+                page_items(Rc::clone(&config), Rc::clone(&repo), &next).unwrap_or_else(|err| {
+                    vec![Item {
+                        id: err.to_string().into(),
+                        display: err.to_string().into(),
+                        ..Default::default()
+                    }]
+                })
+            }))),
+            ..Default::default()
+        });
+    }
+
+    Ok(items)
 }
@@ -1,34 +1,99 @@
 use ratatui::prelude::*;
 
-use crate::{config::Config, items::TargetData, Res};
+use crate::{
+    config::{Config, StyleConfig},
+    forge::{CiState, CiStatus},
+    images::{Placement, IMAGE_ROWS},
+    items::TargetData,
+    jobs::Work,
+    session::{SavedScreen, View},
+    Res,
+};
 
 use super::Item;
-use std::{borrow::Cow, collections::HashSet, rc::Rc};
-
+use itertools::Itertools;
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    rc::Rc,
+};
+
+pub(crate) mod blame;
+pub(crate) mod clean;
+pub(crate) mod compare;
+pub(crate) mod diff;
+pub(crate) mod insights;
 pub(crate) mod log;
+pub(crate) mod pull_requests;
+pub(crate) mod recent;
 pub(crate) mod show;
 pub(crate) mod show_refs;
+pub(crate) mod snapshots;
 pub(crate) mod status;
+pub(crate) mod submodules;
+pub(crate) mod tags;
+pub(crate) mod time_machine;
+pub(crate) mod trash;
+pub(crate) mod worktrees;
 
 const BOTTOM_CONTEXT_LINES: usize = 2;
+/// Lazy items are created this many screens ahead of the bottom of the viewport.
+const LAZY_SCREENS_AHEAD: usize = 1;
+
+/// Declares what an action may have changed, letting screens skip recomputing the rest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RefreshScope {
+    /// Anything may have changed, such as HEAD, refs or the whole worktree.
+    All,
+    /// Only the worktree and index state of these paths changed (e.g. by staging a file).
+    Paths(Vec<PathBuf>),
+    /// Like `All`, but also scan for untracked files if that's turned off.
+    ScanUntracked,
+    /// Like `All`, but show ignored files if they're hidden and hide them if they're shown.
+    ToggleIgnored,
+    /// Nothing changed, but work loading in the background may have progressed.
+    Progress,
+}
+
+pub(crate) type RefreshItems = Box<dyn Fn(&RefreshScope) -> Res<Vec<Item>>>;
+
+/// Work to be done on the job worker before the items can be refreshed with
+/// `RefreshScope::Progress`. `None` if the screen is to be refreshed right away.
+pub(crate) type RefreshWork = Box<dyn Fn(&RefreshScope) -> Res<Option<Work>>>;
 
 pub(crate) struct Screen {
     pub(crate) cursor: usize,
     pub(crate) scroll: usize,
     pub(crate) size: Rect,
     config: Rc<Config>,
-    refresh_items: Box<dyn Fn() -> Res<Vec<Item>>>,
+    refresh_items: RefreshItems,
     items: Vec<Item>,
     line_index: Vec<usize>,
     collapsed: HashSet<Cow<'static, str>>,
+    /// Lazily created items that have been collapsed by default, and are up to the user since.
+    seen_lazy_collapsed: HashSet<Cow<'static, str>>,
+    /// Set while items are still being loaded in the background.
+    loading: Option<Rc<Cell<bool>>>,
+    refresh_work: Option<RefreshWork>,
+    /// Refreshes whose work is still running, the items are kept as they are until then.
+    refreshing: usize,
+    /// How many lines were created of expanded lazy items, those below are left to be created
+    /// as they're scrolled to. `None` once all of them are created.
+    expanded_lines: Option<usize>,
+    /// Set for screens that are brought back in the next session.
+    view: Option<View>,
+    /// Where to return to once the items are loaded, as saved in an earlier session.
+    restoring: Option<SavedScreen>,
+    /// How CI went for commits, by their hash. Kept up to date by `State`.
+    pub(crate) ci_statuses: Rc<HashMap<String, CiStatus>>,
+    /// Ids of the items marked to act on together, on screens where they can be.
+    marked: Option<HashSet<Cow<'static, str>>>,
 }
 
 impl Screen {
-    pub(crate) fn new(
-        config: Rc<Config>,
-        size: Rect,
-        refresh_items: Box<dyn Fn() -> Res<Vec<Item>>>,
-    ) -> Res<Self> {
+    pub(crate) fn new(config: Rc<Config>, size: Rect, refresh_items: RefreshItems) -> Res<Self> {
         let mut screen = Self {
             cursor: 0,
             scroll: 0,
@@ -38,26 +103,184 @@ impl Screen {
             items: vec![],
             line_index: vec![],
             collapsed: HashSet::new(),
+            seen_lazy_collapsed: HashSet::new(),
+            loading: None,
+            refresh_work: None,
+            refreshing: 0,
+            expanded_lines: None,
+            view: None,
+            restoring: None,
+            ci_statuses: Rc::default(),
+            marked: None,
         };
 
-        screen.update()?;
+        screen.items = (screen.refresh_items)(&RefreshScope::All)?;
+        screen.init_items();
 
-        // TODO Maybe this should be done on update. Better keep track of toggled sections rather than collapsed then.
-        screen
-            .items
-            .iter()
-            .filter(|item| item.default_collapsed)
-            .for_each(|item| {
-                screen.collapsed.insert(item.id.clone());
-            });
-        screen.update_line_index();
-
-        screen.cursor = screen
-            .find_first_hunk()
-            .or_else(|| screen.find_first_selectable())
+        Ok(screen)
+    }
+
+    /// Collapse sections and select the first hunk, as the items are seen for the first time.
+    /// Unless the screen is restored, then it's as it was left.
+    fn init_items(&mut self) {
+        let restoring = self.restoring.take();
+
+        if let Some(saved) = &restoring {
+            self.collapsed = saved.collapsed.iter().cloned().map(Cow::Owned).collect();
+            self.seen_lazy_collapsed = saved.seen.iter().cloned().map(Cow::Owned).collect();
+
+            // Such as files changed since
+            self.items
+                .iter()
+                .filter(|item| {
+                    item.default_collapsed && !self.seen_lazy_collapsed.contains(&item.id)
+                })
+                .for_each(|item| {
+                    self.collapsed.insert(item.id.clone());
+                });
+        } else {
+            // TODO Maybe this should be done on update. Better keep track of toggled sections rather than collapsed then.
+            self.items
+                .iter()
+                .filter(|item| item.default_collapsed)
+                .for_each(|item| {
+                    self.collapsed.insert(item.id.clone());
+                });
+        }
+        // For what was in view to be created
+        self.scroll = restoring.as_ref().map_or(0, |saved| saved.scroll);
+        self.expand_lazy_items();
+        self.update_line_index();
+
+        let restored = restoring.as_ref().and_then(|saved| {
+            (0..self.line_index.len()).find(|&line_i| self.at_line(line_i).id == saved.selected)
+        });
+        self.cursor = restored
+            .or_else(|| self.find_first_hunk())
+            .or_else(|| self.find_first_selectable())
             .unwrap_or(0);
 
-        Ok(screen)
+        if restored.is_some() {
+            self.scroll_fit_end();
+            self.scroll_fit_start();
+        } else {
+            self.scroll = 0;
+        }
+    }
+
+    pub(crate) fn with_view(mut self, view: View) -> Self {
+        self.view = Some(view);
+        self
+    }
+
+    /// Lets the items that have something to act on be marked, and shows which are.
+    pub(crate) fn with_marking(mut self) -> Self {
+        self.marked = Some(HashSet::new());
+        self
+    }
+
+    /// Mark the selected item, or unmark it, and move on to the next.
+    pub(crate) fn toggle_mark(&mut self) -> Res<()> {
+        let selected = &self.items[self.line_index[self.cursor]];
+        let Some(marked) = &mut self.marked else {
+            return Err("Nothing can be marked on this screen".into());
+        };
+        if selected.target_data.is_none() {
+            return Err("There's nothing to mark here".into());
+        }
+
+        if !marked.remove(&selected.id) {
+            marked.insert(selected.id.clone());
+        }
+
+        self.select_next();
+        Ok(())
+    }
+
+    /// What the marked items are about, from the top down. Ones gone since aren't.
+    pub(crate) fn marked_targets(&self) -> Vec<&TargetData> {
+        let Some(marked) = &self.marked else {
+            return vec![];
+        };
+
+        self.items
+            .iter()
+            .filter(|item| marked.contains(&item.id))
+            .filter_map(|item| item.target_data.as_ref())
+            .collect()
+    }
+
+    pub(crate) fn view(&self) -> Option<&View> {
+        self.view.as_ref()
+    }
+
+    /// Where the user is on this screen, if it's one that can be restored.
+    pub(crate) fn save(&self) -> Option<SavedScreen> {
+        Some(SavedScreen {
+            view: self.view.clone()?,
+            selected: self
+                .items
+                .get(*self.line_index.get(self.cursor)?)?
+                .id
+                .to_string(),
+            scroll: self.scroll,
+            collapsed: self.collapsed.iter().map(|id| id.to_string()).collect(),
+            seen: self
+                .items
+                .iter()
+                .filter(|item| item.default_collapsed)
+                .map(|item| &item.id)
+                .chain(&self.seen_lazy_collapsed)
+                .map(|id| id.to_string())
+                .unique()
+                .collect(),
+        })
+    }
+
+    /// Go back to where the user was in an earlier session, as soon as the items are loaded.
+    /// Items may be gone since, sections are then as they'd be by default.
+    pub(crate) fn restore(&mut self, saved: SavedScreen) -> Res<()> {
+        self.restoring = Some(saved);
+        self.update()
+    }
+
+    /// Have the screen refreshed with `RefreshScope::Progress` for as long as `loading` is set.
+    pub(crate) fn with_loading(mut self, loading: Rc<Cell<bool>>) -> Self {
+        self.loading = Some(loading);
+        self
+    }
+
+    /// Have refreshes prepared by work running in the background, rather than on the main thread.
+    pub(crate) fn with_refresh_work(mut self, refresh_work: RefreshWork) -> Self {
+        self.refresh_work = Some(refresh_work);
+        self
+    }
+
+    /// The work a refresh needs done first, if any. Once it is, call `finish_refresh`.
+    pub(crate) fn refresh_work(&mut self, scope: &RefreshScope) -> Res<Option<Work>> {
+        let Some(refresh_work) = &self.refresh_work else {
+            return Ok(None);
+        };
+
+        let work = refresh_work(scope)?;
+        if work.is_some() {
+            self.refreshing += 1;
+        }
+        Ok(work)
+    }
+
+    /// Swap in the items of a refresh whose work is done. Unless the work wasn't for this screen.
+    pub(crate) fn finish_refresh(&mut self) -> Res<()> {
+        if self.refreshing == 0 {
+            return Ok(());
+        }
+
+        self.refreshing -= 1;
+        self.update_scope(&RefreshScope::Progress)
+    }
+
+    pub(crate) fn is_loading(&self) -> bool {
+        self.loading.as_ref().is_some_and(|loading| loading.get())
     }
 
     fn find_first_hunk(&mut self) -> Option<usize> {
@@ -79,6 +302,7 @@ impl Screen {
         self.cursor = self.find_next();
         self.scroll_fit_end();
         self.scroll_fit_start();
+        self.expand_to_viewport();
     }
 
     fn scroll_fit_start(&mut self) {
@@ -125,6 +349,7 @@ impl Screen {
             .unwrap_or(self.cursor);
 
         self.scroll_fit_start();
+        self.expand_to_viewport();
     }
 
     pub(crate) fn scroll_half_page_up(&mut self) {
@@ -140,9 +365,21 @@ impl Screen {
                 .copied()
                 .enumerate()
                 .map(|(line, _)| (line + 1).saturating_sub(half_screen))
-                .last()
+                .next_back()
                 .unwrap_or(0),
         );
+        self.expand_to_viewport();
+    }
+
+    pub(crate) fn resize(&mut self, size: Rect) {
+        self.size = size;
+        self.expand_to_viewport();
+    }
+
+    /// Have a section collapsed, which takes effect once the screen is updated.
+    /// Sections that show up later start out collapsed this way.
+    pub(crate) fn collapse(&mut self, id: &'static str) {
+        self.collapsed.insert(id.into());
     }
 
     pub(crate) fn toggle_section(&mut self) {
@@ -156,17 +393,106 @@ impl Screen {
             }
         }
 
+        self.expand_lazy_items();
         self.update_line_index();
     }
 
     pub(crate) fn update(&mut self) -> Res<()> {
-        self.items = (self.refresh_items)()?;
+        self.update_scope(&RefreshScope::All)
+    }
+
+    pub(crate) fn update_scope(&mut self, scope: &RefreshScope) -> Res<()> {
+        let was_loading = self.is_loading();
+        self.items = (self.refresh_items)(scope)?;
+
+        // What was cleaned away and comes back isn't marked anymore
+        if let Some(marked) = &mut self.marked {
+            marked.retain(|id| self.items.iter().any(|item| item.id == *id));
+        }
+
+        if (was_loading || self.restoring.is_some()) && !self.is_loading() {
+            // What was shown so far was only a stand-in
+            self.init_items();
+            return Ok(());
+        }
+
+        self.expand_lazy_items();
         self.update_line_index();
         self.clamp_cursor();
         self.move_from_unselectable();
         Ok(())
     }
 
+    /// Create the children of expanded sections that haven't been created yet, as far as
+    /// a screen below the viewport. Those further down are created once scrolled to, so that
+    /// thousands of expanded files aren't all diffed and formatted up front.
+    fn expand_lazy_items(&mut self) {
+        let limit = self.scroll + self.size.height as usize * (1 + LAZY_SCREENS_AHEAD);
+        self.expand_lazy_items_through(limit);
+    }
+
+    /// Create every lazy item of expanded sections, for everything to be printed.
+    pub(crate) fn expand_all(&mut self) {
+        self.expand_lazy_items_through(usize::MAX);
+        self.update_line_index();
+    }
+
+    fn expand_to_viewport(&mut self) {
+        let bottom = self.scroll + self.size.height as usize;
+        if self.expanded_lines.is_some_and(|lines| lines < bottom) {
+            self.expand_lazy_items();
+            self.update_line_index();
+        }
+    }
+
+    fn expand_lazy_items_through(&mut self, limit: usize) {
+        self.expanded_lines = None;
+        if !self.items.iter().any(|item| item.lazy_children.is_some()) {
+            return;
+        }
+
+        // Children come right after their parent, so they're pushed back to be taken next
+        let mut pending = std::mem::take(&mut self.items);
+        pending.reverse();
+        let mut expanded = Vec::with_capacity(pending.len());
+        let mut lines = 0;
+        let mut collapse_depth = None;
+
+        while let Some(mut item) = pending.pop() {
+            // Counted the same way as `update_line_index` does
+            let is_shown = collapse_depth.is_none_or(|depth| depth >= item.depth);
+            if is_shown {
+                lines += 1;
+                collapse_depth = (item.section && self.is_collapsed(&item)).then_some(item.depth);
+            }
+
+            let lazy_children = match item.lazy_children.take() {
+                Some(lazy) if self.is_collapsed(&item) => {
+                    item.lazy_children = Some(lazy);
+                    None
+                }
+                Some(lazy) if is_shown && lines > limit => {
+                    item.lazy_children = Some(lazy);
+                    self.expanded_lines = Some(limit);
+                    None
+                }
+                lazy => lazy,
+            };
+
+            expanded.push(item);
+
+            for child in lazy_children.into_iter().flat_map(|lazy| (lazy.0)()).rev() {
+                if child.default_collapsed && self.seen_lazy_collapsed.insert(child.id.clone()) {
+                    self.collapsed.insert(child.id.clone());
+                }
+
+                pending.push(child);
+            }
+        }
+
+        self.items = expanded;
+    }
+
     fn update_line_index(&mut self) {
         self.line_index = self
             .items
@@ -209,6 +535,94 @@ impl Screen {
         self.collapsed.contains(&item.id)
     }
 
+    /// Lines of all items that aren't within a collapsed section.
+    pub(crate) fn visible_lines<'a>(
+        &'a self,
+        style: &'a StyleConfig,
+    ) -> impl Iterator<Item = Cow<'a, Line<'static>>> {
+        self.line_index
+            .iter()
+            .map(move |&item_i| self.items[item_i].line(style))
+    }
+
+    /// Images on lines within `area`, that fit in it entirely.
+    pub(crate) fn image_placements(&self, area: Rect) -> Vec<Placement> {
+        let end = (self.scroll + area.height as usize).min(self.line_index.len());
+
+        (self.scroll.min(end)..end)
+            .filter_map(|line_i| {
+                let image = self.items[self.line_index[line_i]].image.as_ref()?;
+                let row = (line_i - self.scroll) as u16;
+                (row + IMAGE_ROWS <= area.height).then(|| {
+                    image.placements(area.x + 1, area.y + row, area.width.saturating_sub(1))
+                })
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Commits shown, from the top down, each once.
+    pub(crate) fn commits(&self) -> impl Iterator<Item = &str> {
+        self.items
+            .iter()
+            .filter_map(|item| match &item.target_data {
+                Some(TargetData::Commit(hash)) => Some(hash.as_str()),
+                _ => None,
+            })
+            .unique()
+    }
+
+    /// Commits are marked right after their hash, where they're shown with one at the start.
+    fn mark_ci_status<'a>(
+        &self,
+        item: &Item,
+        line: Cow<'a, Line<'static>>,
+    ) -> Cow<'a, Line<'static>> {
+        let Some(TargetData::Commit(hash)) = &item.target_data else {
+            return line;
+        };
+        let Some(status) = self.ci_statuses.get(hash) else {
+            return line;
+        };
+        let Some(first) = line.spans.first() else {
+            return line;
+        };
+        if first.content.is_empty() || !hash.starts_with(first.content.as_ref()) {
+            return line;
+        }
+
+        let style = &self.config.style.ci;
+        let mark = match status.state {
+            CiState::Success => Span::styled("✔", &style.success),
+            CiState::Failure => Span::styled("✘", &style.failure),
+            CiState::Pending => Span::styled("●", &style.pending),
+        };
+
+        let mut marked = line.into_owned();
+        marked.spans.splice(1..1, [Span::raw(" "), mark]);
+        Cow::Owned(marked)
+    }
+
+    /// Items that can be marked have a box in front, which is ticked if they are.
+    fn mark_marked<'a>(&self, item: &Item, line: Cow<'a, Line<'static>>) -> Cow<'a, Line<'static>> {
+        let Some(marked) = &self.marked else {
+            return line;
+        };
+        if item.target_data.is_none() {
+            return line;
+        }
+
+        let mark = if marked.contains(&item.id) {
+            "[x] "
+        } else {
+            "[ ] "
+        };
+
+        let mut marked = line.into_owned();
+        marked.spans.insert(0, Span::raw(mark));
+        Cow::Owned(marked)
+    }
+
     pub(crate) fn get_selected_item(&self) -> &Item {
         &self.items[self.line_index[self.cursor]]
     }
@@ -234,7 +648,8 @@ impl Widget for &Screen {
                     *highlight_depth = None;
                 };
 
-                Some((item_i, item, &item.display, *highlight_depth))
+                let line = self.mark_marked(item, self.mark_ci_status(item, item.line(style)));
+                Some((item_i, item, line, *highlight_depth))
             })
             .skip(context_lines)
             .enumerate()
@@ -260,7 +675,7 @@ impl Widget for &Screen {
                 }
             }
 
-            line.render(indented_line_area, buf);
+            line.as_ref().render(indented_line_area, buf);
             let overflow = line.width() > line_area.width as usize;
 
             if self.is_collapsed(item) && line.width() > 0 || overflow {
@@ -273,3 +688,110 @@ impl Widget for &Screen {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Screen;
+    use crate::{
+        config::Config,
+        forge::{CiState, CiStatus},
+        items::{Item, LazyItems, TargetData},
+    };
+    use ratatui::{layout::Rect, text::Line};
+    use std::{cell::Cell, collections::HashMap, rc::Rc};
+
+    #[test]
+    fn ci_status_is_marked_after_the_hash() {
+        let commit = |hash: &str| Item {
+            display: Line::from(vec![
+                hash[..7].to_string().into(),
+                " ".into(),
+                "subject".into(),
+            ]),
+            target_data: Some(TargetData::Commit(hash.to_string())),
+            ..Default::default()
+        };
+        let items = [commit("abcdef0123"), commit("0123456789")];
+        let mut screen = Screen::new(
+            Rc::new(Config::default()),
+            Rect::new(0, 0, 80, 10),
+            Box::new(move |_scope| Ok(items.to_vec())),
+        )
+        .unwrap();
+        screen.ci_statuses = Rc::new(HashMap::from([(
+            "abcdef0123".to_string(),
+            CiStatus {
+                state: CiState::Failure,
+                url: String::new(),
+            },
+        )]));
+
+        let lines = screen
+            .items
+            .iter()
+            .map(|item| screen.mark_ci_status(item, item.line(&screen.config.style)))
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, ["abcdef0 ✘ subject", "0123456 subject"]);
+        assert_eq!(
+            screen.commits().collect::<Vec<_>>(),
+            ["abcdef0123", "0123456789"]
+        );
+    }
+
+    #[test]
+    fn lazy_items_are_created_as_they_are_scrolled_to() {
+        let created = Rc::new(Cell::new(0));
+        let sections = {
+            let created = Rc::clone(&created);
+            move || {
+                (0..100)
+                    .map(|i| {
+                        let created = Rc::clone(&created);
+                        Item {
+                            id: format!("file_{}", i).into(),
+                            display: Line::raw(format!("file {}", i)),
+                            section: true,
+                            depth: 0,
+                            lazy_children: Some(LazyItems(Rc::new(move || {
+                                created.set(created.get() + 1);
+                                vec![Item {
+                                    id: format!("hunk_{}", i).into(),
+                                    display: Line::raw("hunk"),
+                                    depth: 1,
+                                    ..Default::default()
+                                }]
+                            }))),
+                            ..Default::default()
+                        }
+                    })
+                    .collect()
+            }
+        };
+        let mut screen = Screen::new(
+            Rc::new(Config::default()),
+            Rect::new(0, 0, 80, 10),
+            Box::new(move |_scope| Ok(sections())),
+        )
+        .unwrap();
+
+        // Two lines per file, two screens' worth of it
+        assert_eq!(created.get(), 10);
+
+        // Not until the viewport gets to the end of what's been created
+        for _ in 0..10 {
+            screen.select_next();
+        }
+        assert_eq!(created.get(), 10);
+        for _ in 0..10 {
+            screen.select_next();
+        }
+        assert_eq!(screen.get_selected_item().id, "file_10");
+        assert_eq!((screen.scroll, created.get()), (14, 16));
+
+        screen.expand_all();
+        assert_eq!(created.get(), 100);
+        assert_eq!(screen.visible_lines(&screen.config.style).count(), 200);
+    }
+}
@@ -0,0 +1,149 @@
+use std::{iter, rc::Rc};
+
+use super::Screen;
+use crate::{
+    config::Config,
+    forge::{Forge, PullRequest},
+    git,
+    items::{self, Item, TargetData},
+    Res,
+};
+use git2::Repository;
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+
+/// Lists the open pull requests of the repository's forge.
+/// They're only requested once, as refreshing would mean waiting on the network after every
+/// command. Reopening the screen gets a fresh list.
+pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
+    let forge = Forge::of_repo(&repo)?;
+    let pull_requests = forge.pull_requests(&config.forge, git::command_dir(&repo))?;
+
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+
+            Ok(iter::once(Item {
+                id: "pull_requests".into(),
+                display: Line::styled(
+                    format!("Open pull requests ({})", pull_requests.len()),
+                    &style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            })
+            .chain(pull_requests.iter().map(|pull_request| Item {
+                id: format!("pull_request_{}", pull_request.number).into(),
+                display: Line::from(vec![
+                    Span::styled(format!("#{}", pull_request.number), &style.hash),
+                    Span::raw(" "),
+                    Span::styled(pull_request.branch.clone(), &style.branch),
+                    Span::raw(" "),
+                    Span::raw(if pull_request.draft { "[draft] " } else { "" }),
+                    Span::raw(pull_request.title.clone()),
+                ]),
+                depth: 1,
+                target_data: Some(TargetData::PullRequest(pull_request.clone())),
+                ..Default::default()
+            }))
+            .collect())
+        }),
+    )
+}
+
+/// Description and CI status of a single pull request.
+pub(crate) fn create_details(
+    config: Rc<Config>,
+    repo: Rc<Repository>,
+    size: Rect,
+    pull_request: PullRequest,
+) -> Res<Screen> {
+    let forge = Forge::of_repo(&repo)?;
+    let checks = forge.checks(&config.forge, git::command_dir(&repo), &pull_request)?;
+
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+            let id = pull_request.number;
+
+            let header = Item {
+                id: format!("pull_request_section_{}", id).into(),
+                display: Line::styled(
+                    format!("#{} {}", id, pull_request.title),
+                    &style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                target_data: Some(TargetData::PullRequest(pull_request.clone())),
+                ..Default::default()
+            };
+
+            let details = [
+                format!("Author: {}", pull_request.author),
+                format!("Branch: {}", pull_request.branch),
+                format!("URL:    {}", pull_request.url),
+            ]
+            .into_iter()
+            .map(|line| Item {
+                id: format!("pull_request_{}", id).into(),
+                display: Line::raw(line),
+                depth: 1,
+                unselectable: true,
+                ..Default::default()
+            });
+
+            let checks_header = Item {
+                id: format!("pull_request_checks_{}", id).into(),
+                display: Line::styled(format!("Checks ({})", checks.len()), &style.section_header),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            };
+
+            let check_items = checks.iter().map(|check| Item {
+                id: format!("pull_request_check_{}_{}", id, check.name).into(),
+                display: Line::from(vec![
+                    Span::styled(
+                        format!("{:<12}", check.status),
+                        check_style(config.as_ref(), &check.status),
+                    ),
+                    Span::raw(check.name.clone()),
+                ]),
+                depth: 1,
+                unselectable: true,
+                ..Default::default()
+            });
+
+            let description = pull_request.description.lines().map(|line| Item {
+                id: format!("pull_request_description_{}", id).into(),
+                display: Line::raw(line.to_string()),
+                depth: 1,
+                unselectable: true,
+                ..Default::default()
+            });
+
+            Ok(iter::once(header)
+                .chain(details)
+                .chain([items::blank_line(), checks_header])
+                .chain(check_items)
+                .chain([items::blank_line()])
+                .chain(description)
+                .collect())
+        }),
+    )
+}
+
+fn check_style<'a>(config: &'a Config, status: &str) -> &'a crate::config::StyleConfigEntry {
+    match status {
+        "success" => &config.style.line_added,
+        "failure" | "failed" | "timed_out" | "canceled" | "cancelled" => &config.style.line_removed,
+        _ => &config.style.hash,
+    }
+}
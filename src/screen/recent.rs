@@ -0,0 +1,60 @@
+use std::{iter, rc::Rc};
+
+use super::Screen;
+use crate::{
+    config::Config,
+    items::{Item, TargetData},
+    recent, Res,
+};
+use git2::Repository;
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+
+pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+            let current = repo.workdir().unwrap_or(repo.path()).canonicalize().ok();
+
+            // Repositories that were moved or deleted since are left out
+            let repos = config
+                .recent_repos_file
+                .as_deref()
+                .map(recent::list)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|path| path.is_dir())
+                .collect::<Vec<_>>();
+
+            Ok(iter::once(Item {
+                id: "recent_repos".into(),
+                display: Line::styled(
+                    format!("Recent repositories ({})", repos.len()),
+                    &style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            })
+            .chain(repos.into_iter().map(|path| {
+                let is_current = current.as_ref() == Some(&path);
+
+                Item {
+                    id: format!("recent_repo_{}", path.display()).into(),
+                    display: Line::from(vec![
+                        Span::raw(if is_current { "* " } else { "  " }),
+                        Span::styled(path.display().to_string(), &style.file_header),
+                    ]),
+                    depth: 1,
+                    target_data: Some(TargetData::Repository(path)),
+                    ..Default::default()
+                }
+            }))
+            .collect())
+        }),
+    )
+}
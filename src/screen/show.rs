@@ -2,17 +2,18 @@ use std::{iter, rc::Rc};
 
 use crate::{
     config::Config,
-    git,
-    items::{self, Item},
+    git::{self, tag::TagSummary},
+    items::{self, Item, TargetData},
+    session::View,
     Res,
 };
 use git2::Repository;
 use ratatui::{
     prelude::Rect,
-    text::{Line, Text},
+    text::{Line, Span, Text},
 };
 
-use super::Screen;
+use super::{tags, Screen};
 
 pub(crate) fn create(
     config: Rc<Config>,
@@ -20,37 +21,87 @@ pub(crate) fn create(
     size: Rect,
     reference: String,
 ) -> Res<Screen> {
-    Screen::new(
+    let cache = Rc::new(items::DiffCache::default());
+
+    let view = View::Show {
+        reference: reference.clone(),
+    };
+
+    Ok(Screen::new(
         Rc::clone(&config),
         size,
-        Box::new(move || {
+        Box::new(move |_scope| {
+            cache.start_refresh();
             let style = &config.style;
             let commit = git::show_summary(repo.as_ref(), &reference)?;
             let show = git::show(repo.as_ref(), &reference)?;
             let details = Text::from(commit.details).lines;
 
-            Ok(iter::once(Item {
-                id: format!("commit_section_{}", commit.hash).into(),
-                display: Line::styled(format!("commit {}", commit.hash), &style.section_header),
-                section: true,
-                depth: 0,
-                ..Default::default()
-            })
-            .chain(details.into_iter().map(|line| Item {
-                id: format!("commit_{}", commit.hash).into(),
-                display: line,
-                depth: 1,
-                unselectable: true,
-                ..Default::default()
-            }))
-            .chain([items::blank_line()])
-            .chain(items::create_diff_items(
-                Rc::clone(&config),
-                &show,
-                &0,
-                false,
-            ))
-            .collect())
+            let tag_items = match git::tag::show_tag_summary(repo.as_ref(), &reference)? {
+                Some(tag) => tag_items(&config, tag),
+                None => vec![],
+            };
+
+            Ok(tag_items
+                .into_iter()
+                .chain(iter::once(Item {
+                    id: format!("commit_section_{}", commit.hash).into(),
+                    display: Line::styled(format!("commit {}", commit.hash), &style.section_header),
+                    section: true,
+                    depth: 0,
+                    target_data: Some(TargetData::Commit(commit.hash.clone())),
+                    ..Default::default()
+                }))
+                .chain(details.into_iter().map(|line| Item {
+                    id: format!("commit_{}", commit.hash).into(),
+                    display: line,
+                    depth: 1,
+                    unselectable: true,
+                    ..Default::default()
+                }))
+                .chain([items::blank_line()])
+                .chain(items::create_diff_items(
+                    Rc::clone(&config),
+                    &cache,
+                    &show,
+                    &0,
+                    false,
+                ))
+                .collect())
         }),
-    )
+    )?
+    .with_view(view))
+}
+
+fn tag_items(config: &Config, tag: TagSummary) -> Vec<Item> {
+    let style = &config.style;
+    let details = Text::from(tag.details).lines;
+
+    let signature = tag.signature.into_iter().flat_map(|signature| {
+        [
+            Line::raw(""),
+            Line::from(vec![
+                Span::raw("Signature: "),
+                tags::signature_span(config, &signature),
+            ]),
+        ]
+    });
+
+    iter::once(Item {
+        id: format!("tag_section_{}", tag.name).into(),
+        display: Line::styled(format!("tag {}", tag.name), &style.section_header),
+        section: true,
+        depth: 0,
+        target_data: Some(TargetData::Tag(tag.name.clone())),
+        ..Default::default()
+    })
+    .chain(details.into_iter().chain(signature).map(|line| Item {
+        id: format!("tag_{}", tag.name).into(),
+        display: line,
+        depth: 1,
+        unselectable: true,
+        ..Default::default()
+    }))
+    .chain([items::blank_line()])
+    .collect()
 }
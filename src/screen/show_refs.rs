@@ -16,7 +16,7 @@ pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Re
     Screen::new(
         Rc::clone(&config),
         size,
-        Box::new(move || {
+        Box::new(move |_scope| {
             let style = &config.style;
             let head = repo.head().ok();
 
@@ -0,0 +1,53 @@
+use std::{iter, rc::Rc};
+
+use super::Screen;
+use crate::{
+    config::Config,
+    git::{self, snapshot},
+    items::{Item, TargetData},
+    Res,
+};
+use git2::Repository;
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+
+pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+            // There's no reflog until the first snapshot is taken
+            let snapshots = snapshot::list(&repo).unwrap_or_default();
+
+            Ok(iter::once(Item {
+                id: "snapshots".into(),
+                display: Line::styled(
+                    format!("Snapshots ({})", snapshots.len()),
+                    &style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            })
+            .chain(snapshots.into_iter().map(|snapshot| {
+                let id = snapshot.id.to_string();
+
+                Item {
+                    id: format!("snapshot_{}", id).into(),
+                    display: Line::from(vec![
+                        Span::styled(id.chars().take(7).collect::<String>(), &style.hash),
+                        Span::raw(format!(" {}   ", snapshot.message)),
+                        Span::raw(git::format_time(snapshot.time)),
+                    ]),
+                    depth: 1,
+                    target_data: Some(TargetData::Snapshot(id)),
+                    ..Default::default()
+                }
+            }))
+            .collect())
+        }),
+    )
+}
@@ -1,160 +1,850 @@
-use std::rc::Rc;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Instant,
+};
 
-use super::Screen;
+use super::{RefreshScope, Screen};
 use crate::{
-    config::Config,
-    git::{self, diff::Diff},
+    config::{Config, StyleConfig, UntrackedFiles},
+    git::{
+        self,
+        diff::{is_within, DeltaCache, Diff},
+        index_flags::IndexFlag,
+        log::{LogCursor, LogEntry},
+    },
     git2_opts,
-    items::{self, Item},
+    items::{self, DiffCache, Item, LazyItems},
+    jobs::JobOutput,
+    keybinds,
+    ops::Op,
+    session::View,
     Res,
 };
-use git2::Repository;
+use git2::{Repository, Status};
+use itertools::Itertools;
 use ratatui::{
     prelude::Rect,
+    style::Modifier,
     text::{Line, Span},
 };
 
+/// The parts of the status that are slow to get in big repositories.
+/// These can be sent across threads, unlike items.
+struct Changes {
+    statuses: Vec<(PathBuf, Status)>,
+    /// Files git doesn't look at for changes
+    flagged: Vec<(PathBuf, IndexFlag)>,
+    unstaged: Diff,
+    staged: Diff,
+    log: Vec<LogEntry>,
+}
+
+/// What changed of some paths only, to patch the changes of an earlier refresh with.
+struct PathChanges {
+    paths: Vec<PathBuf>,
+    statuses: Vec<(PathBuf, Status)>,
+    unstaged: Diff,
+    staged: Diff,
+    /// Set when `staged` isn't limited to `paths`, as something staged may pair up as a rename
+    /// with a path outside of them.
+    all_staged: bool,
+}
+
+/// While the first changes are loaded in the background, only a skeleton of the screen is shown.
+struct Loading {
+    receiver: Receiver<Result<Changes, String>>,
+    started: Instant,
+    header: Option<Vec<Item>>,
+    /// Something else may have changed since loading started, so the result shouldn't be trusted.
+    outdated: bool,
+}
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
 pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
-    Screen::new(
+    let cache = Rc::new(DiffCache::default());
+    // Everything computed on the last refresh, so a refresh limited to some paths only has
+    // to recompute what concerns those paths. Refreshes in the background update it too.
+    let current = Arc::new(Mutex::new(None::<Changes>));
+    // Files that haven't changed since aren't diffed again
+    let deltas = DeltaCache::default();
+    let header = RefCell::new(vec![]);
+    let scan_untracked = Rc::new(Cell::new(false));
+    let show_ignored = Rc::new(Cell::new(false));
+
+    let is_loading = Rc::new(Cell::new(config.general.background_jobs));
+    let loading = RefCell::new(if is_loading.get() {
+        Some(load_in_background(
+            &repo,
+            git2_opts::untracked_files(&config, &repo)?,
+            &deltas,
+        ))
+    } else {
+        None
+    });
+
+    let refresh_work = {
+        let config = Rc::clone(&config);
+        let repo = Rc::clone(&repo);
+        let current = Arc::clone(&current);
+        let deltas = deltas.clone();
+        let scan_untracked = Rc::clone(&scan_untracked);
+        let show_ignored = Rc::clone(&show_ignored);
+        let is_loading = Rc::clone(&is_loading);
+
+        move |scope: &RefreshScope| {
+            // The first load keeps track of what changes meanwhile itself
+            if !config.general.background_jobs
+                || scope == &RefreshScope::Progress
+                || is_loading.get()
+            {
+                return Ok(None);
+            }
+
+            note_scope(scope, &scan_untracked, &show_ignored);
+            let untracked_files = untracked_files(&config, &repo, scan_untracked.get())?;
+            let ignored = show_ignored.get();
+            let paths = match scope {
+                RefreshScope::Paths(paths) => Some(paths.clone()),
+                _ => None,
+            };
+            let dirs = git::RepoDirs::of(&repo);
+            let current = Arc::clone(&current);
+            let deltas = deltas.clone();
+
+            Ok(Some(Box::new(move || {
+                refresh_in_background(&dirs, untracked_files, ignored, paths, &current, &deltas)
+                    .map_err(|err| err.to_string())?;
+                Ok(JobOutput {
+                    out: String::new(),
+                    success: true,
+                })
+            }) as _))
+        }
+    };
+
+    let screen_is_loading = Rc::clone(&is_loading);
+    Ok(Screen::new(
         Rc::clone(&config),
         size,
-        Box::new(move || {
+        Box::new(move |scope| {
+            cache.start_refresh();
             let style = &config.style;
-            let statuses = repo.statuses(Some(&mut git2_opts::status(&repo)?))?;
-            let untracked = untracked(&config, &statuses);
-            let unmerged = unmerged(&config, &statuses);
-
-            let items = if let Some(rebase) = git::rebase_status(&repo)? {
-                vec![Item {
-                    id: "rebase_status".into(),
-                    display: Line::styled(
-                        format!("Rebasing {} onto {}", rebase.head_name, &rebase.onto),
-                        &style.section_header,
-                    ),
-                    ..Default::default()
-                }]
-                .into_iter()
-            } else if let Some(merge) = git::merge_status(&repo)? {
-                vec![Item {
-                    id: "merge_status".into(),
-                    display: Line::styled(
-                        format!("Merging {}", &merge.head),
-                        &style.section_header,
-                    ),
-                    ..Default::default()
-                }]
-                .into_iter()
-            } else {
-                branch_status_items(&config, &repo)?.into_iter()
+
+            note_scope(scope, &scan_untracked, &show_ignored);
+            let ignored = show_ignored.get();
+
+            // Only hinted at when gitu is told not to scan, turning it off in git means to hide them
+            let not_scanned =
+                config.general.untracked_files == Some(UntrackedFiles::No) && !scan_untracked.get();
+            let untracked_files = untracked_files(&config, &repo, scan_untracked.get())?;
+
+            let mut loaded = None;
+            if let Some(mut pending) = loading.take() {
+                match pending.receiver.try_recv() {
+                    Err(TryRecvError::Empty) => {
+                        let header = match pending.header.take() {
+                            Some(header) => {
+                                pending.outdated |= scope != &RefreshScope::Progress;
+                                header
+                            }
+                            None => header_items(&config, &repo)?,
+                        };
+
+                        let items = skeleton_items(&config, &repo, &header, pending.started);
+                        pending.header = Some(header);
+                        loading.replace(Some(pending));
+                        return Ok(items);
+                    }
+                    Ok(result) => {
+                        is_loading.set(false);
+                        let changes = result?;
+                        if !pending.outdated {
+                            loaded = Some(changes);
+                        }
+                    }
+                    Err(TryRecvError::Disconnected) => is_loading.set(false),
+                }
             }
-            .chain(if untracked.is_empty() {
-                vec![]
-            } else {
-                vec![
-                    items::blank_line(),
-                    Item {
-                        id: "untracked".into(),
-                        display: Line::styled("Untracked files", &style.section_header),
-                        section: true,
-                        depth: 0,
-                        ..Default::default()
-                    },
-                ]
-            })
-            .chain(untracked)
-            .chain(if unmerged.is_empty() {
-                vec![]
-            } else {
-                vec![
-                    items::blank_line(),
-                    Item {
-                        id: "unmerged".into(),
-                        display: Line::styled("Unmerged", &style.section_header),
-                        section: true,
-                        depth: 0,
-                        ..Default::default()
-                    },
-                ]
-            })
-            .chain(unmerged)
-            .chain(create_status_section_items(
-                Rc::clone(&config),
-                "Unstaged changes",
-                &git::diff_unstaged(repo.as_ref())?,
-            ))
-            .chain(create_status_section_items(
-                Rc::clone(&config),
-                "Staged changes",
-                &git::diff_staged(repo.as_ref())?,
-            ))
-            .chain(create_log_section_items(
-                Rc::clone(&config),
-                repo.as_ref(),
-                "Recent commits",
-            ))
-            .collect();
+
+            let mut current = current.lock().unwrap();
+            let changes = match (scope, current.take(), loaded) {
+                (_, _, Some(changes)) => {
+                    header.replace(header_items(&config, &repo)?);
+                    changes
+                }
+                (RefreshScope::Paths(paths), Some(mut previous), None) => {
+                    let paths = previous.staged.with_renamed(paths);
+                    previous.patch(path_changes(
+                        &repo,
+                        untracked_files,
+                        ignored,
+                        paths,
+                        &deltas,
+                    )?);
+                    previous
+                }
+                // Refreshed in the background already, but HEAD may have moved too
+                (RefreshScope::Progress, Some(previous), None) => {
+                    header.replace(header_items(&config, &repo)?);
+                    previous
+                }
+                _ => {
+                    header.replace(header_items(&config, &repo)?);
+                    changes(&repo, untracked_files, ignored, &deltas)?
+                }
+            };
+            let changes = &*current.insert(changes);
+
+            let untracked = untracked(&config, &changes.statuses);
+            let unmerged = unmerged(&config, &changes.statuses);
+            let ignored_files = ignored_files(&changes.statuses);
+            let items = header
+                .borrow()
+                .iter()
+                .cloned()
+                .chain(if not_scanned {
+                    vec![items::blank_line(), not_scanned_item(&config)]
+                } else if untracked.is_empty() {
+                    vec![]
+                } else {
+                    vec![
+                        items::blank_line(),
+                        Item {
+                            id: "untracked".into(),
+                            display: Line::styled("Untracked files", &style.section_header),
+                            section: true,
+                            depth: 0,
+                            ..Default::default()
+                        },
+                    ]
+                })
+                .chain(by_directory(&config, "untracked", untracked))
+                .chain(if unmerged.is_empty() {
+                    vec![]
+                } else {
+                    vec![
+                        items::blank_line(),
+                        Item {
+                            id: "unmerged".into(),
+                            display: Line::styled("Unmerged", &style.section_header),
+                            section: true,
+                            depth: 0,
+                            ..Default::default()
+                        },
+                    ]
+                })
+                .chain(unmerged)
+                .chain(flagged_section(&config, &changes.flagged))
+                .chain(if ignored {
+                    vec![
+                        items::blank_line(),
+                        ignored_section(&config, &repo, ignored_files),
+                    ]
+                } else {
+                    vec![]
+                })
+                .chain(create_status_section_items(
+                    Rc::clone(&config),
+                    &cache,
+                    "Unstaged changes",
+                    "[U]",
+                    &changes.unstaged,
+                ))
+                .chain(create_status_section_items(
+                    Rc::clone(&config),
+                    &cache,
+                    "Staged changes",
+                    "[S]",
+                    &changes.staged,
+                ))
+                .chain(create_log_section_items(
+                    Rc::clone(&config),
+                    &changes.log,
+                    "Recent commits",
+                ))
+                .collect();
 
             Ok(items)
         }),
-    )
+    )?
+    .with_loading(screen_is_loading)
+    .with_refresh_work(Box::new(refresh_work))
+    .with_view(View::Status))
 }
 
-fn untracked(config: &Config, statuses: &git2::Statuses<'_>) -> Vec<Item> {
-    let style = &config.style;
-    statuses
+fn load_in_background(
+    repo: &Repository,
+    untracked_files: UntrackedFiles,
+    deltas: &DeltaCache,
+) -> Loading {
+    let (sender, receiver) = mpsc::channel();
+    let dirs = git::RepoDirs::of(repo);
+    let deltas = deltas.clone();
+
+    thread::spawn(move || {
+        let result = dirs
+            .open()
+            .map_err(Into::into)
+            .and_then(|repo| changes(&repo, untracked_files, false, &deltas))
+            .map_err(|err| err.to_string());
+
+        // Nobody is waiting if gitu has quit already
+        let _ = sender.send(result);
+    });
+
+    Loading {
+        receiver,
+        started: Instant::now(),
+        header: None,
+        outdated: false,
+    }
+}
+
+/// All of the changes, deltas not used since the last time this was called leaving `deltas`.
+fn changes(
+    repo: &Repository,
+    untracked_files: UntrackedFiles,
+    ignored: bool,
+    deltas: &DeltaCache,
+) -> Res<Changes> {
+    let log = match LogCursor::head(repo)? {
+        Some(cursor) => git::log::page(repo, &cursor, 10)?.entries,
+        None => vec![],
+    };
+
+    // Without a worktree there's nothing but history to show
+    if repo.is_bare() {
+        return Ok(Changes {
+            statuses: vec![],
+            flagged: vec![],
+            unstaged: Diff::default(),
+            staged: Diff::default(),
+            log,
+        });
+    }
+
+    let statuses = statuses(repo, untracked_files, ignored, &[])?;
+    let changed = statuses
         .iter()
-        .filter_map(|status| {
-            if !status.status().is_wt_new() {
-                return None;
-            }
+        .filter(|(_, status)| *status != Status::WT_NEW && *status != Status::IGNORED)
+        .map(|(path, _)| path.clone())
+        .collect::<Vec<_>>();
+    deltas.start_refresh();
+    let (unstaged, staged) = git::diff_unstaged_and_staged(repo, &changed, deltas)?;
 
-            let path = status.path()?;
+    Ok(Changes {
+        statuses,
+        flagged: git::index_flags::flagged_files(repo)?,
+        unstaged,
+        staged,
+        log,
+    })
+}
 
-            Some(Item {
-                id: path.to_string().into(),
-                display: Line::styled(path.to_string(), &style.file_header),
-                depth: 1,
-                target_data: Some(items::TargetData::File(path.into())),
+fn skeleton_items(
+    config: &Config,
+    repo: &Repository,
+    header: &[Item],
+    started: Instant,
+) -> Vec<Item> {
+    let frame = (started.elapsed().as_millis() / 100) as usize % SPINNER.len();
+    let titles: &[&str] = if repo.is_bare() {
+        &["Recent commits"]
+    } else {
+        &["Unstaged changes", "Staged changes", "Recent commits"]
+    };
+
+    header
+        .iter()
+        .cloned()
+        .chain(titles.iter().flat_map(|&title| {
+            [
+                items::blank_line(),
+                Item {
+                    id: title.into(),
+                    display: Line::from(vec![
+                        Span::styled(title, &config.style.section_header),
+                        format!(" {}", SPINNER[frame]).into(),
+                    ]),
+                    depth: 0,
+                    unselectable: true,
+                    ..Default::default()
+                },
+            ]
+        }))
+        .collect()
+}
+
+fn path_changes(
+    repo: &Repository,
+    untracked_files: UntrackedFiles,
+    ignored: bool,
+    paths: Vec<PathBuf>,
+    deltas: &DeltaCache,
+) -> Res<PathChanges> {
+    let mut staged = git::diff_staged(repo, &paths, deltas)?;
+    let all_staged = staged.deltas.iter().any(|delta| {
+        matches!(
+            delta.status,
+            git2::Delta::Added | git2::Delta::Deleted | git2::Delta::Renamed
+        )
+    });
+    if all_staged {
+        staged = git::diff_staged(repo, &[], deltas)?;
+    }
+
+    Ok(PathChanges {
+        statuses: statuses(repo, untracked_files, ignored, &paths)?,
+        unstaged: git::diff_unstaged(repo, &paths, deltas)?,
+        staged,
+        all_staged,
+        paths,
+    })
+}
+
+impl Changes {
+    fn patch(&mut self, changes: PathChanges) {
+        let paths = &changes.paths;
+        self.statuses
+            .retain(|(path, _)| !paths.iter().any(|changed| is_within(path, changed)));
+        self.statuses.extend(changes.statuses);
+        self.statuses.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        self.unstaged.replace_paths(paths, changes.unstaged);
+        if changes.all_staged {
+            self.staged = changes.staged;
+        } else {
+            self.staged.replace_paths(paths, changes.staged);
+        }
+    }
+}
+
+/// Brings `current` up to date off the main thread, only as far as `paths` if limited to them.
+/// Nothing is held locked while git is asked, for the screen not to wait on it.
+fn refresh_in_background(
+    dirs: &git::RepoDirs,
+    untracked_files: UntrackedFiles,
+    ignored: bool,
+    paths: Option<Vec<PathBuf>>,
+    current: &Mutex<Option<Changes>>,
+    deltas: &DeltaCache,
+) -> Res<()> {
+    let repo = dirs.open()?;
+
+    if let Some(paths) = paths {
+        let paths = match current.lock().unwrap().as_ref() {
+            Some(changes) => changes.staged.with_renamed(&paths),
+            None => paths,
+        };
+        let update = path_changes(&repo, untracked_files, ignored, paths, deltas)?;
+        if let Some(changes) = current.lock().unwrap().as_mut() {
+            changes.patch(update);
+            return Ok(());
+        }
+    }
+
+    let changes = changes(&repo, untracked_files, ignored, deltas)?;
+    current.lock().unwrap().replace(changes);
+    Ok(())
+}
+
+fn note_scope(scope: &RefreshScope, scan_untracked: &Cell<bool>, show_ignored: &Cell<bool>) {
+    if scope == &RefreshScope::ScanUntracked {
+        scan_untracked.set(true);
+    }
+    if scope == &RefreshScope::ToggleIgnored {
+        show_ignored.set(!show_ignored.get());
+    }
+}
+
+fn untracked_files(config: &Config, repo: &Repository, scan: bool) -> Res<UntrackedFiles> {
+    Ok(match git2_opts::untracked_files(config, repo)? {
+        UntrackedFiles::No if scan => UntrackedFiles::Normal,
+        untracked_files => untracked_files,
+    })
+}
+
+fn header_items(config: &Config, repo: &Repository) -> Res<Vec<Item>> {
+    let style = &config.style;
+
+    let mut items = if let Some(rebase) = git::rebase_status(repo)? {
+        vec![
+            Item {
+                id: "rebase_status".into(),
+                display: Line::styled(
+                    format!("Rebasing {} onto {}", rebase.head_name, &rebase.onto),
+                    &style.section_header,
+                ),
                 ..Default::default()
-            })
-        })
+            },
+            actions_hint(&[("continue", Op::RebaseContinue), ("abort", Op::RebaseAbort)]),
+        ]
+    } else if let Some(cherry_pick) = git::cherry_pick_status(repo)? {
+        vec![
+            Item {
+                id: "cherry_pick_status".into(),
+                display: Line::styled(
+                    format!("Cherry-picking {}", &cherry_pick.head)
+                        .trim_end()
+                        .to_string(),
+                    &style.section_header,
+                ),
+                ..Default::default()
+            },
+            actions_hint(&[
+                ("continue", Op::CherryPickContinue),
+                ("skip", Op::CherryPickSkip),
+                ("abort", Op::CherryPickAbort),
+            ]),
+        ]
+    } else if let Some(revert) = git::revert_status(repo)? {
+        vec![
+            Item {
+                id: "revert_status".into(),
+                display: Line::styled(
+                    format!("Reverting {}", &revert.head).trim_end().to_string(),
+                    &style.section_header,
+                ),
+                ..Default::default()
+            },
+            actions_hint(&[("continue", Op::RevertContinue), ("abort", Op::RevertAbort)]),
+        ]
+    } else if let Some(am) = git::am_status(repo)? {
+        vec![
+            Item {
+                id: "am_status".into(),
+                display: Line::styled(
+                    format!("Applying patch {} of {}", am.next, am.last),
+                    &style.section_header,
+                ),
+                ..Default::default()
+            },
+            actions_hint(&[
+                ("continue", Op::AmContinue),
+                ("skip", Op::AmSkip),
+                ("abort", Op::AmAbort),
+            ]),
+        ]
+    } else if let Some(bisect) = git::bisect_status(repo)? {
+        vec![
+            Item {
+                id: "bisect_status".into(),
+                display: Line::styled(
+                    match bisect.revisions_left {
+                        Some(1) => "Bisecting: 1 revision left".to_string(),
+                        Some(n) => format!("Bisecting: {} revisions left", n),
+                        None => "Bisecting: mark a good and a bad commit".to_string(),
+                    },
+                    &style.section_header,
+                ),
+                ..Default::default()
+            },
+            actions_hint(&[
+                ("mark good", Op::BisectGood),
+                ("bad", Op::BisectBad),
+                ("skip", Op::BisectSkip),
+                ("reset", Op::BisectReset),
+            ]),
+        ]
+    } else if let Some(merge) = git::merge_status(repo)? {
+        vec![Item {
+            id: "merge_status".into(),
+            display: Line::styled(format!("Merging {}", &merge.head), &style.section_header),
+            ..Default::default()
+        }]
+    } else {
+        branch_status_items(config, repo)?
+    };
+
+    if repo.is_bare() {
+        items.push(Item {
+            id: "bare".into(),
+            display: Line::raw("Bare repository, there's no worktree"),
+            unselectable: true,
+            ..Default::default()
+        });
+    }
+
+    Ok(items)
+}
+
+/// File statuses, limited to `paths` unless empty. Ignored directories are listed as a whole.
+fn statuses(
+    repo: &Repository,
+    untracked_files: UntrackedFiles,
+    ignored: bool,
+    paths: &[PathBuf],
+) -> Res<Vec<(PathBuf, Status)>> {
+    let opts = &mut git2_opts::status(untracked_files);
+    opts.include_ignored(ignored);
+    paths.iter().for_each(|path| {
+        opts.pathspec(path).disable_pathspec_match(true);
+    });
+
+    Ok(repo
+        .statuses(Some(opts))?
+        .iter()
+        .map(|entry| (git::path_from_bytes(entry.path_bytes()), entry.status()))
+        .collect())
+}
+
+/// Tells which keys continue or get out of what's in progress, like
+/// "Continue with r c or abort with r a."
+fn actions_hint(actions: &[(&str, Op)]) -> Item {
+    let mut parts = actions
+        .iter()
+        .map(|(action, op)| format!("{} with {}", action, op_key(*op)))
+        .collect::<Vec<_>>();
+
+    let last = parts.pop().unwrap_or_default();
+    let hint = if parts.is_empty() {
+        last
+    } else {
+        format!("{} or {}", parts.join(", "), last)
+    };
+
+    let mut chars = hint.chars();
+    let hint = chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+        .unwrap_or_default();
+
+    Item {
+        id: "actions_hint".into(),
+        display: format!("{}.", hint).into(),
+        depth: 1,
+        unselectable: true,
+        ..Default::default()
+    }
+}
+
+/// The keys to press for `op`, including those opening its submenu.
+fn op_key(op: Op) -> String {
+    let Some(keybind) = keybinds::KEYBINDS.iter().find(|keybind| keybind.op == op) else {
+        return String::new();
+    };
+
+    let submenu = keybinds::KEYBINDS
+        .iter()
+        .find(|submenu| submenu.op == Op::Submenu(keybind.submenu))
+        .map(|submenu| format!("{} ", submenu.format_key()))
+        .unwrap_or_default();
+
+    format!("{}{}", submenu, keybind.format_key())
+}
+
+/// Stands in for the untracked files section when scanning for them is turned off.
+fn not_scanned_item(config: &Config) -> Item {
+    let key = op_key(Op::ScanUntracked);
+
+    Item {
+        id: "untracked".into(),
+        display: Line::from(vec![
+            Span::styled("Untracked files", &config.style.section_header),
+            format!(" (not scanned, press {} to scan)", key).into(),
+        ]),
+        section: true,
+        depth: 0,
+        ..Default::default()
+    }
+}
+
+fn untracked(config: &Config, statuses: &[(PathBuf, Status)]) -> Vec<Item> {
+    let style = &config.style;
+    statuses
+        .iter()
+        .filter(|(_, status)| status.is_wt_new())
+        .map(|(path, _)| marked(config, "[?]", file_item(style, path)))
         .collect::<Vec<_>>()
 }
 
-fn unmerged(config: &Config, statuses: &git2::Statuses<'_>) -> Vec<Item> {
+/// Changes to these never show up, so they're listed to not go unnoticed.
+fn flagged_section(config: &Config, flagged: &[(PathBuf, IndexFlag)]) -> Vec<Item> {
+    if flagged.is_empty() {
+        return vec![];
+    }
+
     let style = &config.style;
+    [
+        items::blank_line(),
+        Item {
+            id: "flagged".into(),
+            display: Line::styled("Not checked for changes", &style.section_header),
+            section: true,
+            depth: 0,
+            ..Default::default()
+        },
+    ]
+    .into_iter()
+    .chain(flagged.iter().map(|(path, flag)| {
+        let mut item = file_item(style, path);
+        item.display.spans.push(Span::raw(format!("  {}", flag)));
+        item
+    }))
+    .collect()
+}
+
+fn ignored_files(statuses: &[(PathBuf, Status)]) -> Vec<PathBuf> {
     statuses
         .iter()
-        .filter_map(|status| {
-            if !status.status().is_conflicted() {
-                return None;
-            }
+        .filter(|(_, status)| status.is_ignored())
+        .map(|(path, _)| path.clone())
+        .collect()
+}
 
-            let path = status.path()?;
+pub(crate) const IGNORED_SECTION: &str = "ignored";
 
-            Some(Item {
-                id: path.to_string().into(),
-                display: Line::styled(path.to_string(), &style.file_header),
-                depth: 1,
-                target_data: Some(items::TargetData::File(path.into())),
-                ..Default::default()
-            })
-        })
+/// Collapsed at first, what ignores each file is asked of git once it's expanded.
+fn ignored_section(config: &Rc<Config>, repo: &Rc<Repository>, files: Vec<PathBuf>) -> Item {
+    let config = Rc::clone(config);
+    let repo = Rc::clone(repo);
+
+    Item {
+        id: IGNORED_SECTION.into(),
+        display: Line::styled(
+            format!("Ignored files ({})", files.len()),
+            &config.style.section_header,
+        ),
+        section: true,
+        default_collapsed: true,
+        depth: 0,
+        lazy_children: Some(LazyItems(Rc::new({
+            let config = Rc::clone(&config);
+            move || {
+                let style = &config.style;
+                let rules = git::ignore_rules(&repo, &files).unwrap_or_default();
+
+                files
+                    .iter()
+                    .map(|path| {
+                        let mut item = marked(&config, "[I]", file_item(style, path));
+                        if let Some(rule) = rules.get(path) {
+                            item.display.spans.push(Span::raw(format!("  {}", rule)));
+                        }
+                        item
+                    })
+                    .collect()
+            }
+        }))),
+        ..Default::default()
+    }
+}
+
+fn unmerged(config: &Config, statuses: &[(PathBuf, Status)]) -> Vec<Item> {
+    let style = &config.style;
+    statuses
+        .iter()
+        .filter(|(_, status)| status.is_conflicted())
+        .map(|(path, _)| marked(config, "[!]", file_item(style, path)))
         .collect::<Vec<_>>()
 }
 
+/// Tells what section a file is in without relying on the colors of the section headers.
+fn marked(config: &Config, marker: &str, mut item: Item) -> Item {
+    if config.general.accessible {
+        item.display
+            .spans
+            .insert(0, Span::styled(format!("{} ", marker), Modifier::BOLD));
+    }
+    item
+}
+
+fn file_item(style: &StyleConfig, path: &Path) -> Item {
+    let path_str = git::display_path(path).into_owned();
+
+    Item {
+        id: path_str.clone().into(),
+        display: Line::styled(path_str, &style.file_header),
+        depth: 1,
+        target_data: Some(items::TargetData::File(path.to_path_buf())),
+        ..Default::default()
+    }
+}
+
+fn detached_head_items(
+    config: &Config,
+    repo: &Repository,
+    head: &git2::Reference,
+) -> Res<Vec<Item>> {
+    let commit = head.peel_to_commit()?;
+    let short_id = commit.as_object().short_id()?.as_str().unwrap().to_string();
+    let describe = commit
+        .as_object()
+        .describe(git2::DescribeOptions::new().describe_tags())
+        .and_then(|describe| describe.format(None))
+        .map(|describe| format!(" ({})", describe))
+        .unwrap_or_default();
+
+    // Whatever was checked out before, if it's a branch that's still around
+    let previous = repo
+        .revparse_ext("@{-1}")
+        .ok()
+        .and_then(|(_, reference)| reference?.shorthand().map(str::to_string));
+    let hint = match previous {
+        Some(previous) => format!(
+            "Create a branch here with {}, or return to '{}' with {}.",
+            op_key(Op::CheckoutNewBranch),
+            previous,
+            op_key(Op::CheckoutPrevious)
+        ),
+        None => format!(
+            "Create a branch here with {}.",
+            op_key(Op::CheckoutNewBranch)
+        ),
+    };
+
+    Ok(vec![
+        Item {
+            id: "branch_status".into(),
+            display: Line::styled(
+                format!("HEAD detached at {}{}", short_id, describe),
+                &config.style.section_header,
+            ),
+            section: true,
+            depth: 0,
+            ..Default::default()
+        },
+        Item {
+            id: "detached_hint".into(),
+            display: hint.into(),
+            depth: 1,
+            unselectable: true,
+            ..Default::default()
+        },
+    ])
+}
+
 fn branch_status_items(config: &Config, repo: &Repository) -> Res<Vec<Item>> {
     let style = &config.style;
     let Ok(head) = repo.head() else {
+        let status = match git::unborn_branch(repo) {
+            Some(branch) => format!("No commits yet on {}", branch),
+            None => "No branch".to_string(),
+        };
+
         return Ok(vec![Item {
             id: "branch_status".into(),
-            display: Line::styled("No branch", &style.section_header),
+            display: Line::styled(status, &style.section_header),
             section: true,
             depth: 0,
             ..Default::default()
         }]);
     };
 
+    if repo.head_detached()? {
+        return detached_head_items(config, repo, &head);
+    }
+
     let mut items = vec![Item {
         id: "branch_status".into(),
         display: Line::styled(
@@ -219,7 +909,9 @@ fn branch_status_items(config: &Config, repo: &Repository) -> Res<Vec<Item>> {
 
 fn create_status_section_items<'a>(
     config: Rc<Config>,
+    cache: &'a Rc<DiffCache>,
     header: &str,
+    marker: &'a str,
     diff: &'a Diff,
 ) -> impl Iterator<Item = Item> + 'a {
     let style = &config.style;
@@ -246,16 +938,110 @@ fn create_status_section_items<'a>(
         ]
     }
     .into_iter()
-    .chain(items::create_diff_items(config, diff, &1, true))
+    .chain(by_directory(
+        &config,
+        header,
+        items::create_diff_items(Rc::clone(&config), cache, diff, &1, true)
+            .map(|item| marked(&config, marker, item))
+            .collect(),
+    ))
+}
+
+/// With `status_tree`, the files of a section are put in sections of their own per directory.
+fn by_directory(config: &Config, section: &str, files: Vec<Item>) -> Vec<Item> {
+    if !config.general.status_tree {
+        return files;
+    }
+
+    let entries = files
+        .into_iter()
+        .map(|item| {
+            let path = match &item.target_data {
+                Some(items::TargetData::File(path)) => path.clone(),
+                Some(items::TargetData::Delta(delta)) => delta.new_file.clone(),
+                _ => PathBuf::new(),
+            };
+            let dirs = path
+                .parent()
+                .into_iter()
+                .flat_map(Path::components)
+                .map(|dir| dir.as_os_str().to_os_string())
+                .collect();
+
+            (dirs, item)
+        })
+        .collect();
+
+    directory_items(config, section, entries, OsStr::new(""), 1)
+}
+
+/// Directories first, then the files directly in `parent`. Each file is in
+/// `entries` with the directories it's in below `parent`.
+fn directory_items(
+    config: &Config,
+    section: &str,
+    entries: Vec<(Vec<OsString>, Item)>,
+    parent: &OsStr,
+    depth: usize,
+) -> Vec<Item> {
+    let (files, in_dirs): (Vec<_>, Vec<_>) =
+        entries.into_iter().partition(|(dirs, _)| dirs.is_empty());
+
+    let mut dirs = BTreeMap::<OsString, Vec<(Vec<OsString>, Item)>>::new();
+    for (mut path, item) in in_dirs {
+        let dir = path.remove(0);
+        dirs.entry(dir).or_default().push((path, item));
+    }
+
+    let mut items = vec![];
+    for (mut name, mut entries) in dirs {
+        // A directory with nothing but another directory in it goes along with it, like `src/git/`
+        while entries.iter().all(|(dirs, _)| !dirs.is_empty())
+            && entries.iter().map(|(dirs, _)| &dirs[0]).all_equal()
+        {
+            name.push("/");
+            name.push(&entries[0].0[0]);
+            entries.iter_mut().for_each(|(dirs, _)| {
+                dirs.remove(0);
+            });
+        }
+
+        let mut path = parent.to_os_string();
+        path.push(&name);
+        path.push("/");
+        items.push(Item {
+            id: format!("{}_dir_{}", section, git::display_path(path.as_ref())).into(),
+            display: Line::from(vec![
+                Span::raw("  ".repeat(depth - 1)),
+                Span::styled(
+                    format!("{}/", git::display_path(name.as_ref())),
+                    &config.style.file_header,
+                ),
+                format!(" ({})", entries.len()).into(),
+            ]),
+            section: true,
+            depth,
+            ..Default::default()
+        });
+        items.extend(directory_items(config, section, entries, &path, depth + 1));
+    }
+
+    items.extend(files.into_iter().map(|(_, mut item)| {
+        item.display
+            .spans
+            .insert(0, Span::raw("  ".repeat(depth - 1)));
+        let by = depth.saturating_sub(item.depth);
+        item.deepen(by)
+    }));
+    items
 }
 
 fn create_log_section_items<'a>(
     config: Rc<Config>,
-    repo: &Repository,
+    log: &'a [LogEntry],
     header: &str,
 ) -> impl Iterator<Item = Item> + 'a {
-    let style = &config.style;
-    [
+    let header_items = [
         Item {
             display: Line::raw(""),
             depth: 0,
@@ -264,12 +1050,15 @@ fn create_log_section_items<'a>(
         },
         Item {
             id: header.to_string().into(),
-            display: Line::styled(header.to_string(), &style.section_header),
+            display: Line::styled(header.to_string(), &config.style.section_header),
             section: true,
             depth: 0,
             ..Default::default()
         },
-    ]
-    .into_iter()
-    .chain(items::log(&config, repo, 10, None).unwrap())
+    ];
+
+    header_items.into_iter().chain(
+        log.iter()
+            .map(move |entry| items::log_entry(&config, entry)),
+    )
 }
@@ -0,0 +1,80 @@
+use std::{iter, rc::Rc};
+
+use super::Screen;
+use crate::{
+    config::Config,
+    items::{Item, TargetData},
+    Res,
+};
+use git2::{Repository, SubmoduleIgnore, SubmoduleStatus};
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+
+pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+            let submodules = repo.submodules()?;
+
+            let items = submodules
+                .iter()
+                .map(|submodule| -> Res<Item> {
+                    let path = submodule.path().to_path_buf();
+                    let name = submodule.name().unwrap_or_default();
+                    let status = repo.submodule_status(name, SubmoduleIgnore::None)?;
+
+                    let pinned = submodule
+                        .head_id()
+                        .map(|id| id.to_string()[..7].to_string())
+                        .unwrap_or_else(|| "-------".to_string());
+
+                    Ok(Item {
+                        id: format!("submodule_{}", path.display()).into(),
+                        display: Line::from(vec![
+                            Span::styled(pinned, &style.hash),
+                            Span::raw(" "),
+                            Span::styled(path.display().to_string(), &style.file_header),
+                            Span::raw(describe(status)),
+                        ]),
+                        depth: 1,
+                        target_data: Some(TargetData::Submodule(path)),
+                        ..Default::default()
+                    })
+                })
+                .collect::<Res<Vec<_>>>()?;
+
+            Ok(iter::once(Item {
+                id: "submodules".into(),
+                display: Line::styled(
+                    format!("Submodules ({})", items.len()),
+                    &style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            })
+            .chain(items)
+            .collect())
+        }),
+    )
+}
+
+fn describe(status: SubmoduleStatus) -> &'static str {
+    if status.contains(SubmoduleStatus::WD_UNINITIALIZED) {
+        " (uninitialized)"
+    } else if status.contains(SubmoduleStatus::WD_MODIFIED) {
+        " (out of date)"
+    } else if status.intersects(
+        SubmoduleStatus::WD_INDEX_MODIFIED
+            | SubmoduleStatus::WD_WD_MODIFIED
+            | SubmoduleStatus::WD_UNTRACKED,
+    ) {
+        " (dirty)"
+    } else {
+        ""
+    }
+}
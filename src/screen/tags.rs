@@ -0,0 +1,83 @@
+use std::{iter, rc::Rc};
+
+use super::Screen;
+use crate::{
+    config::Config,
+    git::tag::{self, Signature},
+    items::{Item, TargetData},
+    Res,
+};
+use git2::Repository;
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+
+pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+            let names = repo.tag_names(None)?;
+
+            let items = names
+                .iter()
+                .flatten()
+                // Newest versions first, which is what's usually looked for
+                .rev()
+                .map(|name| -> Res<Item> {
+                    let object = repo.revparse_single(&format!("refs/tags/{}", name))?;
+                    let short_id = object.peel_to_commit()?.as_object().short_id()?;
+
+                    let message = object
+                        .as_tag()
+                        .and_then(|tag| tag.message())
+                        .and_then(|message| message.lines().next())
+                        .unwrap_or("")
+                        .to_string();
+
+                    let mut spans = vec![
+                        Span::styled(short_id.as_str().unwrap().to_string(), &style.hash),
+                        Span::raw(" "),
+                        Span::styled(name.to_string(), &style.tag),
+                        Span::raw(" "),
+                        Span::raw(message),
+                    ];
+                    if let Some(signature) = tag::verify(&repo, name)? {
+                        spans.push(Span::raw(" "));
+                        spans.push(signature_span(&config, &signature));
+                    }
+
+                    Ok(Item {
+                        id: format!("tag_{}", name).into(),
+                        display: Line::from(spans),
+                        depth: 1,
+                        target_data: Some(TargetData::Tag(name.to_string())),
+                        ..Default::default()
+                    })
+                })
+                .collect::<Res<Vec<_>>>()?;
+
+            Ok(iter::once(Item {
+                id: "tags".into(),
+                display: Line::styled(format!("Tags ({})", items.len()), &style.section_header),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            })
+            .chain(items)
+            .collect())
+        }),
+    )
+}
+
+pub(crate) fn signature_span<'a>(config: &Config, signature: &Signature) -> Span<'a> {
+    match signature {
+        Signature::Good(Some(signer)) => {
+            Span::styled(format!("✓ {}", signer), &config.style.line_added)
+        }
+        Signature::Good(None) => Span::styled("✓ good signature", &config.style.line_added),
+        Signature::Bad(reason) => Span::styled(format!("✗ {}", reason), &config.style.line_removed),
+    }
+}
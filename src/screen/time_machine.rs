@@ -0,0 +1,121 @@
+use super::Screen;
+use crate::{
+    config::Config,
+    git::{self, blame},
+    items::{self, Item, TargetData},
+    session::View,
+    Res,
+};
+use git2::{Oid, Repository};
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+use std::{
+    iter,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+pub(crate) fn create(
+    config: Rc<Config>,
+    repo: Rc<Repository>,
+    size: Rect,
+    path: PathBuf,
+    commit: String,
+) -> Res<Screen> {
+    let cache = Rc::new(items::DiffCache::default());
+    let view = View::TimeMachine {
+        path: path.clone(),
+        commit: commit.clone(),
+    };
+
+    Ok(Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            cache.start_refresh();
+            let style = &config.style;
+            let oid = repo.revparse_single(&commit)?.peel_to_commit()?.id();
+            let history = history(&repo, &path, oid)?;
+            let revision = history
+                .iter()
+                .position(|&id| id == oid)
+                .ok_or_else(|| format!("{} didn't change {}", commit, path.display()))?;
+
+            let summary = repo
+                .find_commit(oid)?
+                .summary()
+                .unwrap_or_default()
+                .to_string();
+            let hash = oid.to_string();
+            let diff = git::show_file(&repo, oid, &path)?;
+            let lines = blame::file_lines(&repo, oid, &path)?;
+
+            Ok(iter::once(Item {
+                id: "time_machine".into(),
+                display: Line::styled(
+                    format!("{} at {}", path.display(), &hash[..7]),
+                    &style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                target_data: Some(TargetData::Commit(hash.clone())),
+                ..Default::default()
+            })
+            .chain(iter::once(Item {
+                id: "time_machine_revision".into(),
+                // The oldest is the first revision
+                display: Line::raw(format!(
+                    "Revision {} of {}: {}",
+                    history.len() - revision,
+                    history.len(),
+                    summary
+                )),
+                depth: 1,
+                unselectable: true,
+                ..Default::default()
+            }))
+            .chain([items::blank_line()])
+            .chain(items::create_diff_items(
+                Rc::clone(&config),
+                &cache,
+                &diff,
+                &0,
+                false,
+            ))
+            .chain([items::blank_line()])
+            .chain(iter::once(Item {
+                id: "time_machine_content".into(),
+                display: Line::styled("Content", &style.section_header),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            }))
+            .chain(lines.into_iter().enumerate().map(|(i, line)| Item {
+                id: format!("time_machine_line_{}", i + 1).into(),
+                display: Line::from(vec![
+                    Span::styled(format!("{:>4} ", i + 1), &style.hunk_header),
+                    Span::raw(line),
+                ]),
+                depth: 1,
+                ..Default::default()
+            }))
+            .collect())
+        }),
+    )?
+    .with_view(view))
+}
+
+/// Revisions of the file the commit is one of, the newest first. Those since are stepped
+/// through as well when the commit is in the history of HEAD.
+pub(crate) fn history(repo: &Repository, path: &Path, commit: Oid) -> Res<Vec<Oid>> {
+    if let Ok(head) = repo.head().and_then(|head| head.peel_to_commit()) {
+        let history = git::log::file_history(repo, head.id(), path)?;
+        if history.contains(&commit) {
+            return Ok(history);
+        }
+    }
+
+    git::log::file_history(repo, commit, path)
+}
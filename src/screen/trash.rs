@@ -0,0 +1,44 @@
+use std::{iter, rc::Rc};
+
+use super::Screen;
+use crate::{
+    config::Config,
+    git::trash,
+    items::{Item, TargetData},
+    Res,
+};
+use git2::Repository;
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+
+pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+            let trashed = trash::list(&repo)?;
+
+            Ok(iter::once(Item {
+                id: "trash".into(),
+                display: Line::styled(format!("Trash ({})", trashed.len()), &style.section_header),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            })
+            .chain(trashed.into_iter().map(|trashed| Item {
+                id: format!("trashed_{}", trashed.path.display()).into(),
+                display: Line::from(vec![
+                    Span::styled(trashed.original.display().to_string(), &style.file_header),
+                    Span::raw(format!("   {}", trashed.discarded_at)),
+                ]),
+                depth: 1,
+                target_data: Some(TargetData::Trashed(trashed)),
+                ..Default::default()
+            }))
+            .collect())
+        }),
+    )
+}
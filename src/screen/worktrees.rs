@@ -0,0 +1,64 @@
+use std::{iter, rc::Rc};
+
+use super::Screen;
+use crate::{
+    config::Config,
+    git,
+    items::{Item, TargetData},
+    Res,
+};
+use git2::Repository;
+use ratatui::{
+    prelude::Rect,
+    text::{Line, Span},
+};
+
+pub(crate) fn create(config: Rc<Config>, repo: Rc<Repository>, size: Rect) -> Res<Screen> {
+    Screen::new(
+        Rc::clone(&config),
+        size,
+        Box::new(move |_scope| {
+            let style = &config.style;
+            let current = repo.workdir().and_then(|dir| dir.canonicalize().ok());
+            let worktrees = git::worktree::list(&repo)?;
+
+            Ok(iter::once(Item {
+                id: "worktrees".into(),
+                display: Line::styled(
+                    format!("Worktrees ({})", worktrees.len()),
+                    &style.section_header,
+                ),
+                section: true,
+                depth: 0,
+                ..Default::default()
+            })
+            .chain(worktrees.into_iter().map(|worktree| {
+                let is_current = current.is_some() && worktree.path.canonicalize().ok() == current;
+
+                let checked_out = match (&worktree.branch, &worktree.head) {
+                    _ if worktree.bare => Span::raw("(bare)"),
+                    (Some(branch), _) => Span::styled(branch.clone(), &style.branch),
+                    (None, Some(head)) => {
+                        Span::styled(head.chars().take(7).collect::<String>(), &style.hash)
+                    }
+                    (None, None) => Span::raw(""),
+                };
+
+                Item {
+                    id: format!("worktree_{}", worktree.path.display()).into(),
+                    display: Line::from(vec![
+                        Span::raw(if is_current { "* " } else { "  " }),
+                        checked_out,
+                        Span::raw("   "),
+                        Span::styled(worktree.path.display().to_string(), &style.file_header),
+                    ]),
+                    depth: 1,
+                    target_data: (!worktree.bare)
+                        .then(|| TargetData::Worktree(worktree.path.clone())),
+                    ..Default::default()
+                }
+            }))
+            .collect())
+        }),
+    )
+}
@@ -0,0 +1,125 @@
+use crate::{git::log::LogFilter, Res};
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// What a screen shows, which is all it takes to create it again.
+/// Only screens that are worth coming back to have one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "screen", rename_all = "snake_case")]
+pub(crate) enum View {
+    Status,
+    Log {
+        reference: Option<String>,
+        #[serde(default)]
+        filter: Option<LogFilter>,
+    },
+    Show {
+        reference: String,
+    },
+    TimeMachine {
+        path: PathBuf,
+        commit: String,
+    },
+}
+
+/// Where the user was on a screen, items are told apart by their ids.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SavedScreen {
+    pub(crate) view: View,
+    pub(crate) selected: String,
+    pub(crate) scroll: usize,
+    pub(crate) collapsed: Vec<String>,
+    /// Items collapsed by default which were seen already, and so were up to the user
+    pub(crate) seen: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Session {
+    screens: Vec<SavedScreen>,
+}
+
+/// Every repository gets a file named after a hash of its path, which any path can be
+/// a file name of on every platform. Anything in there can be deleted.
+fn file(dir: &Path, repo: &Repository) -> io::Result<PathBuf> {
+    let repo_dir = repo.workdir().unwrap_or(repo.path()).canonicalize()?;
+    let name = git2::Oid::hash_object(
+        git2::ObjectType::Blob,
+        repo_dir.as_os_str().as_encoded_bytes(),
+    )
+    .map_err(io::Error::other)?;
+
+    Ok(dir.join(name.to_string()))
+}
+
+/// The screens open when gitu was last quit in this repository, the bottom one first.
+/// A session that can't be read is as good as none.
+pub(crate) fn load(dir: &Path, repo: &Repository) -> Vec<SavedScreen> {
+    let session = file(dir, repo)
+        .and_then(fs::read_to_string)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            serde_json::from_str::<Session>(&contents).map_err(|err| err.to_string())
+        });
+
+    match session {
+        Ok(session) => session.screens,
+        Err(err) => {
+            log::debug!("No session restored: {}", err);
+            vec![]
+        }
+    }
+}
+
+pub(crate) fn save(dir: &Path, repo: &Repository, screens: Vec<SavedScreen>) -> Res<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        file(dir, repo)?,
+        serde_json::to_string(&Session { screens })?,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file, load, save, SavedScreen, View};
+    use crate::git::test_repo::TestRepo;
+
+    #[test]
+    fn session_is_kept_per_repository() {
+        let test = TestRepo::at("first");
+        let sessions = test.dir.child("sessions");
+        let (first, second) = (&test.repo, test.init_other("second"));
+        let screens = vec![SavedScreen {
+            view: View::Log {
+                reference: Some("main".to_string()),
+                filter: None,
+            },
+            selected: "abc".to_string(),
+            scroll: 3,
+            collapsed: vec!["Recent commits".to_string()],
+            seen: vec![],
+        }];
+
+        save(&sessions, first, screens.clone()).unwrap();
+
+        assert_eq!(load(&sessions, first), screens);
+        assert!(load(&sessions, &second).is_empty());
+    }
+
+    #[test]
+    fn sessions_of_similar_paths_are_apart() {
+        let test = TestRepo::at("x%/y");
+        let sessions = test.dir.child("sessions");
+        let (first, second) = (&test.repo, test.init_other("x/%y"));
+
+        assert_ne!(
+            file(&sessions, first).unwrap(),
+            file(&sessions, &second).unwrap()
+        );
+    }
+}
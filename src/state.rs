@@ -1,10 +1,24 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
 use std::process::Command;
+use std::process::ExitStatus;
 use std::process::Stdio;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
 use crossterm::event;
 use crossterm::event::Event;
+use crossterm::event::KeyCode;
 use crossterm::event::KeyEventKind;
 use git2::Repository;
 use ratatui::layout::Rect;
@@ -13,38 +27,119 @@ use tui_prompts::Status;
 
 use crate::cli;
 use crate::config::Config;
+use crate::forge::CiState;
+use crate::forge::CiStatus;
+use crate::forge::Forge;
+use crate::git;
 use crate::handle_op;
+use crate::hooks;
+use crate::hooks::Hook;
+use crate::images;
+use crate::images::ImageProtocol;
+use crate::images::Placement;
+use crate::jobs::Job;
+use crate::jobs::JobOutput;
+use crate::jobs::Jobs;
+use crate::jobs::OnDone;
+use crate::jobs::Work;
 use crate::keybinds;
+use crate::ops::Action;
+use crate::ops::Op;
 use crate::ops::SubmenuOp;
 use crate::prompt;
+use crate::prompt::PromptData;
+use crate::recent;
 use crate::screen;
+use crate::screen::RefreshScope;
 use crate::screen::Screen;
+use crate::session;
+use crate::session::SavedScreen;
+use crate::session::View;
 use crate::term;
 use crate::term::Term;
 use crate::ui;
+use crate::watcher::FileWatcher;
 
 use super::command_args;
+use super::copy_cmd;
 use super::CmdMetaBuffer;
 use super::ErrorBuffer;
 use super::Res;
+use super::APP_NAME;
+
+/// How many commits from the top of a screen to show the CI status of
+const CI_COMMITS: usize = 20;
+
+/// How soon to ask again about commits whose CI is still running, or hasn't started
+const CI_RECHECK: Duration = Duration::from_secs(30);
+
+/// How many times to ask about commits without any checks, twice as long apart each time,
+/// before they're taken to have none
+const CI_NO_CHECKS_ASKS: u32 = 4;
 
 pub struct State {
     pub repo: Rc<Repository>,
     pub(crate) config: Rc<Config>,
     pub(crate) quit: bool,
+    /// The screens of the current tab
     pub(crate) screens: Vec<Screen>,
+    /// Screens of every tab, except for the current one's which are in `screens`
+    tabs: Vec<Vec<Screen>>,
+    tab: usize,
     pub(crate) pending_submenu_op: SubmenuOp,
+    /// Arguments toggled on in the open submenu
+    pub(crate) pending_args: Vec<&'static str>,
     pub(crate) cmd_meta_buffer: Option<CmdMetaBuffer>,
     pub(crate) error_buffer: Option<ErrorBuffer>,
     pub(crate) prompt: prompt::Prompt,
+    jobs: Jobs,
+    /// Output so far of a command whose output is shown while it runs
+    streamed_output: Option<Arc<Mutex<String>>>,
+    watcher: Option<FileWatcher>,
+    last_background_fetch: Instant,
+    /// Set when a background fetch moved the upstream, until the next key press.
+    pub(crate) new_upstream_commits: bool,
+    /// What the terminal's title was last set to
+    title: Option<String>,
+    /// Keys pressed since recording a macro started
+    pub(crate) recording: Option<Vec<event::KeyEvent>>,
+    /// The last macro recorded, if any
+    pub(crate) keyboard_macro: Vec<event::KeyEvent>,
+    /// Screens closed one after the other so far, which are saved once the last one is
+    pub(crate) closed_screens: Vec<SavedScreen>,
+    /// Images to draw over the last frame, and those drawn already
+    pub(crate) images: Vec<Placement>,
+    drawn_images: Vec<Placement>,
+    /// How CI went for commits, by their hash. Only asked for with a forge token configured.
+    pub(crate) ci_statuses: Rc<HashMap<String, CiStatus>>,
+    /// When the CI status of commits was last asked for
+    ci_asked: HashMap<String, CiAsked>,
+}
+
+struct CiAsked {
+    at: Instant,
+    /// How many times there were no checks yet
+    without_checks: u32,
 }
 
 impl State {
     pub fn create(repo: Repository, size: Rect, args: &cli::Args, config: Config) -> Res<Self> {
         let repo = Rc::new(repo);
+        let jobs = Jobs::new(config.general.background_jobs);
+        let watcher = if config.general.refresh_on_file_change {
+            repo.workdir().map(|workdir| {
+                FileWatcher::new(
+                    workdir.to_path_buf(),
+                    repo.path().to_path_buf(),
+                    config.general.poll_for_file_changes,
+                )
+            })
+        } else {
+            None
+        };
         let config = Rc::new(config);
 
-        let screens = match args.command {
+        let mut screens = match args.command {
             Some(cli::Commands::Show { ref reference }) => {
                 vec![screen::show::create(
                     Rc::clone(&config),
@@ -53,53 +148,523 @@ impl State {
                     reference.clone(),
                 )?]
             }
-            None => vec![screen::status::create(
+            Some(cli::Commands::Log { ref reference }) => {
+                vec![screen::log::create(
+                    Rc::clone(&config),
+                    Rc::clone(&repo),
+                    size,
+                    reference.clone(),
+                    None,
+                )?]
+            }
+            Some(cli::Commands::Blame { ref path }) => {
+                vec![screen::blame::create(
+                    Rc::clone(&config),
+                    Rc::clone(&repo),
+                    size,
+                    path_in_workdir(&repo, path)?,
+                    None,
+                )?]
+            }
+            Some(cli::Commands::Diff { ref range }) => {
+                vec![screen::diff::create(
+                    Rc::clone(&config),
+                    Rc::clone(&repo),
+                    size,
+                    range.clone(),
+                )?]
+            }
+            Some(cli::Commands::Status) | None => {
+                let saved = match &config.sessions_dir {
+                    Some(dir)
+                        if config.general.restore_session
+                            && args.revision.is_none()
+                            && !args.recent
+                            && !args.print =>
+                    {
+                        session::load(dir, &repo)
+                    }
+                    _ => vec![],
+                };
+
+                let mut screens = restore_screens(&config, &repo, size, saved);
+                if screens.is_empty() {
+                    screens.push(screen::status::create(
+                        Rc::clone(&config),
+                        Rc::clone(&repo),
+                        size,
+                    )?);
+                }
+
+                if let Some(ref revision) = args.revision {
+                    repo.revparse_single(revision)
+                        .map_err(|_| format!("Unknown revision '{}'", revision))?;
+
+                    screens.push(screen::show::create(
+                        Rc::clone(&config),
+                        Rc::clone(&repo),
+                        size,
+                        revision.clone(),
+                    )?);
+                }
+
+                screens
+            }
+        };
+
+        if args.recent {
+            screens.push(screen::recent::create(
                 Rc::clone(&config),
                 Rc::clone(&repo),
                 size,
-            )?],
-        };
+            )?);
+        }
 
-        Ok(Self {
+        let state = Self {
             repo,
             config,
             quit: false,
             screens,
+            tabs: vec![vec![]],
+            tab: 0,
             pending_submenu_op: SubmenuOp::None,
+            pending_args: vec![],
             cmd_meta_buffer: None,
             error_buffer: None,
             prompt: prompt::Prompt::new(),
-        })
+            jobs,
+            streamed_output: None,
+            watcher,
+            last_background_fetch: Instant::now(),
+            new_upstream_commits: false,
+            title: None,
+            recording: None,
+            keyboard_macro: vec![],
+            closed_screens: vec![],
+            images: vec![],
+            drawn_images: vec![],
+            ci_statuses: Rc::default(),
+            ci_asked: HashMap::new(),
+        };
+
+        // What's printed for e.g. a shell prompt isn't really opening the repository
+        if !args.print {
+            state.remember_repo();
+        }
+
+        Ok(state)
     }
 
     pub fn update(&mut self, term: &mut Term, events: &[Event]) -> Res<()> {
+        self.collect_jobs(term);
+        self.fetch_in_background();
+
+        if self.screens.last().is_some_and(Screen::is_loading) {
+            self.refresh_screen(&RefreshScope::Progress)?;
+        }
+
+        if self.watcher.as_ref().is_some_and(FileWatcher::has_changes) {
+            self.refresh_screen(&RefreshScope::All)?;
+        }
+
         for event in events {
             match *event {
                 Event::Resize(w, h) => {
-                    for screen in self.screens.iter_mut() {
-                        screen.size = Rect::new(0, 0, w, h);
-                    }
-                }
-                Event::Key(key) => {
-                    if self.prompt.state.is_focused() {
-                        self.prompt.state.handle_key_event(key)
-                    } else if key.kind == KeyEventKind::Press {
-                        self.cmd_meta_buffer = None;
-                        self.error_buffer = None;
-
-                        self.handle_key_input(term, key)?;
+                    for screen in self
+                        .screens
+                        .iter_mut()
+                        .chain(self.tabs.iter_mut().flatten())
+                    {
+                        screen.resize(Rect::new(0, 0, w, h));
                     }
                 }
+                Event::Key(key) => self.handle_key(term, key)?,
                 _ => (),
             }
 
             self.update_prompt(term)?;
+            self.collect_jobs(term);
+        }
+
+        self.show_streamed_output();
+        self.fetch_ci_statuses();
+
+        if let Some(screen) = self.screens.last_mut() {
+            screen.ci_statuses = Rc::clone(&self.ci_statuses);
+
+            self.update_title(term)?;
+            term.draw(|frame| ui::ui(frame, self))?;
+            self.draw_images(term)?;
+        }
+
+        Ok(())
+    }
+
+    /// Continue in another repository, such as a different worktree.
+    /// Screens of the old repository are dropped, as their items point into it.
+    pub(crate) fn switch_repo(&mut self, repo: Repository, size: Rect) -> Res<()> {
+        let repo = Rc::new(repo);
+        let screen = screen::status::create(Rc::clone(&self.config), Rc::clone(&repo), size)?;
+
+        // The old watcher stops once dropped
+        if self.watcher.is_some() {
+            self.watcher = repo.workdir().map(|workdir| {
+                FileWatcher::new(
+                    workdir.to_path_buf(),
+                    repo.path().to_path_buf(),
+                    self.config.general.poll_for_file_changes,
+                )
+            });
+        }
+
+        self.repo = repo;
+        self.screens = vec![screen];
+        self.tabs = vec![vec![]];
+        self.tab = 0;
+        self.ci_statuses = Rc::default();
+        self.ci_asked.clear();
+        self.remember_repo();
+        Ok(())
+    }
+
+    /// Like `gitu: my-repo (main)`, only written out when it changed, as when checking out.
+    fn update_title(&mut self, term: &mut Term) -> Res<()> {
+        let dir = self.repo.workdir().unwrap_or(self.repo.path());
+        let name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| dir.display().to_string());
+        let branch = match git::unborn_branch(&self.repo) {
+            Some(branch) => branch,
+            None => match self.repo.head() {
+                Ok(head) if head.is_branch() => head.shorthand().unwrap_or("HEAD").to_string(),
+                Ok(head) => head
+                    .peel_to_commit()
+                    .ok()
+                    .and_then(|commit| commit.as_object().short_id().ok())
+                    .and_then(|id| id.as_str().map(str::to_string))
+                    .unwrap_or_else(|| "HEAD".to_string()),
+                Err(_) => "HEAD".to_string(),
+            },
+        };
+
+        let title = format!("{}: {} ({})", APP_NAME, name, branch);
+        if self.title.as_ref() != Some(&title) {
+            term::set_title(term, &title, dir)?;
+            self.title = Some(title);
+        }
+
+        Ok(())
+    }
+
+    /// Images are drawn around ratatui, only when they moved, as they're sent in full.
+    fn draw_images(&mut self, term: &mut Term) -> Res<()> {
+        let Some(protocol) = self.config.image_protocol else {
+            return Ok(());
+        };
+        if self.images == self.drawn_images {
+            return Ok(());
         }
 
-        if self.screens.last_mut().is_some() {
+        // iTerm2 draws images into cells, which ratatui doesn't know to redraw once they move
+        if protocol == ImageProtocol::Iterm2 && !self.drawn_images.is_empty() {
+            term.clear()?;
             term.draw(|frame| ui::ui(frame, self))?;
         }
 
+        images::draw(term, protocol, &self.repo, &self.images)?;
+        self.drawn_images = self.images.clone();
+        Ok(())
+    }
+
+    fn save_session(&mut self) {
+        let screens = std::mem::take(&mut self.closed_screens);
+        let Some(dir) = &self.config.sessions_dir else {
+            return;
+        };
+        if !self.config.general.restore_session {
+            return;
+        }
+
+        if let Err(err) = session::save(dir, &self.repo, screens) {
+            log::warn!("Couldn't save session: {}", err);
+        }
+    }
+
+    fn remember_repo(&self) {
+        let Some(file) = &self.config.recent_repos_file else {
+            return;
+        };
+
+        if let Err(err) = recent::record(file, &self.repo) {
+            log::warn!("Couldn't remember recent repository: {}", err);
+        }
+    }
+
+    pub(crate) fn run_hook(&mut self, hook: Hook) {
+        let commands = hook.commands(&self.config.hooks);
+        if commands.is_empty() {
+            return;
+        }
+
+        for command in commands {
+            let mut cmd = hooks::hook_cmd(hook, command, git::command_dir(&self.repo));
+            git::in_repo(&mut cmd, &self.repo);
+
+            self.jobs.spawn_detached(
+                Job {
+                    display: format!("{}: {}", hook.name(), command).into(),
+                    quiet: false,
+                    on_done: Box::new(|_state, _term, _output| Ok(())),
+                },
+                Box::new(move || {
+                    let output = cmd.output().map_err(|err| err.to_string())?;
+                    Ok(JobOutput {
+                        out: [output.stdout, output.stderr]
+                            .iter()
+                            .map(|bytes| String::from_utf8_lossy(bytes))
+                            .collect(),
+                        success: output.status.success(),
+                    })
+                }),
+            );
+        }
+    }
+
+    /// Fetch once the configured interval has passed, unless something else is already running.
+    /// Failures are ignored, the remote may well be unreachable for a while.
+    fn fetch_in_background(&mut self) {
+        let fetch_config = &self.config.general.background_fetch;
+        if fetch_config.interval == 0
+            || !self.jobs.is_idle()
+            || self.last_background_fetch.elapsed() < Duration::from_secs(fetch_config.interval)
+        {
+            return;
+        }
+
+        self.last_background_fetch = Instant::now();
+
+        let mut cmd = git::background_fetch_cmd(&self.repo, &fetch_config.remotes);
+        git::in_repo(&mut cmd, &self.repo);
+        cmd.stdin(Stdio::null());
+
+        let upstream_before = git::upstream_id(&self.repo);
+
+        self.jobs.spawn(
+            Job {
+                display: command_args(&cmd),
+                quiet: true,
+                on_done: Box::new(move |state, _term, output| {
+                    if !output.success {
+                        return Ok(());
+                    }
+
+                    let upstream_after = git::upstream_id(&state.repo);
+                    if upstream_after.is_some() && upstream_after != upstream_before {
+                        state.new_upstream_commits = true;
+                    }
+
+                    state.refresh_screen(&RefreshScope::All)
+                }),
+            },
+            Box::new(move || {
+                let output = cmd.output().map_err(|err| err.to_string())?;
+                Ok(JobOutput {
+                    out: String::from_utf8_lossy(&output.stderr).into_owned(),
+                    success: output.status.success(),
+                })
+            }),
+        );
+    }
+
+    /// Ask the forge how CI went for the topmost commits shown, in the background.
+    /// Ones that were still running are asked for again after a while. So are those that
+    /// had nothing run yet, less and less often, as CI may not have picked them up.
+    fn fetch_ci_statuses(&mut self) {
+        let forge_config = &self.config.forge;
+        if forge_config.github_token.is_none() && forge_config.gitlab_token.is_none() {
+            return;
+        }
+
+        let Some(screen) = self.screens.last().filter(|screen| !screen.is_loading()) else {
+            return;
+        };
+
+        let hashes = screen
+            .commits()
+            .take(CI_COMMITS)
+            .filter(|hash| {
+                let Some(asked) = self.ci_asked.get(*hash) else {
+                    return true;
+                };
+
+                match self.ci_statuses.get(*hash) {
+                    Some(status) => {
+                        status.state == CiState::Pending && asked.at.elapsed() >= CI_RECHECK
+                    }
+                    None => {
+                        asked.without_checks < CI_NO_CHECKS_ASKS
+                            && asked.at.elapsed() >= CI_RECHECK * 2u32.pow(asked.without_checks)
+                    }
+                }
+            })
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        if hashes.is_empty() {
+            return;
+        }
+
+        for hash in &hashes {
+            self.ci_asked
+                .entry(hash.clone())
+                .and_modify(|asked| asked.at = Instant::now())
+                .or_insert(CiAsked {
+                    at: Instant::now(),
+                    without_checks: 0,
+                });
+        }
+
+        let Ok(forge) = Forge::of_repo(&self.repo) else {
+            return;
+        };
+        let forge_config = forge_config.clone();
+        let dir = git::command_dir(&self.repo).to_path_buf();
+        let found = Arc::new(Mutex::new(vec![]));
+        let found_in_work = Arc::clone(&found);
+
+        self.jobs.spawn_detached(
+            Job {
+                display: "CI status".into(),
+                quiet: true,
+                on_done: Box::new(move |state, _term, _output| {
+                    let statuses = Rc::make_mut(&mut state.ci_statuses);
+                    for (hash, status) in found.lock().unwrap().drain(..) {
+                        match status {
+                            Some(status) => {
+                                statuses.insert(hash, status);
+                            }
+                            None => {
+                                statuses.remove(&hash);
+                                if let Some(asked) = state.ci_asked.get_mut(&hash) {
+                                    asked.without_checks += 1;
+                                }
+                            }
+                        }
+                    }
+                    Ok(())
+                }),
+            },
+            Box::new(move || {
+                for hash in hashes {
+                    match forge.commit_checks(&forge_config, &dir, &hash) {
+                        Ok(checks) => found_in_work
+                            .lock()
+                            .unwrap()
+                            .push((hash, CiStatus::of_checks(&checks))),
+                        // Not something to interrupt anyone with, it's asked for again later
+                        Err(err) => {
+                            log::debug!("Couldn't get the CI status of {}: {}", hash, err);
+                            break;
+                        }
+                    }
+                }
+
+                Ok(JobOutput {
+                    out: String::new(),
+                    success: true,
+                })
+            }),
+        );
+    }
+
+    /// Hand finished background work back to whoever spawned it.
+    fn collect_jobs(&mut self, term: &mut Term) {
+        while let Some((job, result)) = self.jobs.try_recv() {
+            self.finish_job(term, job, result);
+        }
+    }
+
+    /// Block until the queued work is done, including refreshes following from it.
+    /// Work on threads of its own, like hooks and asking for CI statuses, isn't waited on.
+    pub(crate) fn await_jobs(&mut self, term: &mut Term) {
+        while let Some((job, result)) = self.jobs.recv_queued() {
+            self.finish_job(term, job, result);
+        }
+    }
+
+    /// Quitting shouldn't cut a push short, but fetching and refreshing in the background
+    /// is of no use anymore.
+    pub(crate) fn await_commands(&mut self, term: &mut Term) {
+        loop {
+            self.jobs.drop_quiet();
+            let Some((job, result)) = self.jobs.recv_queued() else {
+                break;
+            };
+            self.finish_job(term, job, result);
+        }
+    }
+
+    fn finish_job(&mut self, term: &mut Term, job: Job, result: Result<JobOutput, String>) {
+        let result = result.map_err(Into::into).and_then(|output| {
+            if !job.quiet {
+                self.cmd_meta_buffer = Some(CmdMetaBuffer {
+                    args: job.display,
+                    out: Some(output.out.clone()),
+                });
+            }
+
+            (job.on_done)(self, term, &output)
+        });
+
+        if let Err(error) = result {
+            self.error_buffer = Some(ErrorBuffer(error.to_string()));
+        }
+    }
+
+    pub(crate) fn spawn_job<S: Into<Cow<'static, str>>>(
+        &mut self,
+        display: S,
+        work: Work,
+        on_done: OnDone,
+    ) {
+        let display = display.into();
+        self.cmd_meta_buffer = Some(CmdMetaBuffer {
+            args: display.clone(),
+            out: None,
+        });
+
+        self.jobs.spawn(
+            Job {
+                display,
+                quiet: false,
+                on_done,
+            },
+            work,
+        );
+    }
+
+    /// Refreshes the screen, or has the work it takes done on the job worker first.
+    pub(crate) fn refresh_screen(&mut self, scope: &RefreshScope) -> Res<()> {
+        let Some(screen) = self.screens.last_mut() else {
+            return Ok(());
+        };
+
+        match screen.refresh_work(scope)? {
+            Some(work) => self.jobs.spawn(
+                Job {
+                    display: "Refresh".into(),
+                    quiet: true,
+                    on_done: Box::new(|state, _term, _output| match state.screens.last_mut() {
+                        Some(screen) => screen.finish_refresh(),
+                        None => Ok(()),
+                    }),
+                },
+                work,
+            ),
+            None => screen.update_scope(scope)?,
+        }
+
         Ok(())
     }
 
@@ -108,7 +673,8 @@ impl State {
             self.prompt.reset(term)?;
         } else if let Some(mut prompt_data) = self.prompt.data.take() {
             (Rc::get_mut(&mut prompt_data.update_fn).unwrap())(self, term)?;
-            if self.prompt.state.is_focused() {
+            // Unless the prompt was done and another one asked for
+            if self.prompt.state.is_focused() && self.prompt.data.is_none() {
                 self.prompt.data = Some(prompt_data);
             }
         }
@@ -116,18 +682,63 @@ impl State {
         Ok(())
     }
 
-    pub(crate) fn handle_key_input(&mut self, term: &mut Term, key: event::KeyEvent) -> Res<()> {
+    /// A key typed into a prompt, or one running an op. Keys are recorded into a macro from
+    /// here, but not keys that record or replay macros themselves.
+    pub(crate) fn handle_key(&mut self, term: &mut Term, key: event::KeyEvent) -> Res<()> {
+        let is_prompt_key = self.prompt.state.is_focused();
+        let record = self.recording.is_some()
+            && (is_prompt_key
+                || !matches!(self.op_of_key(key), Some(Op::RecordMacro | Op::ReplayMacro)));
+
+        if is_prompt_key && key.code == KeyCode::Tab && key.kind != KeyEventKind::Release {
+            self.prompt.complete();
+        } else if is_prompt_key {
+            self.prompt.state.handle_key_event(key)
+        } else if key.kind == KeyEventKind::Press {
+            // Keep showing what's running until it's done
+            if !self.jobs.is_running() {
+                self.cmd_meta_buffer = None;
+            }
+            self.error_buffer = None;
+            self.new_upstream_commits = false;
+
+            self.handle_key_input(term, key)?;
+        }
+
+        if let Some(keys) = self.recording.as_mut().filter(|_| record) {
+            keys.push(key);
+        }
+
+        Ok(())
+    }
+
+    fn op_of_key(&self, key: event::KeyEvent) -> Option<Op> {
         let pending = if self.pending_submenu_op == SubmenuOp::Help {
             SubmenuOp::None
         } else {
             self.pending_submenu_op
         };
 
-        if let Some(op) = keybinds::op_of_key_event(pending, key) {
+        keybinds::op_of_key_event(pending, key)
+    }
+
+    pub(crate) fn handle_key_input(&mut self, term: &mut Term, key: event::KeyEvent) -> Res<()> {
+        if let Some(op) = self.op_of_key(key) {
+            // It's only the screens closed on the way out that are saved
+            if op != Op::Quit {
+                self.closed_screens.clear();
+            }
+
             let result = handle_op(self, op, term);
 
             if let Err(error) = result {
-                self.error_buffer = Some(ErrorBuffer(error.to_string()));
+                let message = error.to_string();
+                if git::index_lock::is_lock_error(&message) {
+                    self.recover_from_index_lock(Rc::new(move |state, term| {
+                        handle_op(state, op, term)
+                    }));
+                }
+                self.error_buffer = Some(ErrorBuffer(message));
             }
         }
 
@@ -137,11 +748,18 @@ impl State {
     pub(crate) fn handle_quit(&mut self) -> Res<()> {
         match self.pending_submenu_op {
             SubmenuOp::None => {
+                if let Some(saved) = self.screen().save() {
+                    self.closed_screens.insert(0, saved);
+                }
+
                 self.screens.pop();
-                if let Some(screen) = self.screens.last_mut() {
-                    screen.update()?;
+                if !self.screens.is_empty() {
+                    self.refresh_screen(&RefreshScope::All)?;
+                } else if self.tabs.len() > 1 {
+                    self.close_tab()?;
                 } else {
-                    self.quit = true
+                    self.quit = true;
+                    self.save_session();
                 }
             }
             _ => {
@@ -153,6 +771,43 @@ impl State {
         Ok(())
     }
 
+    pub(crate) fn tab(&self) -> usize {
+        self.tab
+    }
+
+    pub(crate) fn tab_count(&self) -> usize {
+        self.tabs.len()
+    }
+
+    /// Open `screen` in a new tab after the current one, and switch to it.
+    pub(crate) fn new_tab(&mut self, screen: Screen) {
+        self.tabs[self.tab] = std::mem::take(&mut self.screens);
+        self.tab += 1;
+        self.tabs.insert(self.tab, vec![]);
+        self.screens = vec![screen];
+    }
+
+    /// Switch to another tab, which is refreshed as things may have changed meanwhile.
+    pub(crate) fn select_tab(&mut self, tab: usize) -> Res<()> {
+        if tab == self.tab {
+            return Ok(());
+        }
+
+        self.tabs[self.tab] = std::mem::take(&mut self.screens);
+        self.screens = std::mem::take(&mut self.tabs[tab]);
+        self.tab = tab;
+        self.refresh_screen(&RefreshScope::All)
+    }
+
+    /// The current tab's last screen was closed, the one before it takes over.
+    fn close_tab(&mut self) -> Res<()> {
+        self.tabs.remove(self.tab);
+        self.tab = self.tab.saturating_sub(1);
+        self.screens = std::mem::take(&mut self.tabs[self.tab]);
+        self.closed_screens.clear();
+        self.refresh_screen(&RefreshScope::All)
+    }
+
     pub(crate) fn screen_mut(&mut self) -> &mut Screen {
         self.screens.last_mut().expect("No screen")
     }
@@ -165,61 +820,257 @@ impl State {
         &mut self,
         term: &mut Term,
         input: &[u8],
-        mut cmd: Command,
+        cmd: Command,
+        scope: RefreshScope,
+    ) -> Res<()> {
+        self.run_external_cmd_then(term, input, cmd, scope, |_state, _term| Ok(()))
+    }
+
+    /// Run a command in the background, refreshing `scope` of the screen once it has exited.
+    /// `on_success` is called afterwards, unless the command failed.
+    pub(crate) fn run_external_cmd_then<F: FnOnce(&mut State, &mut Term) -> Res<()> + 'static>(
+        &mut self,
+        term: &mut Term,
+        input: &[u8],
+        cmd: Command,
+        scope: RefreshScope,
+        on_success: F,
     ) -> Res<()> {
-        cmd.current_dir(self.repo.workdir().expect("No workdir"));
+        self.run_external_cmd_or(
+            term,
+            input,
+            cmd,
+            scope,
+            on_success,
+            |_state, _term, _output| Ok(()),
+        )
+    }
+
+    /// Like `run_external_cmd_then`, with `on_failure` called if the command failed.
+    /// Except when it failed because the index was locked, then it may be retried.
+    pub(crate) fn run_external_cmd_or<F, E>(
+        &mut self,
+        term: &mut Term,
+        input: &[u8],
+        mut cmd: Command,
+        scope: RefreshScope,
+        on_success: F,
+        on_failure: E,
+    ) -> Res<()>
+    where
+        F: FnOnce(&mut State, &mut Term) -> Res<()> + 'static,
+        E: FnOnce(&mut State, &mut Term, &JobOutput) -> Res<()> + 'static,
+    {
+        git::in_repo(&mut cmd, &self.repo);
 
         cmd.stdin(Stdio::piped());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
 
-        self.run_cmd(term, command_args(&cmd), |_state| {
-            let mut child = cmd.spawn()?;
+        let input = input.to_vec();
+        let retry_input = input.clone();
+        let retry_cmd = copy_cmd(&cmd);
+        self.spawn_job(
+            command_args(&cmd),
+            Box::new(move || {
+                let mut child = cmd.spawn().map_err(|err| err.to_string())?;
+
+                use std::io::Write;
+                child
+                    .stdin
+                    .take()
+                    .unwrap()
+                    .write_all(&input)
+                    .map_err(|err| err.to_string())?;
 
-            use std::io::Write;
-            child.stdin.take().unwrap().write_all(input)?;
+                let output = child.wait_with_output().map_err(|err| err.to_string())?;
 
-            let out = String::from_utf8(child.wait_with_output()?.stderr.clone())
-                .expect("Error turning command output to String");
+                Ok(JobOutput {
+                    out: String::from_utf8_lossy(&output.stderr).into_owned(),
+                    success: output.status.success(),
+                })
+            }),
+            Box::new(move |state, term, output| {
+                state.refresh_screen(&scope)?;
 
-            Ok(out)
-        })?;
+                if output.success {
+                    on_success(state, term)?;
+                } else if git::index_lock::is_lock_error(&output.out) {
+                    let mut retry = Some((retry_cmd, on_success, on_failure));
+                    state.recover_from_index_lock(Rc::new(move |state, term| {
+                        let Some((cmd, on_success, on_failure)) = retry.take() else {
+                            return Ok(());
+                        };
+                        state.run_external_cmd_or(
+                            term,
+                            &retry_input,
+                            cmd,
+                            scope.clone(),
+                            on_success,
+                            on_failure,
+                        )
+                    }));
+                } else {
+                    on_failure(state, term, output)?;
+                }
 
+                Ok(())
+            }),
+        );
+
+        term.draw(|frame| ui::ui(frame, self))?;
         Ok(())
     }
 
-    pub(crate) fn run_cmd<S: Into<Cow<'static, str>>, F: FnMut(&mut Self) -> Res<String>>(
+    /// Git failed as the index is locked. Unless it's been unlocked since, ask whether to retry,
+    /// as another git process may be done by now, or to remove a lock that was left behind.
+    pub(crate) fn recover_from_index_lock(&mut self, mut retry: Action) {
+        let Some(age) = git::index_lock::age(&self.repo) else {
+            return;
+        };
+
+        self.prompt.set(PromptData {
+            prompt_text: format!(
+                "Index locked for {}. Retry (r), remove index.lock (d) or cancel (n)?",
+                git::index_lock::format_age(age)
+            )
+            .into(),
+            update_fn: Rc::new(move |state: &mut State, term: &mut Term| {
+                if state.prompt.state.status().is_pending() {
+                    match state.prompt.state.value() {
+                        "r" => {
+                            state.prompt.reset(term)?;
+                            Rc::get_mut(&mut retry).unwrap()(state, term)?;
+                        }
+                        "d" => {
+                            state.prompt.reset(term)?;
+                            git::index_lock::remove(&state.repo)?;
+                            Rc::get_mut(&mut retry).unwrap()(state, term)?;
+                        }
+                        "" => (),
+                        _ => state.prompt.reset(term)?,
+                    }
+                }
+                Ok(())
+            }),
+        });
+    }
+
+    /// Like `run_external_cmd`, but shows stdout as well as stderr, as it arrives.
+    pub(crate) fn run_streamed_cmd(
         &mut self,
         term: &mut Term,
-        display: S,
-        mut cmd: F,
+        mut cmd: Command,
+        scope: RefreshScope,
     ) -> Res<()> {
-        self.cmd_meta_buffer = Some(CmdMetaBuffer {
-            args: display.into(),
-            out: None,
-        });
-        term.draw(|frame| ui::ui(frame, self))?;
+        git::in_repo(&mut cmd, &self.repo);
+        git::never_prompt(&mut cmd, self.repo.config());
 
-        self.cmd_meta_buffer.as_mut().unwrap().out = Some(cmd(self)?);
-        self.screen_mut().update()?;
+        cmd.stdin(Stdio::null());
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
 
+        let streamed = Arc::new(Mutex::new(String::new()));
+        self.streamed_output = Some(Arc::clone(&streamed));
+
+        self.spawn_job(
+            command_args(&cmd),
+            Box::new(move || {
+                let mut child = cmd.spawn().map_err(|err| err.to_string())?;
+
+                let readers = [
+                    stream_into(child.stdout.take().unwrap(), Arc::clone(&streamed)),
+                    stream_into(child.stderr.take().unwrap(), Arc::clone(&streamed)),
+                ];
+                for reader in readers {
+                    let _ = reader.join();
+                }
+
+                let status = child.wait().map_err(|err| err.to_string())?;
+                let out = streamed.lock().unwrap().clone();
+
+                Ok(JobOutput {
+                    out,
+                    success: status.success(),
+                })
+            }),
+            Box::new(move |state, _term, _output| {
+                state.streamed_output = None;
+                state.refresh_screen(&scope)
+            }),
+        );
+
+        term.draw(|frame| ui::ui(frame, self))?;
         Ok(())
     }
 
-    pub(crate) fn issue_subscreen_command(&mut self, term: &mut Term, mut cmd: Command) -> Res<()> {
-        cmd.current_dir(self.repo.workdir().expect("No workdir"));
+    fn show_streamed_output(&mut self) {
+        let (Some(streamed), Some(cmd)) = (&self.streamed_output, &mut self.cmd_meta_buffer) else {
+            return;
+        };
+
+        let out = streamed.lock().unwrap();
+        // Keep showing that it's running until there's something else to show
+        if !out.is_empty() {
+            cmd.out = Some(out.clone());
+        }
+    }
+
+    pub(crate) fn issue_subscreen_command(
+        &mut self,
+        term: &mut Term,
+        cmd: Command,
+    ) -> Res<ExitStatus> {
+        self.subscreen_command(term, cmd, false)
+    }
+
+    /// Like `issue_subscreen_command`, with what the command says on stderr copied to the
+    /// command log too, as what's on the terminal is cleared after. For commands that may
+    /// fail for reasons that are worth reading, like a hook rejecting them.
+    pub(crate) fn issue_logged_subscreen_command(
+        &mut self,
+        term: &mut Term,
+        cmd: Command,
+    ) -> Res<ExitStatus> {
+        self.subscreen_command(term, cmd, true)
+    }
+
+    fn subscreen_command(
+        &mut self,
+        term: &mut Term,
+        mut cmd: Command,
+        log_stderr: bool,
+    ) -> Res<ExitStatus> {
+        git::in_repo(&mut cmd, &self.repo);
 
         cmd.stdin(Stdio::piped());
-        let child = cmd.spawn()?;
+        if log_stderr {
+            cmd.stderr(Stdio::piped());
+        }
+        let mut child = cmd.spawn()?;
+
+        // Still shown while the command runs
+        let tee = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut logged = vec![];
+                let mut buf = [0; 4096];
+                while let Ok(n @ 1..) = stderr.read(&mut buf) {
+                    logged.extend_from_slice(&buf[..n]);
+                    let mut terminal = std::io::stderr();
+                    let _ = terminal
+                        .write_all(&buf[..n])
+                        .and_then(|()| terminal.flush());
+                }
+                logged
+            })
+        });
 
-        let out = child.wait_with_output()?;
+        let status = child.wait()?;
+        let logged = tee.map(|tee| tee.join().unwrap_or_default());
 
         self.cmd_meta_buffer = Some(CmdMetaBuffer {
             args: command_args(&cmd),
-            out: Some(
-                String::from_utf8(out.stderr.clone())
-                    .expect("Error turning command output to String"),
-            ),
+            out: logged.map(|logged| String::from_utf8_lossy(&logged).into_owned()),
         });
 
         // Prevents cursor flash when exiting editor
@@ -229,8 +1080,127 @@ impl State {
         term::enter_alternate_screen()?;
 
         term.clear()?;
-        self.screen_mut().update()?;
+        self.refresh_screen(&RefreshScope::All)?;
 
-        Ok(())
+        Ok(status)
+    }
+}
+
+/// The screens left open in an earlier session, if the status was the first of them.
+/// One that can't be created anymore, such as a commit that's gone, is left out along with
+/// those on top of it.
+fn restore_screens(
+    config: &Rc<Config>,
+    repo: &Rc<Repository>,
+    size: Rect,
+    saved: Vec<SavedScreen>,
+) -> Vec<Screen> {
+    if saved.first().map(|saved| &saved.view) != Some(&View::Status) {
+        return vec![];
+    }
+
+    let mut screens = vec![];
+    for saved in saved {
+        let config = Rc::clone(config);
+        let repo = Rc::clone(repo);
+        let screen = match &saved.view {
+            View::Status => screen::status::create(config, repo, size),
+            View::Log { reference, filter } => {
+                screen::log::create(config, repo, size, reference.clone(), filter.clone())
+            }
+            View::Show { reference } => screen::show::create(config, repo, size, reference.clone()),
+            View::TimeMachine { path, commit } => {
+                screen::time_machine::create(config, repo, size, path.clone(), commit.clone())
+            }
+        }
+        .and_then(|mut screen| {
+            screen.restore(saved)?;
+            Ok(screen)
+        });
+
+        match screen {
+            Ok(screen) => screens.push(screen),
+            Err(err) => {
+                log::debug!("Stopped restoring screens: {}", err);
+                break;
+            }
+        }
+    }
+
+    screens
+}
+
+/// Paths given on the command line are relative to where gitu was started,
+/// which might be deeper into the worktree.
+fn path_in_workdir(repo: &Repository, path: &Path) -> Res<PathBuf> {
+    let workdir = repo.workdir().ok_or("Blaming requires a worktree")?;
+    let absolute = env::current_dir()?.join(path);
+    let workdir = workdir.canonicalize()?;
+
+    Ok(absolute
+        .canonicalize()
+        .map_err(|err| format!("Couldn't find {}: {}", path.display(), err))?
+        .strip_prefix(&workdir)
+        .map_err(|_| format!("{} is outside the repository", path.display()))?
+        .to_path_buf())
+}
+
+pub(crate) fn stream_into<R: Read + Send + 'static>(
+    mut reader: R,
+    out: Arc<Mutex<String>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = [0; 4096];
+        // A character may be split between reads, the start of it waits for the rest
+        let mut undecoded = vec![];
+        loop {
+            let len = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+
+            undecoded.extend_from_slice(&buf[..len]);
+            out.lock()
+                .unwrap()
+                .push_str(&decode_complete(&mut undecoded));
+        }
+
+        out.lock()
+            .unwrap()
+            .push_str(&String::from_utf8_lossy(&undecoded));
+    })
+}
+
+/// Decodes `bytes` like `String::from_utf8_lossy`, but leaves an incomplete character at the
+/// end in there.
+fn decode_complete(bytes: &mut Vec<u8>) -> String {
+    let mut decoded = String::new();
+    let mut rest = &bytes[..];
+
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                decoded.push_str(valid);
+                rest = &[];
+                break;
+            }
+            Err(err) => {
+                let (valid, after) = rest.split_at(err.valid_up_to());
+                decoded.push_str(std::str::from_utf8(valid).unwrap());
+
+                let Some(invalid) = err.error_len() else {
+                    rest = after;
+                    break;
+                };
+                decoded.push(char::REPLACEMENT_CHARACTER);
+                rest = &after[invalid..];
+            }
+        }
     }
+
+    let complete = bytes.len() - rest.len();
+    bytes.drain(..complete);
+    decoded
 }
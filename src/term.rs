@@ -3,6 +3,7 @@ use crossterm::terminal::disable_raw_mode;
 use crossterm::terminal::enable_raw_mode;
 use crossterm::terminal::EnterAlternateScreen;
 use crossterm::terminal::LeaveAlternateScreen;
+use crossterm::terminal::SetTitle;
 use crossterm::ExecutableCommand;
 use ratatui::backend::Backend;
 use ratatui::backend::CrosstermBackend;
@@ -15,6 +16,7 @@ use std::fmt::Display;
 use std::io;
 use std::io::stderr;
 use std::io::Stderr;
+use std::path::Path;
 
 pub type Term = Terminal<TermBackend>;
 
@@ -30,6 +32,23 @@ pub fn alternate_screen<T, F: Fn() -> Res<T>>(fun: F) -> Res<T> {
     result
 }
 
+/// Terminals keep a stack of titles (xterm's window manipulation 22 and 23), so whatever
+/// title there was before is put back after `fun`, even though it can't be read.
+pub fn saved_title<T, F: Fn() -> Res<T>>(fun: F) -> Res<T> {
+    use std::io::Write;
+    write!(stderr(), "\x1b[22;0t")?;
+    let result = fun();
+    restore_title()?;
+    result
+}
+
+fn restore_title() -> io::Result<()> {
+    use std::io::Write;
+    let mut stderr = stderr();
+    write!(stderr, "\x1b[23;0t")?;
+    stderr.flush()
+}
+
 pub fn raw_mode<T, F: Fn() -> Res<T>>(fun: F) -> Res<T> {
     enable_raw_mode()?;
     let result = fun();
@@ -37,10 +56,104 @@ pub fn raw_mode<T, F: Fn() -> Res<T>>(fun: F) -> Res<T> {
     result
 }
 
+/// Hand the terminal over to another program, such as an editor, for as long as `fun` runs.
+pub fn suspended<T, F: FnOnce() -> Res<T>>(term: &mut Term, fun: F) -> Res<T> {
+    let is_tty = matches!(term.backend(), TermBackend::Crossterm(_));
+
+    if is_tty {
+        stderr().execute(LeaveAlternateScreen)?;
+        disable_raw_mode()?;
+        term.show_cursor()?;
+    }
+
+    let result = fun();
+
+    if is_tty {
+        enable_raw_mode()?;
+        stderr().execute(EnterAlternateScreen)?;
+        term.hide_cursor()?;
+        term.clear()?;
+    }
+
+    result
+}
+
+/// Put `text` on the system clipboard by asking the terminal to, through an OSC 52 escape
+/// sequence. This works over ssh too, as long as the terminal emulator supports it.
+pub fn copy_to_clipboard(term: &mut Term, text: &str) -> Res<()> {
+    if let TermBackend::Crossterm(_) = term.backend() {
+        use std::io::Write;
+        let mut stderr = stderr();
+        write!(stderr, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+        stderr.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Set the title of the terminal, and tell it the directory it's in through OSC 7, so
+/// that multiplexers and tab bars can show where gitu is, and open new tabs there.
+pub fn set_title(term: &mut Term, title: &str, dir: &Path) -> Res<()> {
+    if let TermBackend::Crossterm(_) = term.backend() {
+        use std::io::Write;
+        let mut stderr = stderr();
+        stderr.execute(SetTitle(title))?;
+        write!(stderr, "\x1b]7;{}\x07", file_url(&hostname(), dir))?;
+        stderr.flush()?;
+    }
+
+    Ok(())
+}
+
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|hostname| hostname.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn file_url(hostname: &str, dir: &Path) -> String {
+    let mut url = format!("file://{}", hostname);
+    for byte in dir.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                url.push(*byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+pub(crate) fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
 pub fn cleanup_alternate_screen() {
     print_err(stderr().execute(LeaveAlternateScreen));
 }
 
+pub fn cleanup_title() {
+    print_err(restore_title());
+}
+
 pub fn cleanup_raw_mode() {
     print_err(disable_raw_mode());
 }
@@ -129,3 +242,26 @@ impl Backend for TermBackend {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{base64, file_url};
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn file_url_is_percent_encoded() {
+        assert_eq!(
+            file_url("host", "/home/me/my repo".as_ref()),
+            "file://host/home/me/my%20repo"
+        );
+        assert_eq!(file_url("", "/tmp/ä".as_ref()), "file:///tmp/%C3%A4");
+    }
+}
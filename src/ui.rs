@@ -23,7 +23,7 @@ enum Popup<'a> {
 
 pub(crate) fn ui(frame: &mut Frame, state: &mut State) {
     let (popup_line_count, popup): (usize, Popup) = if let Some(ref error) = state.error_buffer {
-        let text = error.0.clone().red().bold();
+        let text = Line::styled(error.0.clone(), &state.config.style.error);
         (1, command_popup(text.into()))
     } else if let Some(ref cmd) = state.cmd_meta_buffer {
         let text = format_command(&state.config, cmd);
@@ -32,6 +32,7 @@ pub(crate) fn ui(frame: &mut Frame, state: &mut State) {
         format_keybinds_menu(
             &state.config,
             &state.pending_submenu_op,
+            &state.pending_args,
             state.screen().get_selected_item(),
         )
     } else {
@@ -56,6 +57,40 @@ pub(crate) fn ui(frame: &mut Frame, state: &mut State) {
 
     frame.render_widget(state.screen(), layout[0]);
 
+    let mut notice = if state.recording.is_some() {
+        Line::styled("● recording macro", &state.config.style.error)
+    } else if state.new_upstream_commits {
+        Line::styled("↓ new upstream commits", Style::new().dim())
+    } else {
+        Line::default()
+    };
+
+    // Like `1 [2] 3`, the current tab in brackets
+    if state.tab_count() > 1 {
+        if notice.width() > 0 {
+            notice.spans.push(Span::raw(" "));
+        }
+        notice.spans.extend((0..state.tab_count()).map(|tab| {
+            if tab == state.tab() {
+                Span::styled(format!("[{}]", tab + 1), &state.config.style.hotkey)
+            } else {
+                Span::raw(format!(" {} ", tab + 1))
+            }
+        }));
+    }
+
+    if notice.width() > 0 {
+        let width = (notice.width() as u16).min(layout[0].width);
+        let area = Rect {
+            x: layout[0].right() - width,
+            width,
+            height: 1,
+            ..layout[0]
+        };
+        frame.render_widget(Clear, area);
+        frame.render_widget(notice, area);
+    }
+
     match popup {
         Popup::None => (),
         Popup::Paragraph(paragraph) => frame.render_widget(paragraph, layout[1]),
@@ -69,6 +104,10 @@ pub(crate) fn ui(frame: &mut Frame, state: &mut State) {
         frame.set_cursor(cx, cy);
         state.prompt.data = Some(prompt_data);
     }
+
+    if state.config.image_protocol.is_some() {
+        state.images = state.screen().image_placements(layout[0]);
+    }
 }
 
 fn format_command<'a>(config: &Config, cmd: &'a CmdMetaBuffer) -> Text<'a> {
@@ -86,9 +125,18 @@ fn format_command<'a>(config: &Config, cmd: &'a CmdMetaBuffer) -> Text<'a> {
     .into()
 }
 
+fn op_label(op: &Op, pending_args: &[&str]) -> String {
+    match op {
+        Op::ToggleArg(arg) if pending_args.contains(arg) => format!("[x] {}", arg),
+        Op::ToggleArg(arg) => format!("[ ] {}", arg),
+        _ => op.implementation().to_string(),
+    }
+}
+
 fn format_keybinds_menu<'b>(
     config: &Config,
     pending: &'b SubmenuOp,
+    pending_args: &[&str],
     item: &'b Item,
 ) -> (usize, Popup<'b>) {
     let style = &config.style;
@@ -99,11 +147,11 @@ fn format_keybinds_menu<'b>(
 
     let mut pending_binds_column = vec![];
     pending_binds_column.push(Line::styled(format!("{}", pending), &style.command));
-    for (op, binds) in non_target_binds
+    for (label, binds) in non_target_binds
         .iter()
-        .group_by(|bind| bind.op)
+        .filter(|bind| !matches!(bind.op, Op::Submenu(_)))
+        .group_by(|bind| op_label(&bind.op, pending_args))
         .into_iter()
-        .filter(|(op, _binds)| !matches!(op, Op::Submenu(_)))
     {
         pending_binds_column.push(Line::from(vec![
             Span::styled(
@@ -113,7 +161,7 @@ fn format_keybinds_menu<'b>(
                     .join(" "),
                 &style.hotkey,
             ),
-            Span::styled(format!(" {}", op.implementation()), Style::new()),
+            Span::styled(format!(" {}", label), Style::new()),
         ]));
     }
 
@@ -188,11 +236,11 @@ fn format_keybinds_menu<'b>(
     )
 }
 
-fn command_popup(text: Text<'_>) -> Popup {
+fn command_popup(text: Text<'_>) -> Popup<'_> {
     Popup::Paragraph(Paragraph::new(text).block(popup_block()))
 }
 
-fn popup_block() -> Block<'static> {
+pub(crate) fn popup_block() -> Block<'static> {
     Block::new()
         .borders(Borders::TOP)
         .border_style(Style::new().dim())
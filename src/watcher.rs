@@ -0,0 +1,531 @@
+use crate::git::RepoDirs;
+use git2::Repository;
+use std::{
+    cell::Cell,
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often the watcher checks whether it should stop. Changes are reported once a tick
+/// passes without any, so that e.g. a rebase rewriting lots of files causes a single refresh.
+const TICK: Duration = Duration::from_millis(200);
+
+/// How often the worktree is scanned when falling back to polling.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Directories inside the git dir that don't affect what gitu shows, but would be costly to watch.
+const IGNORED_GIT_DIRS: [&str; 4] = ["objects", "logs", "modules", "lfs"];
+
+/// The index ends with a checksum of what's in it, this long at most (with SHA-256).
+const INDEX_CHECKSUM_LEN: usize = 32;
+
+/// Whether there are native file events to be had on this OS.
+const HAS_FILE_EVENTS: bool = cfg!(target_os = "linux");
+
+/// Notices when files in the worktree or refs in the git directory change outside of gitu,
+/// from native file events where there are any. Scanning file metadata periodically instead
+/// only happens when `poll` is set, as it's costly in big repositories. When it's not set,
+/// that's done if the OS has no file events.
+/// The background thread stops once this is dropped.
+pub(crate) struct FileWatcher {
+    receiver: Receiver<()>,
+    stop: Arc<AtomicBool>,
+}
+
+impl FileWatcher {
+    pub(crate) fn new(workdir: PathBuf, git_dir: PathBuf, poll: Option<bool>) -> Self {
+        let poll = poll.unwrap_or(!HAS_FILE_EVENTS);
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+
+        thread::spawn(move || {
+            // A repository of our own, git2 repositories can't be shared between threads
+            let repo = RepoDirs {
+                git_dir: git_dir.clone(),
+                workdir: Some(workdir.clone()),
+            }
+            .open()
+            .ok();
+            let paths = Paths {
+                index_checksum: Cell::new(index_checksum(&git_dir)),
+                repo,
+                workdir,
+                git_dir,
+            };
+
+            let Err(err) = watch_events(&paths, &sender, &stopped) else {
+                return;
+            };
+
+            if poll {
+                log::debug!("No file events ({}), polling for changes instead", err);
+                poll_changes(&paths, &sender, &stopped);
+            } else {
+                log::warn!(
+                    "Not refreshing on file changes, there are no file events: {}",
+                    err
+                );
+            }
+        });
+
+        Self { receiver, stop }
+    }
+
+    pub(crate) fn has_changes(&self) -> bool {
+        self.receiver.try_iter().count() > 0
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Tells the paths that matter apart from those that don't.
+struct Paths {
+    repo: Option<Repository>,
+    workdir: PathBuf,
+    git_dir: PathBuf,
+    /// Of the index as it was when last looked at
+    index_checksum: Cell<Option<[u8; INDEX_CHECKSUM_LEN]>>,
+}
+
+impl Paths {
+    fn is_index(&self, path: &Path) -> bool {
+        path == self.git_dir.join("index")
+    }
+
+    /// Whether what's staged may have changed since this was last asked, not just the file
+    /// stats kept in the index.
+    fn index_changed(&self) -> bool {
+        let checksum = index_checksum(&self.git_dir);
+        checksum != self.index_checksum.replace(checksum)
+    }
+
+    fn skip(&self, path: &Path) -> bool {
+        if let Ok(in_git_dir) = path.strip_prefix(&self.git_dir) {
+            return skip_in_git_dir(in_git_dir);
+        }
+
+        let Ok(relative) = path.strip_prefix(&self.workdir) else {
+            return true;
+        };
+        if relative
+            .components()
+            .any(|component| component.as_os_str() == ".git")
+        {
+            return true;
+        }
+
+        self.repo
+            .as_ref()
+            .is_some_and(|repo| repo.is_path_ignored(relative).unwrap_or(false))
+    }
+}
+
+/// gitu refreshes by itself after writing its own files. The index is looked at apart from
+/// the rest, as it's also rewritten by any `git status` just to update the file stats kept in it.
+fn skip_in_git_dir(relative: &Path) -> bool {
+    let Some(Component::Normal(first)) = relative.components().next() else {
+        return false;
+    };
+    let first = first.to_string_lossy();
+
+    IGNORED_GIT_DIRS.contains(&first.as_ref())
+        || first == "index"
+        || first == "index.lock"
+        || first.starts_with("gitu-")
+        || first.starts_with("GITU_")
+}
+
+fn index_checksum(git_dir: &Path) -> Option<[u8; INDEX_CHECKSUM_LEN]> {
+    let mut file = File::open(git_dir.join("index")).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(
+        len.saturating_sub(INDEX_CHECKSUM_LEN as u64),
+    ))
+    .ok()?;
+
+    let mut checksum = [0; INDEX_CHECKSUM_LEN];
+    file.read_exact(&mut checksum).ok()?;
+    Some(checksum)
+}
+
+/// Sends a change once a tick has passed without any. Stops when told to or when nobody is
+/// listening anymore.
+struct Debounce<'a> {
+    sender: &'a Sender<()>,
+    pending: bool,
+}
+
+impl Debounce<'_> {
+    /// Whether to keep going
+    fn tick(&mut self, changed: bool) -> bool {
+        if changed {
+            self.pending = true;
+        } else if self.pending {
+            self.pending = false;
+            return self.sender.send(()).is_ok();
+        }
+
+        true
+    }
+}
+
+/// Returns an error if there are no file events to be had, once it's stopped otherwise.
+#[cfg(target_os = "linux")]
+fn watch_events(paths: &Paths, sender: &Sender<()>, stop: &AtomicBool) -> io::Result<()> {
+    let mut inotify = inotify::Inotify::new()?;
+    watch_dir(&mut inotify, paths, &paths.workdir)?;
+    if !paths.git_dir.starts_with(&paths.workdir) {
+        watch_dir(&mut inotify, paths, &paths.git_dir)?;
+    }
+
+    let mut debounce = Debounce {
+        sender,
+        pending: false,
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        let mut changed = false;
+        for event in inotify.wait(TICK)? {
+            if event.overflowed {
+                changed = true;
+                continue;
+            }
+            if paths.is_index(&event.path) {
+                changed |= paths.index_changed();
+                continue;
+            }
+            if paths.skip(&event.path) {
+                continue;
+            }
+
+            changed = true;
+            if event.new_dir {
+                // Files may have been created in it already, before it's watched
+                let _ = watch_dir(&mut inotify, paths, &event.path);
+            }
+        }
+
+        if !debounce.tick(changed) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn watch_events(_paths: &Paths, _sender: &Sender<()>, _stop: &AtomicBool) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Watches `dir` and what's in it, inotify doesn't watch subdirectories by itself.
+#[cfg(target_os = "linux")]
+fn watch_dir(inotify: &mut inotify::Inotify, paths: &Paths, dir: &Path) -> io::Result<()> {
+    inotify.add(dir)?;
+
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|kind| kind.is_dir()) && !paths.skip(&path) {
+            watch_dir(inotify, paths, &path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn poll_changes(paths: &Paths, sender: &Sender<()>, stop: &AtomicBool) {
+    let mut last = fingerprint(paths);
+    let mut debounce = Debounce {
+        sender,
+        pending: false,
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(POLL_INTERVAL);
+
+        let current = fingerprint(paths);
+        let changed = current != last;
+        last = current;
+
+        if !debounce.tick(changed) {
+            break;
+        }
+    }
+}
+
+fn fingerprint(paths: &Paths) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    hash_dir(&mut hasher, paths, &paths.workdir);
+    if !paths.git_dir.starts_with(&paths.workdir) {
+        hash_dir(&mut hasher, paths, &paths.git_dir);
+    }
+    index_checksum(&paths.git_dir).hash(&mut hasher);
+
+    hasher.finish()
+}
+
+fn hash_dir(hasher: &mut DefaultHasher, paths: &Paths, dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut entries = entries.filter_map(Result::ok).collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if paths.skip(&path) {
+            continue;
+        }
+
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        path.hash(hasher);
+
+        // Directory metadata changes with ignored files within, so only look at their contents
+        if metadata.is_dir() {
+            hash_dir(hasher, paths, &path);
+        } else {
+            metadata.len().hash(hasher);
+            metadata.modified().ok().hash(hasher);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::{
+        collections::HashMap,
+        ffi::{CString, OsStr},
+        io, mem,
+        os::unix::ffi::OsStrExt,
+        path::{Path, PathBuf},
+        ptr,
+        time::Duration,
+    };
+
+    const MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MODIFY
+        | libc::IN_ATTRIB
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO;
+
+    pub(super) struct Event {
+        pub(super) path: PathBuf,
+        /// A directory was created or moved here, which isn't watched yet
+        pub(super) new_dir: bool,
+        /// Events were lost, anything could have changed
+        pub(super) overflowed: bool,
+    }
+
+    pub(super) struct Inotify {
+        fd: libc::c_int,
+        dirs: HashMap<libc::c_int, PathBuf>,
+    }
+
+    impl Inotify {
+        pub(super) fn new() -> io::Result<Self> {
+            // SAFETY: takes no pointers, the descriptor is closed on drop
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self {
+                fd,
+                dirs: HashMap::new(),
+            })
+        }
+
+        pub(super) fn add(&mut self, dir: &Path) -> io::Result<()> {
+            let path = CString::new(dir.as_os_str().as_bytes())?;
+            // SAFETY: `path` is a valid C string that outlives the call
+            let wd = unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), MASK) };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            self.dirs.insert(wd, dir.to_path_buf());
+            Ok(())
+        }
+
+        /// Events that happened, waiting up to `timeout` for the first one.
+        pub(super) fn wait(&mut self, timeout: Duration) -> io::Result<Vec<Event>> {
+            let mut poll = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: `poll` is a single valid pollfd
+            let ready = unsafe { libc::poll(&mut poll, 1, timeout.as_millis() as libc::c_int) };
+            if ready < 0 {
+                let err = io::Error::last_os_error();
+                return match err.kind() {
+                    io::ErrorKind::Interrupted => Ok(vec![]),
+                    _ => Err(err),
+                };
+            }
+
+            let mut events = vec![];
+            let mut buffer = [0u8; 4096];
+            loop {
+                // SAFETY: reads at most `buffer.len()` bytes into `buffer`
+                let read = unsafe { libc::read(self.fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+                if read <= 0 {
+                    // Nothing more to read, the descriptor doesn't block
+                    break;
+                }
+
+                let mut offset = 0;
+                while offset + mem::size_of::<libc::inotify_event>() <= read as usize {
+                    // SAFETY: the kernel wrote a whole event header at `offset`, which
+                    // needn't be aligned
+                    let event: libc::inotify_event =
+                        unsafe { ptr::read_unaligned(buffer.as_ptr().add(offset).cast()) };
+                    let name_start = offset + mem::size_of::<libc::inotify_event>();
+                    let name = &buffer[name_start..name_start + event.len as usize];
+                    offset = name_start + event.len as usize;
+
+                    if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                        events.push(Event {
+                            path: PathBuf::new(),
+                            new_dir: false,
+                            overflowed: true,
+                        });
+                        continue;
+                    }
+                    if event.mask & libc::IN_IGNORED != 0 {
+                        // The directory is gone, and so is its watch
+                        self.dirs.remove(&event.wd);
+                        continue;
+                    }
+                    let Some(dir) = self.dirs.get(&event.wd) else {
+                        continue;
+                    };
+
+                    // Names are padded with NULs
+                    let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+                    events.push(Event {
+                        path: dir.join(OsStr::from_bytes(name)),
+                        new_dir: event.mask & libc::IN_ISDIR != 0
+                            && event.mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0,
+                        overflowed: false,
+                    });
+                }
+            }
+
+            Ok(events)
+        }
+    }
+
+    impl Drop for Inotify {
+        fn drop(&mut self) {
+            // SAFETY: the descriptor is owned by this and not used after
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fingerprint, index_checksum, FileWatcher, Paths};
+    use crate::git::test_repo::TestRepo;
+    use std::{cell::Cell, fs, thread, time::Duration};
+
+    fn paths(test: TestRepo) -> (Paths, temp_dir::TempDir) {
+        let git_dir = test.dir.path().join(".git");
+        let paths = Paths {
+            workdir: test.dir.path().to_path_buf(),
+            index_checksum: Cell::new(index_checksum(&git_dir)),
+            git_dir,
+            repo: Some(test.repo),
+        };
+        (paths, test.dir)
+    }
+
+    #[test]
+    fn fingerprint_changes_with_files_but_not_ignored_ones() {
+        let (paths, dir) = paths(TestRepo::new());
+        fs::write(dir.child(".gitignore"), "ignored\n").unwrap();
+
+        let before = fingerprint(&paths);
+        fs::write(dir.child("ignored"), "hello").unwrap();
+        assert_eq!(before, fingerprint(&paths));
+
+        fs::write(dir.child("tracked"), "hello").unwrap();
+        assert_ne!(before, fingerprint(&paths));
+    }
+
+    #[test]
+    fn gitus_own_files_and_the_index_are_skipped() {
+        let (paths, dir) = paths(TestRepo::new());
+        let git_dir = dir.path().join(".git");
+
+        assert!(paths.skip(&git_dir.join("index")));
+        assert!(paths.skip(&git_dir.join("gitu-trash/2024-03-01/file")));
+        assert!(paths.skip(&git_dir.join("GITU_COMMIT_DRAFT")));
+        assert!(!paths.skip(&git_dir.join("refs/heads/main")));
+        assert!(!paths.skip(&git_dir.join("HEAD")));
+    }
+
+    #[test]
+    fn index_changes_are_told_apart_from_rewrites() {
+        let test = TestRepo::new();
+        test.stage("file", "hello");
+        let (paths, _dir) = paths(test);
+        let mut index = paths.repo.as_ref().unwrap().index().unwrap();
+
+        index.write().unwrap();
+        assert!(!paths.index_changed());
+
+        index.remove_path("file".as_ref()).unwrap();
+        index.write().unwrap();
+        assert!(paths.index_changed());
+        assert!(!paths.index_changed());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn file_events_are_reported() {
+        let TestRepo { dir, .. } = TestRepo::new();
+        let watcher = FileWatcher::new(
+            dir.path().to_path_buf(),
+            dir.path().join(".git"),
+            Some(false),
+        );
+        let changed = || {
+            (0..50).any(|_| {
+                thread::sleep(Duration::from_millis(100));
+                watcher.has_changes()
+            })
+        };
+        // Let it start watching
+        thread::sleep(Duration::from_millis(100));
+
+        fs::create_dir(dir.child("new-dir")).unwrap();
+        assert!(changed());
+
+        // Directories created since are watched too
+        fs::write(dir.child("new-dir/file"), "hello").unwrap();
+        assert!(changed());
+    }
+}
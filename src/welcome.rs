@@ -0,0 +1,389 @@
+use crate::{config::Config, git, state, term::Term, ui, Res, EVENT_POLL_INTERVAL};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use git2::Repository;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+use std::{
+    fs,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread::JoinHandle,
+};
+use tui_prompts::{State as _, TextPrompt, TextState};
+
+/// What's shown when gitu is started outside of any repository, offering to create one.
+pub(crate) struct Welcome {
+    dir: PathBuf,
+    mode: Mode,
+    error: Option<String>,
+}
+
+enum Mode {
+    Menu,
+    CloneUrl(TextState<'static>),
+    Cloning {
+        url: String,
+        target: PathBuf,
+        output: Arc<Mutex<String>>,
+        child: Child,
+        reader: Option<JoinHandle<()>>,
+    },
+}
+
+pub(crate) enum Step {
+    Continue,
+    Open(Repository),
+    Quit,
+}
+
+/// Shows the welcome screen until there's a repository to open in `dir`, or the user quits.
+pub(crate) fn run(term: &mut Term, config: &Config, dir: PathBuf) -> Res<Option<Repository>> {
+    let mut welcome = Welcome::new(dir);
+
+    loop {
+        let step = match welcome.poll()? {
+            Step::Continue => {
+                term.draw(|frame| welcome.draw(frame, config))?;
+
+                match event::poll(EVENT_POLL_INTERVAL)?
+                    .then(event::read)
+                    .transpose()?
+                {
+                    Some(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                        welcome.handle_key(key)?
+                    }
+                    _ => Step::Continue,
+                }
+            }
+            step => step,
+        };
+
+        match step {
+            Step::Continue => (),
+            Step::Open(repo) => return Ok(Some(repo)),
+            Step::Quit => return Ok(None),
+        }
+    }
+}
+
+impl Welcome {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            mode: Mode::Menu,
+            error: None,
+        }
+    }
+
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> Res<Step> {
+        match &mut self.mode {
+            Mode::Menu => {
+                self.error = None;
+                match key.code {
+                    KeyCode::Char('i') => return self.init(),
+                    KeyCode::Char('c') => {
+                        let mut prompt = TextState::new();
+                        prompt.focus();
+                        self.mode = Mode::CloneUrl(prompt);
+                    }
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(Step::Quit),
+                    _ => (),
+                }
+            }
+            Mode::CloneUrl(prompt) => {
+                prompt.handle_key_event(key);
+
+                if prompt.status().is_aborted() {
+                    self.mode = Mode::Menu;
+                } else if prompt.status().is_done() {
+                    let url = prompt.value().trim().to_string();
+                    if url.is_empty() {
+                        self.mode = Mode::Menu;
+                    } else {
+                        self.clone(url);
+                    }
+                }
+            }
+            Mode::Cloning { child, target, .. } => {
+                if let KeyCode::Char('q') | KeyCode::Esc = key.code {
+                    // What it got so far is of no use
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    let _ = fs::remove_dir_all(target);
+
+                    self.error = Some("Clone cancelled".to_string());
+                    self.mode = Mode::Menu;
+                }
+            }
+        }
+
+        Ok(Step::Continue)
+    }
+
+    /// Checks on a clone in progress.
+    pub(crate) fn poll(&mut self) -> Res<Step> {
+        let Mode::Cloning {
+            target,
+            output,
+            child,
+            reader,
+            ..
+        } = &mut self.mode
+        else {
+            return Ok(Step::Continue);
+        };
+
+        let status = match child.try_wait() {
+            Ok(None) => return Ok(Step::Continue),
+            Ok(Some(status)) => Ok(status),
+            Err(err) => Err(err.to_string()),
+        };
+        // Everything it said is read once it's gone
+        if let Some(reader) = reader.take() {
+            let _ = reader.join();
+        }
+
+        let error = match status {
+            Ok(status) if status.success() => return Ok(Step::Open(Repository::open(target)?)),
+            Ok(_) => last_lines(&output.lock().unwrap(), 1)
+                .pop()
+                .unwrap_or("Clone failed")
+                .to_string(),
+            Err(error) => error,
+        };
+
+        self.error = Some(error);
+        self.mode = Mode::Menu;
+        Ok(Step::Continue)
+    }
+
+    fn init(&mut self) -> Res<Step> {
+        let output = Command::new("git")
+            .arg("init")
+            .current_dir(&self.dir)
+            .output()?;
+
+        if !output.status.success() {
+            self.error = Some(String::from_utf8_lossy(&output.stderr).trim().to_string());
+            return Ok(Step::Continue);
+        }
+
+        Ok(Step::Open(Repository::open(&self.dir)?))
+    }
+
+    fn clone(&mut self, url: String) {
+        let target = self.dir.join(humanish_name(&url));
+        let output = Arc::new(Mutex::new(String::new()));
+
+        let mut cmd = Command::new("git");
+        cmd.args(["clone", "--progress", &url])
+            .arg(&target)
+            .current_dir(&self.dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        // The terminal is taken, there's nowhere to ask for credentials
+        git::never_prompt(&mut cmd, git2::Config::open_default());
+
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                self.error = Some(err.to_string());
+                self.mode = Mode::Menu;
+                return;
+            }
+        };
+        let reader = state::stream_into(child.stderr.take().unwrap(), Arc::clone(&output));
+
+        self.mode = Mode::Cloning {
+            url,
+            target,
+            output,
+            child,
+            reader: Some(reader),
+        };
+    }
+
+    fn draw(&mut self, frame: &mut Frame, config: &Config) {
+        let style = &config.style;
+        let prompting = matches!(self.mode, Mode::CloneUrl(_));
+
+        let layout = Layout::new(
+            Direction::Vertical,
+            [
+                Constraint::Min(1),
+                Constraint::Length(if prompting { 2 } else { 0 }),
+            ],
+        )
+        .split(frame.size());
+
+        let mut lines = vec![
+            Line::styled(
+                format!("Not inside a git repository: {}", self.dir.display()),
+                &style.section_header,
+            ),
+            Line::raw(""),
+        ];
+
+        if let Mode::Cloning { url, output, .. } = &self.mode {
+            lines.push(Line::styled(format!("$ git clone {}", url), &style.command));
+            lines.push(Line::from(vec![
+                Span::styled("q", &style.hotkey),
+                Span::raw(" Cancel"),
+            ]));
+
+            let output = output.lock().unwrap();
+            let room = (layout[0].height as usize).saturating_sub(lines.len());
+            lines.extend(
+                last_lines(&output, room)
+                    .into_iter()
+                    .map(|line| Line::raw(line.to_string())),
+            );
+        } else {
+            for (key, description) in [
+                ("i", "Initialize a repository here"),
+                ("c", "Clone a repository into here"),
+                ("q", "Quit"),
+            ] {
+                lines.push(Line::from(vec![
+                    Span::styled(key, &style.hotkey),
+                    Span::raw(format!(" {}", description)),
+                ]));
+            }
+        }
+
+        if let Some(error) = &self.error {
+            lines.push(Line::raw(""));
+            lines.push(Line::styled(error.clone(), &style.error));
+        }
+
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), layout[0]);
+
+        if let Mode::CloneUrl(prompt) = &mut self.mode {
+            let widget = TextPrompt::from("Clone from:").with_block(ui::popup_block());
+            frame.render_stateful_widget(widget, layout[1], prompt);
+            let (x, y) = prompt.cursor();
+            frame.set_cursor(x, y);
+        }
+    }
+}
+
+/// The directory `git clone` would create for `url`.
+fn humanish_name(url: &str) -> String {
+    let path = url.trim_end_matches('/').trim_end_matches("/.git");
+    let name = path
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or(path)
+        .trim_end_matches(".git");
+
+    if name.is_empty() {
+        "repository".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// The last `count` lines of progress output, where `\r` redraws the current line.
+fn last_lines(output: &str, count: usize) -> Vec<&str> {
+    let lines = output
+        .lines()
+        .filter_map(|line| line.rsplit('\r').find(|part| !part.trim().is_empty()))
+        .collect::<Vec<_>>();
+
+    lines[lines.len().saturating_sub(count)..].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{humanish_name, last_lines, Mode, Step, Welcome};
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::{path::Path, process::Command, thread, time::Duration};
+    use temp_dir::TempDir;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::empty())
+    }
+
+    #[test]
+    fn humanish_names() {
+        assert_eq!(humanish_name("https://github.com/altsem/gitu.git"), "gitu");
+        assert_eq!(humanish_name("git@github.com:altsem/gitu"), "gitu");
+        assert_eq!(humanish_name("/srv/repos/project/.git/"), "project");
+        assert_eq!(humanish_name("host:repo.git"), "repo");
+    }
+
+    #[test]
+    fn progress_shows_the_latest_redraw() {
+        let output = "Cloning into 'x'...\nReceiving objects:  50%\rReceiving objects: 100%\r\n";
+        assert_eq!(
+            last_lines(output, 2),
+            ["Cloning into 'x'...", "Receiving objects: 100%"]
+        );
+    }
+
+    #[test]
+    fn init_opens_the_new_repository() {
+        let dir = TempDir::new().unwrap();
+        let mut welcome = Welcome::new(dir.path().to_path_buf());
+
+        let Step::Open(repo) = welcome.handle_key(key(KeyCode::Char('i'))).unwrap() else {
+            panic!("Expected a repository");
+        };
+        assert_eq!(
+            repo.workdir().unwrap().canonicalize().unwrap(),
+            dir.path().canonicalize().unwrap()
+        );
+    }
+
+    fn start_clone(welcome: &mut Welcome, remote: &Path) {
+        let status = Command::new("git")
+            .args(["init", "--bare", "origin.git"])
+            .current_dir(remote)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success());
+
+        welcome.handle_key(key(KeyCode::Char('c'))).unwrap();
+        for c in remote.join("origin.git").to_str().unwrap().chars() {
+            welcome.handle_key(key(KeyCode::Char(c))).unwrap();
+        }
+        welcome.handle_key(key(KeyCode::Enter)).unwrap();
+    }
+
+    #[test]
+    fn clone_opens_the_clone() {
+        let remote = TempDir::new().unwrap();
+        let dir = TempDir::new().unwrap();
+        let mut welcome = Welcome::new(dir.path().to_path_buf());
+        start_clone(&mut welcome, remote.path());
+
+        for _ in 0..100 {
+            if let Step::Open(repo) = welcome.poll().unwrap() {
+                assert!(repo.workdir().unwrap().ends_with("origin"));
+                return;
+            }
+            assert!(welcome.error.is_none(), "{:?}", welcome.error);
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("Clone didn't finish");
+    }
+    #[test]
+    fn clone_can_be_cancelled() {
+        let remote = TempDir::new().unwrap();
+        let dir = TempDir::new().unwrap();
+        let mut welcome = Welcome::new(dir.path().to_path_buf());
+        start_clone(&mut welcome, remote.path());
+
+        welcome.handle_key(key(KeyCode::Char('q'))).unwrap();
+        assert!(matches!(welcome.mode, Mode::Menu));
+        assert!(!dir.child("origin").exists());
+        assert!(matches!(welcome.poll().unwrap(), Step::Continue));
+    }
+}
@@ -2,7 +2,7 @@ use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use git2::Repository;
 use gitu::{
     cli::Args,
-    config,
+    config::{self, Config},
     state::State,
     term::{Term, TermBackend},
 };
@@ -72,13 +72,32 @@ impl TestContext {
     }
 
     pub fn init_state_at_path(&mut self, path: PathBuf) -> State {
-        let mut state = State::create(
-            Repository::open(path).unwrap(),
-            self.size,
-            &Args::default(),
-            config::init_test_config().unwrap(),
-        )
-        .unwrap();
+        self.init_state_with(path, &Args::default(), |_config| ())
+    }
+
+    pub fn init_state_with_config<F: FnOnce(&mut Config)>(&mut self, configure: F) -> State {
+        self.init_state_with(self.dir.path().to_path_buf(), &Args::default(), configure)
+    }
+
+    pub fn init_state_with_args(&mut self, args: Args) -> State {
+        self.init_state_with(self.dir.path().to_path_buf(), &args, |_config| ())
+    }
+
+    fn init_state_with<F: FnOnce(&mut Config)>(
+        &mut self,
+        path: PathBuf,
+        args: &Args,
+        configure: F,
+    ) -> State {
+        let mut config = config::init_test_config().unwrap();
+        // Snapshots rely on commands having finished before the next key is handled
+        config.general.background_jobs = false;
+        config.general.refresh_on_file_change = false;
+        configure(&mut config);
+        config.adapt_style();
+
+        let mut state =
+            State::create(Repository::open(path).unwrap(), self.size, args, config).unwrap();
 
         state.update(&mut self.term, &[]).unwrap();
         state
@@ -88,8 +107,32 @@ impl TestContext {
         let TermBackend::Test(test_backend) = self.term.backend() else {
             unreachable!();
         };
-        let mut debug_output = format!("{:#?}", test_backend.buffer());
+        self.redact(format!("{:#?}", test_backend.buffer()))
+    }
+
+    /// Just the text on the terminal, without styles, with temporary directories shown
+    /// as `$TMP`. For screens showing paths, as how long those are varies.
+    pub fn redact_text(&self) -> String {
+        let TermBackend::Test(test_backend) = self.term.backend() else {
+            unreachable!();
+        };
+        let buffer = test_backend.buffer();
+        let mut text = String::new();
+        for y in 0..buffer.area.height {
+            let line = (0..buffer.area.width)
+                .map(|x| buffer.get(x, y).symbol())
+                .collect::<String>();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+
+        for dir in [&self.dir, &self.remote_dir] {
+            text = text.replace(dir.path().to_str().unwrap(), "$TMP");
+        }
+        self.redact(text)
+    }
 
+    fn redact(&self, mut debug_output: String) -> String {
         [&self.dir, &self.remote_dir]
             .iter()
             .flat_map(|dir| Repository::open(dir.path()).ok())
@@ -1,7 +1,12 @@
 use crate::helpers::{clone_and_commit, commit, ctrl, key, key_code, run, TestContext};
+use clap::Parser;
 use crossterm::event::KeyCode;
+use gitu::{
+    cli::{Args, Commands},
+    config::UntrackedFiles,
+};
 use itertools::Itertools;
-use std::fs;
+use std::{fs, iter};
 
 mod helpers;
 
@@ -39,6 +44,43 @@ fn new_file() {
     insta::assert_snapshot!(ctx.redact_buffer());
 }
 
+#[test]
+fn untracked_files_not_scanned() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    run(ctx.dir.path(), &["touch", "new-file"]);
+
+    let mut state = ctx.init_state_with_config(|config| {
+        config.general.untracked_files = Some(UntrackedFiles::No);
+    });
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('G')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn status_loaded_in_background() {
+    let mut ctx = TestContext::setup_clone(80, 10);
+    commit(ctx.dir.path(), "testfile", "testing\n");
+    fs::write(ctx.dir.child("testfile"), "changed\n").unwrap();
+
+    let mut state = ctx.init_state_with_config(|config| {
+        config.general.background_jobs = true;
+    });
+
+    let spinner = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    for _ in 0..500 {
+        if !ctx.redact_buffer().contains(spinner) {
+            break;
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        state.update(&mut ctx.term, &[]).unwrap();
+    }
+
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
 #[test]
 fn unstaged_changes() {
     let mut ctx = TestContext::setup_init(80, 20);
@@ -47,68 +89,1002 @@ fn unstaged_changes() {
 
     let mut state = ctx.init_state();
     state
-        .update(&mut ctx.term, &[key('j'), key('j'), key_code(KeyCode::Tab)])
+        .update(&mut ctx.term, &[key('j'), key('j'), key_code(KeyCode::Tab)])
+        .unwrap();
+
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn binary_file() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    fs::write(ctx.dir.child("image.png"), b"\x89PNG\r\n\x1a\n\0before").unwrap();
+    run(ctx.dir.path(), &["git", "add", "image.png"]);
+    run(ctx.dir.path(), &["git", "commit", "-m", "add image.png"]);
+    fs::write(ctx.dir.child("image.png"), b"\x89PNG\r\n\x1a\n\0after").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key_code(KeyCode::Tab)])
+        .unwrap();
+
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn large_hunk() {
+    let mut ctx = TestContext::setup_init(80, 12);
+    commit(ctx.dir.path(), "large", "");
+    let content = (0..3000)
+        .map(|i| format!("line {}\n", i))
+        .collect::<String>();
+    fs::write(ctx.dir.child("large"), content).unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key_code(KeyCode::Tab)])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('j'), key_code(KeyCode::Tab)])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn accessible_markers() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    commit(ctx.dir.path(), "modified", "before\n");
+    fs::write(ctx.dir.child("modified"), "after\n").unwrap();
+    run(ctx.dir.path(), &["touch", "staged", "untracked"]);
+    run(ctx.dir.path(), &["git", "add", "staged"]);
+
+    ctx.init_state_with_config(|config| config.general.accessible = true);
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn monochrome() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    commit(ctx.dir.path(), "modified", "before\n");
+    fs::write(ctx.dir.child("modified"), "after\n").unwrap();
+
+    let mut state = ctx.init_state_with_config(|config| config.style.monochrome = true);
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key_code(KeyCode::Tab)])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn replay_macro() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    run(ctx.dir.path(), &["touch", "a", "b", "c"]);
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('Q'), key('s')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('Q'), key('@')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn tabs() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "testing\n");
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('g'), key('n'), key('l'), key('l')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('g'), key('1')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('g')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn staged_file() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    run(ctx.dir.path(), &["touch", "new-file"]);
+    run(ctx.dir.path(), &["git", "add", "new-file"]);
+
+    ctx.init_state();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn first_commit() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    run(ctx.dir.path(), &["touch", "new-file"]);
+    run(ctx.dir.path(), &["git", "config", "core.editor", "true"]);
+    let hook = ctx.dir.child(".git/hooks/prepare-commit-msg");
+    fs::write(&hook, "#!/bin/sh\necho first > \"$1\"\n").unwrap();
+    run(ctx.dir.path(), &["chmod", "+x", hook.to_str().unwrap()]);
+
+    let mut state = ctx.init_state();
+    // Unstaging can't restore from HEAD when there is none
+    state
+        .update(&mut ctx.term, &[key('j'), key('s'), key('u')])
+        .unwrap();
+    insta::assert_snapshot!("first_commit_unstaged", ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('j'), key('s'), key('c'), key('c')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn index_lock_removed_to_stage() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    run(ctx.dir.path(), &["touch", "new-file"]);
+    fs::write(ctx.dir.child(".git/index.lock"), "").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('s')])
+        .unwrap();
+    // The lock's age could be more than 0s by now
+    assert!(ctx
+        .redact_buffer()
+        .contains("Retry (r), remove index.lock (d) or cancel (n)?"));
+
+    state.update(&mut ctx.term, &[key('d')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn commit_rejected_by_hook() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    run(ctx.dir.path(), &["touch", "new-file"]);
+    run(ctx.dir.path(), &["git", "add", "new-file"]);
+    run(ctx.dir.path(), &["git", "config", "core.editor", "true"]);
+    run(
+        ctx.dir.path(),
+        &["git", "config", "core.hooksPath", "hooks"],
+    );
+    fs::create_dir(ctx.dir.child("hooks")).unwrap();
+    let hooks = [
+        ("prepare-commit-msg", "echo first > \"$1\""),
+        ("pre-commit", "echo 'new-file: lint failed' >&2; exit 1"),
+    ];
+    for (name, script) in hooks {
+        let hook = ctx.dir.child("hooks").join(name);
+        fs::write(&hook, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        run(ctx.dir.path(), &["chmod", "+x", hook.to_str().unwrap()]);
+    }
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('c'), key('c')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('y')]).unwrap();
+    insta::assert_snapshot!("commit_rejected_by_hook_no_verify", ctx.redact_buffer());
+}
+
+#[test]
+fn commit_message_linted() {
+    let mut ctx = TestContext::setup_init(100, 20);
+    run(ctx.dir.path(), &["touch", "new-file"]);
+    run(ctx.dir.path(), &["git", "add", "new-file"]);
+    run(ctx.dir.path(), &["git", "config", "core.editor", "true"]);
+    run(
+        ctx.dir.path(),
+        &["git", "config", "core.hooksPath", "hooks"],
+    );
+    fs::create_dir(ctx.dir.child("hooks")).unwrap();
+    let hook = ctx.dir.child("hooks/prepare-commit-msg");
+    fs::write(&hook, "#!/bin/sh\necho 'Add new-file' > \"$1\"\n").unwrap();
+    run(ctx.dir.path(), &["chmod", "+x", hook.to_str().unwrap()]);
+
+    let mut state = ctx.init_state_with_config(|config| {
+        config.general.commit_lint = Some("conventional".to_string());
+    });
+    state.update(&mut ctx.term, &[key('c'), key('c')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('e'), key('y')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn commit_conventional() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    for subject in ["fix(ui): a", "fix(git): b", "feat(ui): c"] {
+        run(
+            ctx.dir.path(),
+            &["git", "commit", "--allow-empty", "-m", subject],
+        );
+    }
+    run(ctx.dir.path(), &["touch", "new-file"]);
+    run(ctx.dir.path(), &["git", "add", "new-file"]);
+    run(ctx.dir.path(), &["git", "config", "core.editor", "true"]);
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[
+                key('c'),
+                key('t'),
+                key('f'),
+                key('e'),
+                key_code(KeyCode::Tab),
+                key_code(KeyCode::Enter),
+            ],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(
+            &mut ctx.term,
+            &[key('u'), key_code(KeyCode::Tab), key_code(KeyCode::Enter)],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn commit_conventional_linted() {
+    let mut ctx = TestContext::setup_init(80, 20);
+    run(ctx.dir.path(), &["touch", "new-file"]);
+    run(ctx.dir.path(), &["git", "add", "new-file"]);
+    run(ctx.dir.path(), &["git", "config", "core.editor", "true"]);
+
+    let mut state = ctx.init_state_with_config(|config| {
+        config.general.commit_lint = Some("conventional".to_string());
+    });
+    state
+        .update(
+            &mut ctx.term,
+            &[
+                key('c'),
+                key('t'),
+                key_code(KeyCode::Enter),
+                key('u'),
+                key('i'),
+                key_code(KeyCode::Enter),
+                key('y'),
+            ],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn log() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "testing\ntesttest\n");
+    run(ctx.dir.path(), &["git", "tag", "-am", ".", "annotated"]);
+    commit(ctx.dir.path(), "secondfile", "testing\ntesttest\n");
+    run(ctx.dir.path(), &["git", "tag", "a-tag"]);
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('l'), key('l')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn session_restored() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "testing\n");
+    commit(ctx.dir.path(), "secondfile", "testing\n");
+    let sessions = temp_dir::TempDir::new().unwrap();
+    let sessions_dir = sessions.path().to_path_buf();
+
+    let mut state = ctx.init_state_with_config(|config| {
+        config.sessions_dir = Some(sessions_dir.clone());
+    });
+    state
+        .update(
+            &mut ctx.term,
+            &[key('l'), key('l'), key('j'), key('q'), key('q')],
+        )
+        .unwrap();
+
+    ctx.init_state_with_config(|config| config.sessions_dir = Some(sessions_dir));
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn log_other() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "this-should-be-at-the-top", "");
+    commit(ctx.dir.path(), "this-should-not-be-visible", "");
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[key('l'), key('l'), key('j'), key('l'), key('o')],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn yank_path() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    fs::write(ctx.dir.child("new-file"), "").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('Y'), key('f')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn ignore_untracked_file() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    fs::write(ctx.dir.child("build.log"), "").unwrap();
+    fs::write(ctx.dir.child("kept"), "").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('i'), key('e')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+    assert_eq!(
+        fs::read_to_string(ctx.dir.child(".gitignore")).unwrap(),
+        "*.log\n"
+    );
+}
+
+#[test]
+fn submodules() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    let remote = ctx.remote_dir.path().to_str().unwrap().to_string();
+    run(
+        ctx.dir.path(),
+        &[
+            "git",
+            "-c",
+            "protocol.file.allow=always",
+            "submodule",
+            "add",
+            &remote,
+            "vendored",
+        ],
+    );
+    run(ctx.dir.path(), &["git", "commit", "-m", "add submodule"]);
+    fs::write(ctx.dir.child("vendored/initial-file"), "changed").unwrap();
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('o'), key('l')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn worktrees() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    let worktree = ctx.remote_dir.path().join("worktree");
+    run(
+        ctx.dir.path(),
+        &[
+            "git",
+            "worktree",
+            "add",
+            "-b",
+            "feature",
+            worktree.to_str().unwrap(),
+        ],
+    );
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('w'), key('l')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_text());
+
+    state
+        .update(
+            &mut ctx.term,
+            &[key('j'), key('j'), key_code(KeyCode::Enter)],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn recent_repos() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    let other = ctx.remote_dir.path().join("other");
+    run(
+        ctx.dir.path(),
+        &[
+            "git",
+            "worktree",
+            "add",
+            "-b",
+            "other",
+            other.to_str().unwrap(),
+        ],
+    );
+    let recent_repos_file = ctx.remote_dir.path().join("recent_repos");
+    fs::write(&recent_repos_file, format!("{}\n", other.display())).unwrap();
+
+    let mut state = ctx.init_state_with_config(|config| {
+        config.recent_repos_file = Some(recent_repos_file.clone());
+    });
+    // Paths of temporary directories vary in length, so only the switch is snapshotted
+    state
+        .update(
+            &mut ctx.term,
+            &[key('R'), key('j'), key('j'), key_code(KeyCode::Enter)],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    let recent = fs::read_to_string(&recent_repos_file).unwrap();
+    assert!(recent.starts_with(other.canonicalize().unwrap().to_str().unwrap()));
+    assert_eq!(recent.lines().count(), 2);
+}
+
+#[test]
+fn bare_repo() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    ctx.init_state_at_path(ctx.remote_dir.path().to_path_buf());
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn bisect() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "first", "");
+    commit(ctx.dir.path(), "second", "");
+    commit(ctx.dir.path(), "third", "");
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[
+                key('B'),
+                key('s'),
+                key_code(KeyCode::Enter),
+                key('H'),
+                key('E'),
+                key('A'),
+                key('D'),
+                key('~'),
+                key('3'),
+                key_code(KeyCode::Enter),
+            ],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn blame() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "initial-file", "hello\nworld\n");
+    fs::write(ctx.dir.child("initial-file"), "hello\nthere\n").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('l'), key('b')])
+        .unwrap();
+
+    // Ages are relative to now, so this isn't snapshotted
+    let buffer = ctx.redact_buffer();
+    assert!(buffer.contains("Blame initial-file"), "{}", buffer);
+    assert!(buffer.contains("_______ Author Name"), "{}", buffer);
+    assert!(buffer.contains("    1 hello"), "{}", buffer);
+    assert!(buffer.contains("    2 world"), "{}", buffer);
+}
+
+#[test]
+fn time_machine() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "file", "one\n");
+    commit(ctx.dir.path(), "other-file", "");
+    commit(ctx.dir.path(), "file", "one\ntwo\n");
+    commit(ctx.dir.path(), "file", "one\n2\n");
+    fs::write(ctx.dir.child("file"), "one\n2\nthree\n").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('l'), key('t')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('['), key('['), key('[')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn clean() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), ".gitignore", "build\n");
+    fs::write(ctx.dir.child("keep"), "").unwrap();
+    fs::write(ctx.dir.child("scratch"), "").unwrap();
+    fs::create_dir(ctx.dir.child("tmp")).unwrap();
+    fs::write(ctx.dir.child("tmp/notes"), "").unwrap();
+    fs::create_dir(ctx.dir.child("build")).unwrap();
+    fs::write(ctx.dir.child("build/out"), "").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[key('x'), key('i'), key('j'), key('j'), key(' '), key(' ')],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('j'), key(' '), key('x'), key('k')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('y')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+    assert!(ctx.dir.child("keep").exists());
+    assert!(!ctx.dir.child("tmp").exists());
+    assert!(!ctx.dir.child("build").exists());
+
+    // What's cleaned can be restored, ignored or not
+    let repo = git2::Repository::open(ctx.dir.path()).unwrap();
+    for path in ["tmp/notes", "build/out"] {
+        assert!(repo
+            .revparse_single(&format!("refs/gitu/snapshots:{}", path))
+            .is_ok());
+    }
+}
+
+#[test]
+fn ignored_files() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), ".gitignore", "*.log\n");
+    fs::write(ctx.dir.child("debug.log"), "").unwrap();
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('i'), key('s')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('j'), key_code(KeyCode::Tab), key('j')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('i'), key('a')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn skip_worktree() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "config", "default\n");
+    fs::write(ctx.dir.child("config"), "mine\n").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('i'), key('w')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('i'), key('w'), key('i'), key('u')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn rename_file() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "old-name", "one\ntwo\nthree\n");
+    fs::write(ctx.dir.child("old-name"), "one\ntwo\nthree\nfour\n").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[key('j'), key('j'), key('m'), key_code(KeyCode::Tab)],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(
+            &mut ctx.term,
+            &iter::repeat_n(key_code(KeyCode::Backspace), "old-name".len())
+                .chain("dir/new-name".chars().map(key))
+                .chain([key_code(KeyCode::Enter)])
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn stage_file_named_like_a_glob() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "a*", "");
+    commit(ctx.dir.path(), "ab", "");
+    fs::write(ctx.dir.child("a*"), "changed\n").unwrap();
+    fs::write(ctx.dir.child("ab"), "changed\n").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('s')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn stage_renamed_file() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "old-name", "one\ntwo\nthree\n");
+    run(ctx.dir.path(), &["git", "mv", "old-name", "new-name"]);
+    fs::write(ctx.dir.child("new-name"), "one\ntwo\nthree\nfour\n").unwrap();
+
+    let mut state = ctx.init_state();
+    // Only the new name is refreshed, the rename has to be found again all the same
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('s')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn log_subcommand() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "");
+    run(ctx.dir.path(), &["git", "checkout", "-b", "other-branch"]);
+    commit(ctx.dir.path(), "secondfile", "");
+    run(ctx.dir.path(), &["git", "checkout", "main"]);
+
+    ctx.init_state_with_args(Args {
+        command: Some(Commands::Log {
+            reference: Some("other-branch".into()),
+        }),
+        ..Default::default()
+    });
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn blame_subcommand() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "initial-file", "hello\nworld\n");
+
+    ctx.init_state_with_args(Args {
+        command: Some(Commands::Blame {
+            path: ctx.dir.child("initial-file"),
+        }),
+        ..Default::default()
+    });
+
+    let buffer = ctx.redact_buffer();
+    assert!(buffer.contains("Blame initial-file"), "{}", buffer);
+    assert!(buffer.contains("    2 world"), "{}", buffer);
+}
+
+#[test]
+fn diff_subcommand() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "first\n");
+    commit(ctx.dir.path(), "secondfile", "second\n");
+    fs::write(ctx.dir.child("secondfile"), "changed\n").unwrap();
+
+    ctx.init_state_with_args(Args {
+        command: Some(Commands::Diff {
+            range: "origin/main..main".into(),
+        }),
+        ..Default::default()
+    });
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    ctx.init_state_with_args(Args {
+        command: Some(Commands::Diff {
+            range: "HEAD".into(),
+        }),
+        ..Default::default()
+    });
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn open_at_revision() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "first\n");
+    run(ctx.dir.path(), &["git", "tag", "v1.0"]);
+    commit(ctx.dir.path(), "secondfile", "second\n");
+
+    let mut state = ctx.init_state_with_args(Args {
+        revision: Some("v1.0".into()),
+        ..Default::default()
+    });
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    // The status is underneath
+    state.update(&mut ctx.term, &[key('q')]).unwrap();
+    assert!(ctx.redact_buffer().contains("Recent commits"));
+}
+
+#[test]
+fn dir_option() {
+    for args in [["gitu", "-C", "some/repo"], ["gitu", "--dir", "some/repo"]] {
+        let args = Args::try_parse_from(args).unwrap();
+        assert_eq!(args.dir, Some("some/repo".into()));
+    }
+}
+
+#[test]
+fn undo() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "first\n");
+    commit(ctx.dir.path(), "secondfile", "second\n");
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('U')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('y')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+    assert!(!ctx.dir.child("secondfile").exists());
+
+    // Undoing again goes further back, rather than redoing
+    state.update(&mut ctx.term, &[key('U'), key('y')]).unwrap();
+    assert!(!ctx.dir.child("firstfile").exists());
+}
+
+#[test]
+fn detached_head() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "first\n");
+    run(ctx.dir.path(), &["git", "tag", "v1.0"]);
+    commit(ctx.dir.path(), "secondfile", "second\n");
+    run(ctx.dir.path(), &["git", "checkout", "--detach", "HEAD~1"]);
+
+    let mut state = ctx.init_state();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('b'), key('-')]).unwrap();
+    assert!(ctx.redact_buffer().contains("On branch main"));
+}
+
+#[test]
+fn show() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "This should be visible\n");
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[key('l'), key('l'), key_code(KeyCode::Enter)],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn show_notes() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "firstfile", "");
+    run(
+        ctx.dir.path(),
+        &["git", "notes", "add", "-m", "Reviewed-by: CI"],
+    );
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[key('l'), key('l'), key_code(KeyCode::Enter)],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state.update(&mut ctx.term, &[key('T'), key('k')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn am_conflict() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "initial-file", "from patch");
+    run(ctx.dir.path(), &["git", "format-patch", "-1", "-o", "."]);
+    run(ctx.dir.path(), &["git", "reset", "--hard", "HEAD~1"]);
+    commit(ctx.dir.path(), "initial-file", "conflicting");
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[
+                key('j'),
+                key('j'),
+                key('W'),
+                key('w'),
+                key_code(KeyCode::Enter),
+            ],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn tags() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    run(
+        ctx.dir.path(),
+        &["git", "tag", "-am", "First release", "v0.1"],
+    );
+    commit(ctx.dir.path(), "second", "");
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[
+                key('t'),
+                key('t'),
+                key('v'),
+                key('0'),
+                key('.'),
+                key('2'),
+                key_code(KeyCode::Enter),
+                key('t'),
+                key('l'),
+            ],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn signed_tags() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    let keys = ctx.remote_dir.path();
+    for name in ["trusted", "unknown"] {
+        run(
+            keys,
+            &["ssh-keygen", "-t", "ed25519", "-N", "", "-q", "-f", name],
+        );
+    }
+    let trusted = fs::read_to_string(keys.join("trusted.pub")).unwrap();
+    fs::write(
+        keys.join("allowed_signers"),
+        format!("ci@example.com {}", trusted),
+    )
+    .unwrap();
+
+    let signing_key = |name: &str| keys.join(name).to_str().unwrap().to_string();
+    run(ctx.dir.path(), &["git", "config", "gpg.format", "ssh"]);
+    run(
+        ctx.dir.path(),
+        &[
+            "git",
+            "config",
+            "gpg.ssh.allowedSignersFile",
+            &signing_key("allowed_signers"),
+        ],
+    );
+    run(ctx.dir.path(), &["git", "tag", "-am", "Unsigned", "v1"]);
+    for (name, key) in [("v2", "trusted"), ("v3", "unknown")] {
+        run(
+            ctx.dir.path(),
+            &[
+                "git",
+                "-c",
+                &format!("user.signingkey={}", signing_key(key)),
+                "tag",
+                "-sm",
+                "Release",
+                name,
+            ],
+        );
+    }
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('t'), key('l')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(
+            &mut ctx.term,
+            &[key('j'), key('j'), key_code(KeyCode::Enter)],
+        )
         .unwrap();
-
     insta::assert_snapshot!(ctx.redact_buffer());
 }
 
 #[test]
-fn staged_file() {
-    let mut ctx = TestContext::setup_init(80, 20);
-    run(ctx.dir.path(), &["touch", "new-file"]);
-    run(ctx.dir.path(), &["git", "add", "new-file"]);
+fn rebase_conflict() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "new-file", "hello");
+
+    run(ctx.dir.path(), &["git", "checkout", "-b", "other-branch"]);
+    commit(ctx.dir.path(), "new-file", "hey");
+
+    run(ctx.dir.path(), &["git", "checkout", "main"]);
+    commit(ctx.dir.path(), "new-file", "hi");
+
+    run(ctx.dir.path(), &["git", "checkout", "other-branch"]);
+    run(ctx.dir.path(), &["git", "rebase", "main"]);
 
     ctx.init_state();
     insta::assert_snapshot!(ctx.redact_buffer());
 }
 
 #[test]
-fn log() {
+fn cherry_pick_conflict() {
     let mut ctx = TestContext::setup_clone(80, 20);
-    commit(ctx.dir.path(), "firstfile", "testing\ntesttest\n");
-    run(ctx.dir.path(), &["git", "tag", "-am", ".", "annotated"]);
-    commit(ctx.dir.path(), "secondfile", "testing\ntesttest\n");
-    run(ctx.dir.path(), &["git", "tag", "a-tag"]);
+    commit(ctx.dir.path(), "new-file", "hello");
 
-    let mut state = ctx.init_state();
-    state.update(&mut ctx.term, &[key('l'), key('l')]).unwrap();
-    insta::assert_snapshot!(ctx.redact_buffer());
-}
+    run(ctx.dir.path(), &["git", "checkout", "-b", "other-branch"]);
+    commit(ctx.dir.path(), "new-file", "hey");
 
-#[test]
-fn log_other() {
-    let mut ctx = TestContext::setup_clone(80, 20);
-    commit(ctx.dir.path(), "this-should-be-at-the-top", "");
-    commit(ctx.dir.path(), "this-should-not-be-visible", "");
+    run(ctx.dir.path(), &["git", "checkout", "main"]);
+    commit(ctx.dir.path(), "new-file", "hi");
+    run(ctx.dir.path(), &["git", "checkout", "other-branch"]);
 
     let mut state = ctx.init_state();
     state
         .update(
             &mut ctx.term,
-            &[key('l'), key('l'), key('j'), key('l'), key('o')],
+            &[key('y'), key('j'), key('l'), key('o'), key('A'), key('x')],
         )
         .unwrap();
     insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('A'), key('q'), key('g'), key('g')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
 }
 
 #[test]
-fn show() {
+fn revert_conflict() {
     let mut ctx = TestContext::setup_clone(80, 20);
-    commit(ctx.dir.path(), "firstfile", "This should be visible\n");
+    commit(ctx.dir.path(), "new-file", "hello");
+    commit(ctx.dir.path(), "new-file", "hi");
 
     let mut state = ctx.init_state();
     state
         .update(
             &mut ctx.term,
-            &[key('l'), key('l'), key_code(KeyCode::Enter)],
+            &[key('j'), key('j'), key('j'), key('V'), key('n')],
         )
         .unwrap();
     insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('V'), key('g'), key('g')])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
 }
 
 #[test]
-fn rebase_conflict() {
+fn git_command() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+
+    let mut state = ctx.init_state();
+    let mut keys = vec![key('!')];
+    keys.extend("git branch 'a-branch'".chars().map(key));
+    keys.push(key_code(KeyCode::Enter));
+    keys.push(key('!'));
+    keys.extend("branch --list a-*".chars().map(key));
+    keys.push(key_code(KeyCode::Enter));
+    state.update(&mut ctx.term, &keys).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn merge_conflict() {
     let mut ctx = TestContext::setup_clone(80, 20);
     commit(ctx.dir.path(), "new-file", "hello");
 
@@ -118,27 +1094,28 @@ fn rebase_conflict() {
     run(ctx.dir.path(), &["git", "checkout", "main"]);
     commit(ctx.dir.path(), "new-file", "hi");
 
-    run(ctx.dir.path(), &["git", "checkout", "other-branch"]);
-    run(ctx.dir.path(), &["git", "rebase", "main"]);
+    run(ctx.dir.path(), &["git", "merge", "other-branch"]);
 
     ctx.init_state();
     insta::assert_snapshot!(ctx.redact_buffer());
 }
 
 #[test]
-fn merge_conflict() {
+fn merge_conflict_in_worktree() {
     let mut ctx = TestContext::setup_clone(80, 20);
     commit(ctx.dir.path(), "new-file", "hello");
+    run(ctx.dir.path(), &["git", "branch", "other-branch"]);
+    run(
+        ctx.dir.path(),
+        &["git", "worktree", "add", "a-worktree", "other-branch"],
+    );
 
-    run(ctx.dir.path(), &["git", "checkout", "-b", "other-branch"]);
-    commit(ctx.dir.path(), "new-file", "hey");
-
-    run(ctx.dir.path(), &["git", "checkout", "main"]);
+    let worktree = ctx.dir.child("a-worktree");
+    commit(&worktree, "new-file", "hey");
     commit(ctx.dir.path(), "new-file", "hi");
+    run(&worktree, &["git", "merge", "main"]);
 
-    run(ctx.dir.path(), &["git", "merge", "other-branch"]);
-
-    ctx.init_state();
+    ctx.init_state_at_path(worktree);
     insta::assert_snapshot!(ctx.redact_buffer());
 }
 
@@ -160,7 +1137,7 @@ fn hide_untracked() {
     let mut state = ctx.init_state();
     let mut config = state.repo.config().unwrap();
     config.set_str("status.showUntrackedFiles", "off").unwrap();
-    state.update(&mut ctx.term, &[key('g')]).unwrap();
+    state.update(&mut ctx.term, &[key('g'), key('g')]).unwrap();
     insta::assert_snapshot!(ctx.redact_buffer());
 }
 
@@ -301,6 +1278,58 @@ mod discard {
         insta::assert_snapshot!(ctx.redact_buffer());
     }
 
+    #[test]
+    pub(crate) fn discarded_file_can_be_restored() {
+        let mut ctx = TestContext::setup_clone(80, 10);
+        fs::write(ctx.dir.child("some-file"), "precious\n").unwrap();
+        let mut state = ctx.init_state();
+
+        state
+            .update(
+                &mut ctx.term,
+                &[key('j'), key('j'), key('K'), key('y'), key('Z')],
+            )
+            .unwrap();
+        assert!(!ctx.dir.child("some-file").exists());
+        assert!(ctx.redact_buffer().contains("before discarding some-file"));
+
+        // Snapshots are dated, so the screen isn't snapshotted
+        state.update(&mut ctx.term, &[key('j'), key('a')]).unwrap();
+        assert_eq!(
+            fs::read_to_string(ctx.dir.child("some-file")).unwrap(),
+            "precious\n"
+        );
+    }
+
+    #[test]
+    pub(crate) fn discarded_file_goes_to_the_trash() {
+        let mut ctx = TestContext::setup_clone(80, 10);
+        fs::write(ctx.dir.child("some-file"), "precious\n").unwrap();
+        let mut state = ctx.init_state_with_config(|config| {
+            config.general.snapshots = false;
+            config.general.trash.enabled = true;
+        });
+
+        state
+            .update(
+                &mut ctx.term,
+                &[key('j'), key('j'), key('K'), key('y'), key('D')],
+            )
+            .unwrap();
+        assert!(!ctx.dir.child("some-file").exists());
+        // Trashed files are dated, so the screen isn't snapshotted
+        let screen = ctx.redact_buffer();
+        assert!(screen.contains("Trash (1)"));
+        assert!(screen.contains("some-file"));
+
+        state.update(&mut ctx.term, &[key('j'), key('a')]).unwrap();
+        assert_eq!(
+            fs::read_to_string(ctx.dir.child("some-file")).unwrap(),
+            "precious\n"
+        );
+        assert!(ctx.redact_buffer().contains("Trash (0)"));
+    }
+
     // FIXME Deleting branches doesn't work with the test-setup
     // #[test]
     // fn discard_branch() {
@@ -316,7 +1345,10 @@ mod discard {
 mod reset {
     use crate::helpers::commit;
     use crate::helpers::key;
+    use crate::helpers::key_code;
     use crate::helpers::TestContext;
+    use crossterm::event::KeyCode;
+    use std::fs;
 
     #[test]
     pub(crate) fn reset_menu() {
@@ -369,10 +1401,54 @@ mod reset {
         state
             .update(
                 &mut ctx.term,
-                &[key('l'), key('l'), key('j'), key('X'), key('h'), key('q')],
+                &[
+                    key('l'),
+                    key('l'),
+                    key('j'),
+                    key('X'),
+                    key('h'),
+                    key('y'),
+                    key('q'),
+                ],
+            )
+            .unwrap();
+        insta::assert_snapshot!(ctx.redact_buffer());
+    }
+
+    #[test]
+    fn reset_hard_confirm_prompt() {
+        let mut ctx = TestContext::setup_clone(80, 10);
+        commit(ctx.dir.path(), "unwanted-file", "");
+
+        let mut state = ctx.init_state();
+        state
+            .update(
+                &mut ctx.term,
+                &[key('l'), key('l'), key('j'), key('X'), key('h')],
+            )
+            .unwrap();
+        insta::assert_snapshot!(ctx.redact_buffer());
+    }
+
+    #[test]
+    fn reset_keep_to_prompted_revision() {
+        let mut ctx = TestContext::setup_clone(80, 10);
+        commit(ctx.dir.path(), "unwanted-file", "");
+        fs::write(ctx.dir.child("initial-file"), "uncommitted\n").unwrap();
+
+        let mut state = ctx.init_state();
+        state.update(&mut ctx.term, &[key('X'), key('k')]).unwrap();
+        state
+            .update(
+                &mut ctx.term,
+                &"HEAD~1".chars().map(key).collect::<Vec<_>>(),
             )
             .unwrap();
+        state
+            .update(&mut ctx.term, &[key_code(KeyCode::Enter)])
+            .unwrap();
         insta::assert_snapshot!(ctx.redact_buffer());
+        assert!(!ctx.dir.child("unwanted-file").exists());
     }
 }
 
@@ -393,6 +1469,7 @@ mod checkout {
     use crate::helpers::run;
     use crate::helpers::TestContext;
     use crossterm::event::KeyCode;
+    use std::fs;
 
     #[test]
     pub(crate) fn checkout_menu() {
@@ -452,6 +1529,29 @@ mod checkout {
         insta::assert_snapshot!(ctx.redact_buffer());
     }
 
+    #[test]
+    pub(crate) fn checkout_over_changes_snapshots_them() {
+        let mut ctx = TestContext::setup_clone(80, 10);
+        run(ctx.dir.path(), &["git", "branch", "other-branch"]);
+        fs::write(ctx.dir.child("some-file"), "precious\n").unwrap();
+
+        let mut state = ctx.init_state();
+        state
+            .update(
+                &mut ctx.term,
+                &[key('b'), key('b')]
+                    .into_iter()
+                    .chain("other-branch".chars().map(key))
+                    .chain([key_code(KeyCode::Enter), key('Z')])
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap();
+        // Snapshots are dated, so the screen isn't snapshotted
+        assert!(ctx
+            .redact_buffer()
+            .contains("before checking out other-branch"));
+    }
+
     #[test]
     pub(crate) fn checkout_new_branch() {
         let mut ctx = TestContext::setup_clone(80, 10);
@@ -489,7 +1589,21 @@ fn updated_externally() {
 
     fs::write(ctx.dir.child("a"), "test").unwrap();
 
-    state.update(&mut ctx.term, &[key('g')]).unwrap();
+    state.update(&mut ctx.term, &[key('g'), key('g')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn unstage_one_of_two_files() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    fs::write(ctx.dir.child("file-one"), "one\n").unwrap();
+    fs::write(ctx.dir.child("file-two"), "two\n").unwrap();
+    run(ctx.dir.path(), &["git", "add", "."]);
+
+    let mut state = ctx.init_state();
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key('u')])
+        .unwrap();
     insta::assert_snapshot!(ctx.redact_buffer());
 }
 
@@ -571,3 +1685,140 @@ fn inside_submodule() {
     let _state = ctx.init_state_at_path(ctx.dir.child("test-submodule"));
     insta::assert_snapshot!(ctx.redact_buffer());
 }
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_path_and_content() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt, process::Command};
+
+    let mut ctx = TestContext::setup_clone(80, 20);
+    commit(ctx.dir.path(), "latin1", "caf\n");
+    fs::write(ctx.dir.child("latin1"), b"caf\xe9\n").unwrap();
+    fs::write(ctx.dir.path().join(OsStr::from_bytes(b"caf\xe9")), "new\n").unwrap();
+
+    let mut state = ctx.init_state();
+    state
+        .update(
+            &mut ctx.term,
+            &[
+                key('j'),
+                key('j'),
+                key('j'),
+                key('j'),
+                key_code(KeyCode::Tab),
+            ],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    // The hunk is staged as it is, not as it's shown
+    state.update(&mut ctx.term, &[key('j'), key('s')]).unwrap();
+    let staged = Command::new("git")
+        .args(["diff", "--cached"])
+        .current_dir(ctx.dir.path())
+        .output()
+        .unwrap()
+        .stdout;
+    assert!(staged.windows(5).any(|bytes| bytes == b"+caf\xe9"));
+
+    state
+        .update(&mut ctx.term, &[key('k'), key('k'), key('k'), key('s')])
+        .unwrap();
+    insta::assert_snapshot!("non_utf8_path_and_content_staged", ctx.redact_buffer());
+}
+
+#[test]
+fn status_tree() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    fs::create_dir_all(ctx.dir.child("src/git")).unwrap();
+    commit(ctx.dir.path(), "src/git/mod.rs", "");
+    commit(ctx.dir.path(), "src/main.rs", "");
+    fs::write(ctx.dir.child("src/git/mod.rs"), "changed\n").unwrap();
+    fs::write(ctx.dir.child("src/main.rs"), "changed\n").unwrap();
+    fs::create_dir_all(ctx.dir.child("docs/api")).unwrap();
+    fs::write(ctx.dir.child("docs/api/index.md"), "").unwrap();
+    fs::write(ctx.dir.child("docs/api/types.md"), "").unwrap();
+    fs::write(ctx.dir.child("src/git/log.rs"), "").unwrap();
+    fs::write(ctx.dir.child("notes"), "").unwrap();
+
+    let mut state = ctx.init_state_with_config(|config| config.general.status_tree = true);
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('j'), key('j'), key_code(KeyCode::Tab)])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[cfg(unix)]
+#[test]
+fn status_tree_of_directories_not_utf8() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let mut ctx = TestContext::setup_clone(80, 20);
+    for dir in [b"caf\xe8", b"caf\xe9"] {
+        let dir = ctx.dir.path().join(OsStr::from_bytes(dir));
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("menu"), "").unwrap();
+    }
+    run(ctx.dir.path(), &["git", "add", "."]);
+
+    ctx.init_state_with_config(|config| config.general.status_tree = true);
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn compare_with_upstream() {
+    let mut ctx = TestContext::setup_clone(80, 20);
+    clone_and_commit(&ctx.remote_dir, "remote-file", "hello");
+    run(ctx.dir.path(), &["git", "fetch"]);
+    commit(ctx.dir.path(), "local-file", "one\n");
+    fs::write(ctx.dir.child("local-file"), "one\ntwo\n").unwrap();
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('d'), key('w')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key_code(KeyCode::Enter)])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(
+            &mut ctx.term,
+            &[key('q'), key('d'), key('h'), key_code(KeyCode::Enter)],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}
+
+#[test]
+fn insights() {
+    let mut ctx = TestContext::setup_clone(80, 30);
+    commit(ctx.dir.path(), "big-file", "one\ntwo\nthree\n");
+    commit(ctx.dir.path(), "small-file", "one\n");
+
+    let mut state = ctx.init_state();
+    state.update(&mut ctx.term, &[key('l'), key('i')]).unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(&mut ctx.term, &[key('j'), key_code(KeyCode::Enter)])
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+
+    state
+        .update(
+            &mut ctx.term,
+            &[
+                key('q'),
+                key('j'),
+                key('j'),
+                key('j'),
+                key_code(KeyCode::Enter),
+            ],
+        )
+        .unwrap();
+    insta::assert_snapshot!(ctx.redact_buffer());
+}